@@ -0,0 +1,117 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use chrono::Utc;
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use nano_ledger::Repositories;
+use nano_ledger::accounts::{Account, AccountsRepository};
+use nano_ledger::journal::{JournalEntry, JournalRepository, PreparedEntry};
+use nano_ledger::ledgers::DEFAULT_LEDGER_ID;
+use nano_ledger::transactions::{CreateNewTransaction, MovementType, post_transaction};
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+const POSTINGS_PER_THREAD: u64 = 1_000;
+
+fn disjoint_pairs(threads: usize) -> (Repositories, Vec<(Uuid, Uuid)>) {
+    let mut accounts = Vec::new();
+    let mut pairs = Vec::new();
+
+    for pair in 0..threads {
+        let source = Account::new(&format!("source.{pair}"), i64::MAX / 2);
+        let target = Account::new(&format!("target.{pair}"), 0);
+        pairs.push((source.account_id, target.account_id));
+        accounts.extend([source, target]);
+    }
+
+    let repos = Repositories {
+        accounts: AccountsRepository::from(accounts),
+        ..Repositories::default()
+    };
+
+    (repos, pairs)
+}
+
+fn post_on_disjoint_pairs(repos: &Repositories, pairs: &[(Uuid, Uuid)], postings: u64) -> Duration {
+    let started = Instant::now();
+
+    thread::scope(|scope| {
+        for &(source, target) in pairs {
+            scope.spawn(move || {
+                for _ in 0..postings {
+                    let payload = CreateNewTransaction::new_debit(source, target, "benchmark", 1);
+                    post_transaction(repos, DEFAULT_LEDGER_ID, payload).expect("posting failed");
+                }
+            });
+        }
+    });
+
+    started.elapsed()
+}
+
+fn postings_scaling(c: &mut Criterion) {
+    let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+    let mut group = c.benchmark_group("postings_on_disjoint_pairs");
+
+    for threads in [1, 2, 4, 8, 16].into_iter().filter(|threads| *threads <= cores) {
+        group.throughput(Throughput::Elements(threads as u64 * POSTINGS_PER_THREAD));
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |bencher, &threads| {
+            bencher.iter_custom(|iterations| {
+                let (repos, pairs) = disjoint_pairs(threads);
+                post_on_disjoint_pairs(&repos, &pairs, iterations * POSTINGS_PER_THREAD)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn entries(count: usize) -> Vec<JournalEntry> {
+    (0..count)
+        .map(|_| JournalEntry {
+            created_at: Utc::now(),
+            entry_id: Uuid::new_v4(),
+            transaction_id: Uuid::new_v4(),
+            ledger_id: DEFAULT_LEDGER_ID,
+            account_id: Uuid::new_v4(),
+            movement_type: MovementType::Debit,
+            amount_in_cents: 1,
+            previous_hash: String::new(),
+            hash: String::new(),
+        })
+        .collect()
+}
+
+// What postings spend appending to the journal, while holding the locks every posting shares
+fn journal_appends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("journal_appends");
+    group.throughput(Throughput::Elements(POSTINGS_PER_THREAD * 2));
+
+    group.bench_function(BenchmarkId::from_parameter("prepared"), |bencher| {
+        bencher.iter_batched(
+            || {
+                let prepared: Vec<PreparedEntry> = entries(POSTINGS_PER_THREAD as usize * 2)
+                    .into_iter()
+                    .map(PreparedEntry::from)
+                    .collect();
+                (JournalRepository::default(), prepared)
+            },
+            |(mut journal, prepared)| journal.append_prepared(prepared),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function(BenchmarkId::from_parameter("serialized_while_appending"), |bencher| {
+        bencher.iter_batched(
+            || (JournalRepository::default(), entries(POSTINGS_PER_THREAD as usize * 2)),
+            |(mut journal, entries)| journal.save_entries(entries),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, postings_scaling, journal_appends);
+criterion_main!(benches);
@@ -0,0 +1,187 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+// Compares indexed lookups with the linear scans they replaced.
+// Sizes default to 1M accounts and 10M journal entries, and can be lowered with
+// BENCH_ACCOUNTS and BENCH_JOURNAL_ENTRIES.
+
+use chrono::{DateTime, Duration, TimeDelta, Utc};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use nano_ledger::accounts::{Account, AccountsRepository};
+use nano_ledger::journal::{JournalEntry, JournalFilter, JournalRepository};
+use nano_ledger::ledgers::DEFAULT_LEDGER_ID;
+use nano_ledger::transactions::{MovementType, Transaction, TransactionsRepository};
+use std::hint::black_box;
+use uuid::Uuid;
+
+fn size_from_env(variable: &str, default: usize) -> usize {
+    std::env::var(variable)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn accounts() -> Vec<Account> {
+    (0..size_from_env("BENCH_ACCOUNTS", 1_000_000))
+        .map(|index| Account::new(&format!("account.{index}"), 100))
+        .collect()
+}
+
+fn transaction(index: usize, account_ids: &[Uuid], epoch: DateTime<Utc>) -> Transaction {
+    Transaction {
+        created_at: epoch + TimeDelta::milliseconds(index as i64),
+        transaction_id: Uuid::new_v4(),
+        ledger_id: DEFAULT_LEDGER_ID,
+        movement_type: MovementType::Debit,
+        lhs_account_id: account_ids[index % account_ids.len()],
+        rhs_account_id: account_ids[(index + 1) % account_ids.len()],
+        description: String::new(),
+        amount_in_cents: 1,
+        reverses_transaction_id: None,
+    }
+}
+
+fn entries_for(transaction: &Transaction) -> Vec<JournalEntry> {
+    let entry = |account_id, movement_type| JournalEntry {
+        created_at: transaction.created_at,
+        entry_id: Uuid::new_v4(),
+        transaction_id: transaction.transaction_id,
+        ledger_id: transaction.ledger_id,
+        account_id,
+        movement_type,
+        amount_in_cents: transaction.amount_in_cents,
+        previous_hash: String::new(),
+        hash: String::new(),
+    };
+
+    vec![
+        entry(transaction.lhs_account_id, MovementType::Debit),
+        entry(transaction.rhs_account_id, MovementType::Credit),
+    ]
+}
+
+fn accounts_lookups(c: &mut Criterion) {
+    let accounts = accounts();
+    let target = accounts[accounts.len() / 2].clone();
+    let repository = AccountsRepository::from(accounts.clone());
+
+    let mut group = c.benchmark_group("accounts");
+    group.sample_size(10);
+
+    group.bench_function(BenchmarkId::new("fetch_by_id", "indexed"), |bencher| {
+        bencher.iter(|| repository.fetch_by_id(black_box(&target.account_id)))
+    });
+
+    group.bench_function(BenchmarkId::new("fetch_by_id", "linear"), |bencher| {
+        bencher.iter(|| {
+            accounts
+                .iter()
+                .find(|account| account.account_id == *black_box(&target.account_id))
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("fetch_by_alias", "indexed"), |bencher| {
+        bencher.iter(|| repository.fetch_by_alias(&DEFAULT_LEDGER_ID, black_box(&target.alias)))
+    });
+
+    group.bench_function(BenchmarkId::new("fetch_by_alias", "linear"), |bencher| {
+        bencher.iter(|| {
+            accounts
+                .iter()
+                .find(|account| account.ledger_id == DEFAULT_LEDGER_ID && account.alias == *black_box(&target.alias))
+        })
+    });
+
+    group.finish();
+}
+
+fn journal_lookups(c: &mut Criterion) {
+    let account_ids: Vec<Uuid> = accounts().iter().map(|account| account.account_id).collect();
+    let transactions_count = size_from_env("BENCH_JOURNAL_ENTRIES", 10_000_000) / 2;
+    let epoch = Utc::now();
+
+    let mut transactions = TransactionsRepository::default();
+    let mut journal = JournalRepository::default();
+
+    for index in 0..transactions_count {
+        let transaction = transaction(index, &account_ids, epoch);
+        journal.save_entries(entries_for(&transaction));
+        transactions.save_transaction(transaction);
+    }
+
+    let target = transactions.iter().nth(transactions_count / 2).cloned().unwrap();
+    let window_start = target.created_at;
+    let window_end = window_start + Duration::seconds(1);
+
+    let mut group = c.benchmark_group("journal");
+    group.sample_size(10);
+
+    group.bench_function(BenchmarkId::new("fetch_transaction", "indexed"), |bencher| {
+        bencher.iter(|| transactions.fetch_transaction(black_box(&target.transaction_id)))
+    });
+
+    group.bench_function(BenchmarkId::new("fetch_transaction", "linear"), |bencher| {
+        bencher.iter(|| {
+            transactions
+                .iter()
+                .find(|transaction| transaction.transaction_id == *black_box(&target.transaction_id))
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("fetch_by_transaction", "indexed"), |bencher| {
+        bencher.iter(|| journal.fetch_by_transaction(black_box(&target.transaction_id)))
+    });
+
+    group.bench_function(BenchmarkId::new("fetch_by_transaction", "linear"), |bencher| {
+        bencher.iter(|| {
+            journal
+                .iter()
+                .filter(|entry| entry.transaction_id == *black_box(&target.transaction_id))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("fetch_by_account", "indexed"), |bencher| {
+        bencher.iter(|| journal.fetch_by_account(black_box(&target.lhs_account_id)).count())
+    });
+
+    group.bench_function(BenchmarkId::new("fetch_by_account", "linear"), |bencher| {
+        bencher.iter(|| {
+            journal
+                .iter()
+                .filter(|entry| entry.account_id == *black_box(&target.lhs_account_id))
+                .count()
+        })
+    });
+
+    let window = JournalFilter {
+        ledger_id: DEFAULT_LEDGER_ID,
+        from: Some(window_start),
+        until: Some(window_end),
+        ..JournalFilter::default()
+    };
+
+    group.bench_function(BenchmarkId::new("entries_within", "indexed"), |bencher| {
+        bencher.iter(|| {
+            let (entries, _) = journal.entries_within(black_box(&window), 0..usize::MAX, usize::MAX, |_| true);
+            entries.len()
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("entries_within", "linear"), |bencher| {
+        bencher.iter(|| {
+            journal
+                .iter()
+                .filter(|entry| entry.created_at >= black_box(window_start) && entry.created_at < window_end)
+                .cloned()
+                .collect::<Vec<_>>()
+                .len()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, accounts_lookups, journal_lookups);
+criterion_main!(benches);
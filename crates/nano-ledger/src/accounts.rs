@@ -2,103 +2,394 @@
 // SPDX-License-Identifier: MIT
 
 use crate::SharedState;
+use crate::auth::Principal;
+use crate::errors::ApiError;
+use crate::events::EventPayload;
+use crate::ledgers::{DEFAULT_LEDGER_ID, LedgerScope};
+use crate::metrics::{LockMode, METRICS};
 use axum::extract::{Path, State};
-use axum::http::StatusCode;
-use axum::{Error, Json};
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use axum::{Error, Extension, Json};
+use parking_lot::{ArcRwLockWriteGuard, RawRwLock, RwLock};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+pub const DEFAULT_CURRENCY: &str = "EUR";
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountType {
+    #[default]
+    Asset,
+    Liability,
+    Equity,
+    Revenue,
+    Expense,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct CreateNewAccount {
     pub alias: String,
     pub balance: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_type: Option<AccountType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Uuid>,
 }
 
-#[derive(Debug, Clone, Default, Serialize)]
+fn default_currency() -> String {
+    DEFAULT_CURRENCY.to_string()
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Account {
     pub account_id: Uuid,
+    pub ledger_id: Uuid,
     pub alias: String,
-    pub balance: u64,
+    #[serde(default)]
+    pub account_type: AccountType,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Uuid>,
+    // Only equity accounts holding opening balances go below zero
+    pub balance: i64,
+    pub version: u64,
 }
 
 impl Account {
-    #[cfg(test)]
-    pub fn new(alias: &str, balance: u64) -> Self {
+    pub fn new(alias: &str, balance: i64) -> Self {
         Account {
             account_id: Uuid::new_v4(),
+            ledger_id: DEFAULT_LEDGER_ID,
             alias: alias.to_string(),
+            account_type: AccountType::Asset,
+            currency: default_currency(),
+            parent_id: None,
             balance,
+            version: 1,
         }
     }
 
-    pub fn add_balance(&self, amount: u64) {
-        self.balance.checked_add(amount).unwrap();
+    pub fn within(self, ledger_id: Uuid) -> Self {
+        Account { ledger_id, ..self }
+    }
+
+    pub fn add_balance(&mut self, amount: u64) {
+        self.balance = self.balance.checked_add_unsigned(amount).unwrap();
+        self.version += 1;
+    }
+
+    pub fn subtract_balance(&mut self, amount: u64) {
+        self.balance = self.balance.checked_sub_unsigned(amount).unwrap();
+        self.version += 1;
     }
 
-    pub fn subtract_balance(&self, amount: u64) {
-        self.balance.checked_sub(amount).unwrap();
+    pub fn etag(&self) -> String {
+        format!("\"{}:{}\"", self.account_id, self.version)
     }
 }
 
+// Currencies are three-letter codes, as in ISO 4217, normalized to uppercase
+pub fn parse_currency(code: &str) -> Option<String> {
+    let code = code.trim();
+    let is_valid = code.len() == 3 && code.chars().all(|character| character.is_ascii_alphabetic());
+    is_valid.then(|| code.to_ascii_uppercase())
+}
+
+// Parses a strong entity tag in the `"<account_id>:<version>"` form returned by `Account::etag`
+pub fn parse_etag(etag: &str) -> Option<(Uuid, u64)> {
+    let (account_id, version) = etag.trim().strip_prefix('"')?.strip_suffix('"')?.split_once(':')?;
+    Some((Uuid::parse_str(account_id).ok()?, version.parse().ok()?))
+}
+
+#[derive(Clone)]
+struct AccountSlot {
+    ledger_id: Uuid,
+    account: Arc<RwLock<Account>>,
+}
+
+#[derive(Default)]
+struct AccountsIndex {
+    by_id: HashMap<Uuid, AccountSlot>,
+    by_alias: HashMap<(Uuid, String), Uuid>,
+}
+
+// The index lock is only held to find or register accounts, never while an account is locked
 #[derive(Default)]
 pub struct AccountsRepository {
-    pub accounts: Vec<Account>,
+    index: RwLock<AccountsIndex>,
+}
+
+impl From<Vec<Account>> for AccountsRepository {
+    fn from(accounts: Vec<Account>) -> Self {
+        let repository = AccountsRepository::default();
+
+        for account in accounts {
+            repository.save_account(account).expect("Duplicated account alias");
+        }
+
+        repository
+    }
 }
 
 impl AccountsRepository {
-    pub fn save_account(&mut self, account: Account) -> Result<(), Error> {
-        let None = self.fetch_by_alias(&account.alias) else {
-            tracing::debug!("Alias already taken | alias = {:?}", &account.alias);
+    pub fn save_account(&self, account: Account) -> Result<(), Error> {
+        let mut index = self.index.write();
+        let alias_key = (account.ledger_id, account.alias.clone());
+
+        if index.by_alias.contains_key(&alias_key) {
+            tracing::debug!(alias = %account.alias, "Alias already taken");
             return Err(Error::new("Alias already taken by another account"));
+        }
+
+        let slot = AccountSlot {
+            ledger_id: account.ledger_id,
+            account: Arc::new(RwLock::new(account.clone())),
         };
 
-        self.accounts.push(account);
+        index.by_alias.insert(alias_key, account.account_id);
+        index.by_id.insert(account.account_id, slot);
+        Ok(())
+    }
+
+    // Registers all accounts or none, when one of the aliases is already taken
+    pub fn save_accounts(&self, accounts: Vec<Account>) -> Result<(), Error> {
+        let mut index = self.index.write();
+        let mut aliases = HashSet::new();
+
+        for account in &accounts {
+            let alias_key = (account.ledger_id, account.alias.clone());
+
+            if index.by_alias.contains_key(&alias_key) || !aliases.insert(alias_key) {
+                tracing::debug!(alias = %account.alias, "Alias already taken");
+                return Err(Error::new(format!(
+                    "Alias {} already taken by another account",
+                    account.alias
+                )));
+            }
+        }
+
+        for account in accounts {
+            index
+                .by_alias
+                .insert((account.ledger_id, account.alias.clone()), account.account_id);
+            index.by_id.insert(
+                account.account_id,
+                AccountSlot {
+                    ledger_id: account.ledger_id,
+                    account: Arc::new(RwLock::new(account)),
+                },
+            );
+        }
+
         Ok(())
     }
 
-    pub fn fetch_by_id(&self, account_id: &Uuid) -> Option<&Account> {
-        self.accounts.iter().find(|&a| a.account_id == *account_id)
+    // Only undoes `save_accounts` for accounts nobody was told about yet
+    pub fn remove_accounts(&self, account_ids: &[Uuid]) {
+        let mut index = self.index.write();
+
+        for account_id in account_ids {
+            if let Some(slot) = index.by_id.remove(account_id) {
+                let alias = slot.account.read().alias.clone();
+                index.by_alias.remove(&(slot.ledger_id, alias));
+            }
+        }
+    }
+
+    fn slot(&self, account_id: &Uuid) -> Option<AccountSlot> {
+        self.index.read().by_id.get(account_id).cloned()
     }
 
-    pub fn fetch_by_alias(&self, alias: &str) -> Option<&Account> {
-        self.accounts.iter().find(|&a| a.alias == alias)
+    pub fn fetch_by_id(&self, account_id: &Uuid) -> Option<Account> {
+        self.read_with(account_id, Account::clone)
     }
+
+    // Postings on the account wait until `read` returns
+    pub fn read_with<R>(&self, account_id: &Uuid, read: impl FnOnce(&Account) -> R) -> Option<R> {
+        let slot = self.slot(account_id)?;
+        let account = crate::read_lock(&slot.account);
+        Some(read(&account))
+    }
+
+    pub fn ledger_of(&self, account_id: &Uuid) -> Option<Uuid> {
+        self.index.read().by_id.get(account_id).map(|slot| slot.ledger_id)
+    }
+
+    pub fn count(&self) -> usize {
+        self.index.read().by_id.len()
+    }
+
+    pub fn fetch_by_alias(&self, ledger_id: &Uuid, alias: &str) -> Option<Account> {
+        let account_id = *self.index.read().by_alias.get(&(*ledger_id, alias.to_string()))?;
+        self.fetch_by_id(&account_id)
+    }
+
+    // Reads every account of a ledger at the same instant, sorted by id. Accounts are
+    // locked in id order as postings do, so no transfer is seen half-way.
+    pub fn snapshot(&self, ledger_id: &Uuid) -> Vec<Account> {
+        self.snapshot_with(ledger_id, |accounts| accounts)
+    }
+
+    // Like `snapshot`, but postings on the ledger wait until `read` returns
+    pub fn snapshot_with<R>(&self, ledger_id: &Uuid, read: impl FnOnce(Vec<Account>) -> R) -> R {
+        let mut slots: Vec<(Uuid, AccountSlot)> = self
+            .index
+            .read()
+            .by_id
+            .iter()
+            .filter(|(_, slot)| slot.ledger_id == *ledger_id)
+            .map(|(account_id, slot)| (*account_id, slot.clone()))
+            .collect();
+
+        slots.sort_by_key(|(account_id, _)| *account_id);
+
+        let started = Instant::now();
+        let guards: Vec<_> = slots.iter().map(|(_, slot)| slot.account.read_arc()).collect();
+        METRICS.record_lock_wait(LockMode::Read, started.elapsed());
+
+        read(guards.iter().map(|guard| Account::clone(guard)).collect())
+    }
+
+    // Locks accounts in id order, so concurrent postings over the same accounts can't deadlock.
+    // Fails with the first unknown account id, in the given order.
+    pub fn lock_for_posting(&self, account_ids: &[Uuid]) -> Result<LockedAccounts, Uuid> {
+        let mut slots = Vec::with_capacity(account_ids.len());
+
+        for account_id in account_ids {
+            let slot = self.slot(account_id).ok_or(*account_id)?;
+            slots.push((*account_id, slot));
+        }
+
+        slots.sort_by_key(|(account_id, _)| *account_id);
+        slots.dedup_by_key(|(account_id, _)| *account_id);
+
+        let started = Instant::now();
+        let guards = slots.into_iter().map(|(_, slot)| slot.account.write_arc()).collect();
+        METRICS.record_lock_wait(LockMode::Write, started.elapsed());
+
+        Ok(LockedAccounts { guards })
+    }
+}
+
+pub struct LockedAccounts {
+    guards: Vec<ArcRwLockWriteGuard<RawRwLock, Account>>,
+}
+
+impl LockedAccounts {
+    pub fn find(&self, account_id: &Uuid) -> Option<&Account> {
+        self.guards
+            .iter()
+            .find(|guard| guard.account_id == *account_id)
+            .map(|guard| &**guard)
+    }
+
+    pub fn get(&self, account_id: &Uuid) -> &Account {
+        self.find(account_id).expect("Account not locked")
+    }
+
+    pub fn get_mut(&mut self, account_id: &Uuid) -> &mut Account {
+        self.guards
+            .iter_mut()
+            .find(|guard| guard.account_id == *account_id)
+            .expect("Account not locked")
+    }
+
+    // Copies locked accounts as they are now, so changes can be undone with `restore`
+    pub fn snapshot(&self) -> Vec<Account> {
+        self.guards.iter().map(|guard| Account::clone(guard)).collect()
+    }
+
+    pub fn restore(&mut self, snapshot: Vec<Account>) {
+        for (guard, account) in self.guards.iter_mut().zip(snapshot) {
+            **guard = account;
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountPath {
+    pub account_id: Uuid,
 }
 
 pub async fn new_account(
     State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
     Json(payload): Json<CreateNewAccount>,
-) -> Result<Json<Account>, StatusCode> {
+) -> Result<Json<Account>, ApiError> {
+    let currency = match &payload.currency {
+        None => default_currency(),
+        Some(code) => parse_currency(code)
+            .ok_or_else(|| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, format!("Invalid currency {code}")))?,
+    };
+
+    if let Some(parent_id) = payload.parent_id {
+        if state.accounts.ledger_of(&parent_id) != Some(ledger_id) {
+            tracing::debug!(%parent_id, "Parent account not found");
+            return Err(ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Parent account {parent_id} not found"),
+            ));
+        }
+    }
+
+    let balance = i64::try_from(payload.balance.unwrap_or_default())
+        .map_err(|_| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "Balance out of range"))?;
+
     let new_account = Account {
         account_id: Uuid::new_v4(),
+        ledger_id,
         alias: payload.alias.clone(),
-        balance: payload.balance.unwrap_or_default(),
+        account_type: payload.account_type.unwrap_or_default(),
+        currency,
+        parent_id: payload.parent_id,
+        balance,
+        version: 1,
     };
 
-    let mut shared_state = state.write().expect("Cannot acquire shared state");
-
-    tracing::debug!("Creating | alias = {:?}", &payload.alias);
-    let saved = shared_state.accounts.save_account(new_account.clone());
-    tracing::debug!("Created | account_id = {:?}", &new_account.account_id);
+    tracing::debug!(alias = %payload.alias, "Creating account");
 
-    match saved {
-        Ok(_) => Ok(Json(new_account)),
-        Err(_) => Err(StatusCode::CONFLICT),
+    if state.accounts.save_account(new_account.clone()).is_err() {
+        return Err(ApiError::conflict(format!(
+            "Alias {} already taken by another account",
+            payload.alias
+        )));
     }
+
+    state.record_events(
+        ledger_id,
+        vec![EventPayload::AccountCreated {
+            account: new_account.clone(),
+        }],
+    );
+
+    tracing::debug!(account_id = %new_account.account_id, "Account created");
+    Ok(Json(new_account))
 }
 
 pub async fn account_details(
     State(state): State<SharedState>,
-    Path(account_id): Path<Uuid>,
-) -> Result<Json<Account>, StatusCode> {
-    let repos = &state.read().expect("Cannot acquire shared state");
-
-    let existing = repos.accounts.fetch_by_id(&account_id);
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(AccountPath { account_id }): Path<AccountPath>,
+) -> Result<impl IntoResponse, ApiError> {
+    let existing = state
+        .accounts
+        .fetch_by_id(&account_id)
+        .filter(|account| account.ledger_id == ledger_id)
+        .filter(|account| principal.owns(&account.account_id));
 
-    let Some(account) = existing.cloned() else {
-        tracing::debug!("Not found | account_id = {:?}", &account_id);
-        return Err(StatusCode::NOT_FOUND);
+    let Some(account) = existing else {
+        tracing::debug!(%account_id, "Account not found");
+        return Err(ApiError::not_found(format!("Account {account_id} not found")));
     };
 
-    Ok(Json(account))
+    Ok(([(header::ETAG, account.etag())], Json(account)))
 }
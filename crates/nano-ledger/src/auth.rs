@@ -0,0 +1,320 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::AppState;
+use crate::errors::ApiError;
+use crate::jwt::JwtVerifier;
+use axum::Json;
+use axum::extract::{Path, Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    #[serde(rename = "accounts:read")]
+    AccountsRead,
+    #[serde(rename = "accounts:write")]
+    AccountsWrite,
+    #[serde(rename = "transactions:write")]
+    TransactionsWrite,
+    #[serde(rename = "reports:read")]
+    ReportsRead,
+    #[serde(rename = "ledgers:write")]
+    LedgersWrite,
+    #[serde(rename = "keys:admin")]
+    KeysAdmin,
+    #[serde(rename = "webhooks:admin")]
+    WebhooksAdmin,
+    #[serde(rename = "journal:admin")]
+    JournalAdmin,
+}
+
+impl Scope {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scope::AccountsRead => "accounts:read",
+            Scope::AccountsWrite => "accounts:write",
+            Scope::TransactionsWrite => "transactions:write",
+            Scope::ReportsRead => "reports:read",
+            Scope::LedgersWrite => "ledgers:write",
+            Scope::KeysAdmin => "keys:admin",
+            Scope::WebhooksAdmin => "webhooks:admin",
+            Scope::JournalAdmin => "journal:admin",
+        }
+    }
+
+    pub fn all() -> BTreeSet<Scope> {
+        BTreeSet::from([
+            Scope::AccountsRead,
+            Scope::AccountsWrite,
+            Scope::TransactionsWrite,
+            Scope::ReportsRead,
+            Scope::LedgersWrite,
+            Scope::KeysAdmin,
+            Scope::WebhooksAdmin,
+            Scope::JournalAdmin,
+        ])
+    }
+
+    pub fn reads() -> BTreeSet<Scope> {
+        BTreeSet::from([Scope::AccountsRead, Scope::ReportsRead])
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Ownership {
+    Unrestricted,
+    Accounts(HashSet<Uuid>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Principal {
+    pub key_id: Option<Uuid>,
+    pub scopes: BTreeSet<Scope>,
+    pub ownership: Ownership,
+}
+
+impl Principal {
+    fn unrestricted() -> Self {
+        Principal {
+            key_id: None,
+            scopes: Scope::all(),
+            ownership: Ownership::Unrestricted,
+        }
+    }
+
+    pub fn owns(&self, account_id: &Uuid) -> bool {
+        match &self.ownership {
+            Ownership::Unrestricted => true,
+            Ownership::Accounts(owned) => owned.contains(account_id),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateNewApiKey {
+    pub name: String,
+    pub scopes: BTreeSet<Scope>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreatedApiKey {
+    pub key_id: Uuid,
+    pub api_key: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApiKey {
+    pub key_id: Uuid,
+    pub name: String,
+    pub scopes: BTreeSet<Scope>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    hashed_secret: String,
+}
+
+impl ApiKey {
+    fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn generate_secret() -> String {
+    format!("nl_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+#[derive(Default)]
+pub struct ApiKeysRepository {
+    keys: Vec<ApiKey>,
+}
+
+impl ApiKeysRepository {
+    pub fn register(&mut self, name: &str, secret: &str, scopes: BTreeSet<Scope>) -> ApiKey {
+        let key = ApiKey {
+            key_id: Uuid::new_v4(),
+            name: name.to_string(),
+            scopes,
+            created_at: Utc::now(),
+            revoked_at: None,
+            hashed_secret: hash_secret(secret),
+        };
+
+        self.keys.push(key.clone());
+        key
+    }
+
+    // Hashes are compared in constant time, so response times don't tell how close a guess was
+    pub fn authenticate(&self, secret: &str) -> Option<&ApiKey> {
+        let hashed_secret = hash_secret(secret);
+        self.keys.iter().find(|key| {
+            let matches: bool = key.hashed_secret.as_bytes().ct_eq(hashed_secret.as_bytes()).into();
+            key.is_active() && matches
+        })
+    }
+
+    pub fn revoke(&mut self, key_id: &Uuid) -> Option<&ApiKey> {
+        let key = self.keys.iter_mut().find(|key| key.key_id == *key_id)?;
+        key.revoked_at.get_or_insert_with(Utc::now);
+        Some(key)
+    }
+
+    pub fn list(&self) -> Vec<ApiKey> {
+        self.keys.clone()
+    }
+}
+
+#[derive(Default)]
+pub struct Authenticator {
+    api_keys_enabled: bool,
+    keys: RwLock<ApiKeysRepository>,
+    jwt: Option<JwtVerifier>,
+}
+
+impl Authenticator {
+    pub fn with_admin_key(admin_secret: &str) -> Self {
+        let mut keys = ApiKeysRepository::default();
+        keys.register("bootstrap-admin", admin_secret, Scope::all());
+
+        Authenticator {
+            api_keys_enabled: true,
+            keys: RwLock::new(keys),
+            jwt: None,
+        }
+    }
+
+    pub fn with_jwt(self, verifier: JwtVerifier) -> Self {
+        Authenticator {
+            jwt: Some(verifier),
+            ..self
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let authenticator = match std::env::var("ADMIN_API_KEY") {
+            Ok(admin_secret) if !admin_secret.is_empty() => Authenticator::with_admin_key(&admin_secret),
+            _ => Authenticator::default(),
+        };
+
+        let authenticator = match JwtVerifier::from_env() {
+            Some(verifier) => authenticator.with_jwt(verifier.expect("cannot load JWT verification keys")),
+            None => authenticator,
+        };
+
+        // Running without credentials must be asked for, not fallen into by a missing variable
+        if !authenticator.is_enabled() {
+            let disabled = std::env::var("AUTH_DISABLED").is_ok_and(|value| value == "true");
+            assert!(
+                disabled,
+                "Neither ADMIN_API_KEY nor JWT keys are set, set AUTH_DISABLED=true to run without authentication"
+            );
+            tracing::warn!("AUTH_DISABLED is set, authentication is disabled");
+        }
+
+        authenticator
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.api_keys_enabled || self.jwt.is_some()
+    }
+
+    fn identify(&self, headers: &HeaderMap) -> Option<Principal> {
+        if !self.is_enabled() {
+            return Some(Principal::unrestricted());
+        }
+
+        if let Some(secret) = headers.get(API_KEY_HEADER) {
+            if !self.api_keys_enabled {
+                return None;
+            }
+
+            let keys = self.keys.read();
+            let key = keys.authenticate(secret.to_str().ok()?)?;
+
+            return Some(Principal {
+                key_id: Some(key.key_id),
+                scopes: key.scopes.clone(),
+                ownership: Ownership::Unrestricted,
+            });
+        }
+
+        let token = headers
+            .get(header::AUTHORIZATION)?
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")?;
+
+        self.jwt.as_ref()?.verify(token.trim())
+    }
+}
+
+pub async fn authenticate(State(auth): State<Arc<Authenticator>>, mut request: Request, next: Next) -> Response {
+    let Some(principal) = auth.identify(request.headers()) else {
+        tracing::debug!("Missing or invalid credentials");
+        return ApiError::new(StatusCode::UNAUTHORIZED, "Missing or invalid credentials").into_response();
+    };
+
+    request.extensions_mut().insert(principal);
+    next.run(request).await
+}
+
+pub async fn require_scope(State(scope): State<Scope>, request: Request, next: Next) -> Response {
+    let principal = request.extensions().get::<Principal>();
+    let granted = principal.is_some_and(|principal| principal.scopes.contains(&scope));
+
+    if !granted {
+        let key_id = principal.and_then(|principal| principal.key_id);
+        tracing::debug!(?scope, ?key_id, "Missing scope");
+        return ApiError::new(StatusCode::FORBIDDEN, format!("Missing scope {}", scope.name())).into_response();
+    }
+
+    next.run(request).await
+}
+
+pub async fn new_api_key(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateNewApiKey>,
+) -> Result<Json<CreatedApiKey>, ApiError> {
+    let secret = generate_secret();
+
+    let mut keys = state.auth.keys.write();
+    let key = keys.register(&payload.name, &secret, payload.scopes);
+    tracing::debug!(key_id = %key.key_id, "API key created");
+
+    Ok(Json(CreatedApiKey {
+        key_id: key.key_id,
+        api_key: secret,
+    }))
+}
+
+pub async fn list_api_keys(State(state): State<AppState>) -> Json<Vec<ApiKey>> {
+    let keys = state.auth.keys.read();
+    Json(keys.list())
+}
+
+pub async fn revoke_api_key(State(state): State<AppState>, Path(key_id): Path<Uuid>) -> Result<Json<ApiKey>, ApiError> {
+    let mut keys = state.auth.keys.write();
+
+    let Some(key) = keys.revoke(&key_id) else {
+        tracing::debug!(%key_id, "API key not found");
+        return Err(ApiError::not_found(format!("API key {key_id} not found")));
+    };
+
+    tracing::debug!(%key_id, "API key revoked");
+    Ok(Json(key.clone()))
+}
@@ -0,0 +1,218 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::SharedState;
+use crate::accounts::Account;
+use crate::auth::Principal;
+use crate::events::{EventPayload, Subscription};
+use crate::ledgers::LedgerScope;
+use axum::Extension;
+use axum::extract::State;
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code};
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+// Missing this many heartbeats in a row drops the connection
+const MISSED_HEARTBEATS: u32 = 2;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SubscriptionCommand {
+    Subscribe { account_ids: Vec<Uuid> },
+    Unsubscribe { account_ids: Vec<Uuid> },
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BalanceMessage {
+    Balance {
+        account_id: Uuid,
+        balance: i64,
+        version: u64,
+    },
+    Unsubscribed {
+        account_ids: Vec<Uuid>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+struct BalanceSubscriptions {
+    repos: SharedState,
+    principal: Principal,
+    ledger_id: Uuid,
+    // Last version sent per subscribed account, so stale or repeated changes are skipped
+    sent_versions: HashMap<Uuid, u64>,
+}
+
+impl BalanceSubscriptions {
+    fn current_balance(&self, account_id: &Uuid) -> Option<BalanceMessage> {
+        self.repos
+            .accounts
+            .fetch_by_id(account_id)
+            .filter(|account| account.ledger_id == self.ledger_id)
+            .filter(|account| self.principal.owns(&account.account_id))
+            .map(|account| BalanceMessage::Balance {
+                account_id: account.account_id,
+                balance: account.balance,
+                version: account.version,
+            })
+    }
+
+    fn handle(&mut self, command: SubscriptionCommand) -> Vec<BalanceMessage> {
+        match command {
+            SubscriptionCommand::Subscribe { account_ids } => account_ids
+                .into_iter()
+                .map(|account_id| match self.current_balance(&account_id) {
+                    Some(message) => self.track(message),
+                    None => BalanceMessage::Error {
+                        message: format!("Account {account_id} not found"),
+                    },
+                })
+                .collect(),
+            SubscriptionCommand::Unsubscribe { account_ids } => {
+                for account_id in &account_ids {
+                    self.sent_versions.remove(account_id);
+                }
+
+                vec![BalanceMessage::Unsubscribed { account_ids }]
+            },
+        }
+    }
+
+    fn track(&mut self, message: BalanceMessage) -> BalanceMessage {
+        if let BalanceMessage::Balance {
+            account_id, version, ..
+        } = &message
+        {
+            self.sent_versions.insert(*account_id, *version);
+        }
+
+        message
+    }
+
+    // Only balances newer than the last one sent to the client go out
+    fn newer(&mut self, account_id: Uuid, balance: i64, version: u64) -> Option<BalanceMessage> {
+        let sent_version = self.sent_versions.get(&account_id)?;
+
+        if version <= *sent_version {
+            return None;
+        }
+
+        Some(self.track(BalanceMessage::Balance {
+            account_id,
+            balance,
+            version,
+        }))
+    }
+
+    fn on_change(&mut self, payload: &EventPayload) -> Option<BalanceMessage> {
+        match payload {
+            EventPayload::BalanceChanged {
+                account_id,
+                balance,
+                version,
+                ..
+            } => self.newer(*account_id, *balance, *version),
+            _ => None,
+        }
+    }
+
+    // After missing changes, the latest balances are all a client needs to catch up
+    fn resync(&mut self) -> Vec<BalanceMessage> {
+        let latest: Vec<Account> = self
+            .sent_versions
+            .keys()
+            .filter_map(|account_id| self.repos.accounts.fetch_by_id(account_id))
+            .collect();
+
+        latest
+            .into_iter()
+            .filter_map(|account| self.newer(account.account_id, account.balance, account.version))
+            .collect()
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &BalanceMessage) -> bool {
+    let text = serde_json::to_string(message).expect("Cannot serialize balance message");
+    socket.send(Message::Text(text.into())).await.is_ok()
+}
+
+async fn serve_subscriptions(mut socket: WebSocket, mut subscriptions: BalanceSubscriptions) {
+    // Listening starts before any balance is read, so no change goes unnoticed
+    let Ok(Subscription { mut live, .. }) = subscriptions.repos.events.subscribe(None) else {
+        return;
+    };
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut last_heard = Instant::now();
+
+    loop {
+        let replies = tokio::select! {
+            incoming = socket.recv() => {
+                last_heard = Instant::now();
+
+                match incoming {
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str::<SubscriptionCommand>(&text) {
+                        Ok(command) => subscriptions.handle(command),
+                        Err(error) => vec![BalanceMessage::Error { message: format!("Invalid command : {error}") }],
+                    },
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                }
+            },
+            received = live.recv() => match received {
+                Ok(event) => subscriptions.on_change(&event.payload).into_iter().collect(),
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::debug!(missed, "Slow balance subscriber, resending latest balances");
+                    subscriptions.resync()
+                },
+                Err(RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => {
+                if last_heard.elapsed() > HEARTBEAT_INTERVAL * MISSED_HEARTBEATS {
+                    tracing::debug!("Balance subscriber stopped answering heartbeats");
+                    let close = CloseFrame { code: close_code::POLICY, reason: "Heartbeat timeout".into() };
+                    let _ = socket.send(Message::Close(Some(close))).await;
+                    break;
+                }
+
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    break;
+                }
+
+                continue;
+            },
+        };
+
+        for reply in &replies {
+            if !send(&mut socket, reply).await {
+                return;
+            }
+        }
+    }
+}
+
+pub async fn balance_updates(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let subscriptions = BalanceSubscriptions {
+        repos: state,
+        principal,
+        ledger_id,
+        sent_versions: HashMap::new(),
+    };
+
+    tracing::debug!(%ledger_id, "Balance subscriber connected");
+    upgrade.on_upgrade(move |socket| serve_subscriptions(socket, subscriptions))
+}
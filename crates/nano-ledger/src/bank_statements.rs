@@ -0,0 +1,748 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::accounts::{self, AccountPath};
+use crate::auth::Principal;
+use crate::errors::ApiError;
+use crate::ledgers::LedgerScope;
+use crate::transactions::MovementType;
+use crate::{Repositories, SharedState};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use clap::ValueEnum;
+use roxmltree::Node;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+pub const MAX_STATEMENT_LINES: usize = 5000;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum BankStatementFormat {
+    // ISO 20022 bank to customer statement, in XML
+    Camt053,
+    // SWIFT customer statement message
+    Mt940,
+    // Open Financial Exchange, both SGML (1.x) and XML (2.x)
+    Ofx,
+}
+
+impl BankStatementFormat {
+    // Guesses the format from markers each format always carries
+    pub fn detect(content: &str) -> Option<BankStatementFormat> {
+        if content.contains("BkToCstmrStmt") {
+            Some(BankStatementFormat::Camt053)
+        } else if content.contains("OFXHEADER") || content.contains("<OFX>") {
+            Some(BankStatementFormat::Ofx)
+        } else if content.contains(":61:") || content.contains(":60F:") {
+            Some(BankStatementFormat::Mt940)
+        } else {
+            None
+        }
+    }
+}
+
+// A statement line as read from a bank statement, whatever its format
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatementLine {
+    pub statement_reference: Option<String>,
+    pub bank_reference: Option<String>,
+    pub booked_on: NaiveDate,
+    pub value_on: Option<NaiveDate>,
+    // Credits move money into the account, debits out of it, as for journal entries
+    pub movement_type: MovementType,
+    pub amount_in_cents: u64,
+    pub currency: String,
+    pub reference: Option<String>,
+    pub counterparty: Option<String>,
+    pub description: String,
+}
+
+// A statement line tied to a ledger account, to be matched against transactions
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExternalTransaction {
+    pub external_id: Uuid,
+    pub imported_at: DateTime<Utc>,
+    pub ledger_id: Uuid,
+    pub account_id: Uuid,
+    pub format: BankStatementFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement_reference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bank_reference: Option<String>,
+    pub booked_on: NaiveDate,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_on: Option<NaiveDate>,
+    pub movement_type: MovementType,
+    pub amount_in_cents: u64,
+    pub currency: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counterparty: Option<String>,
+    pub description: String,
+}
+
+#[derive(Default)]
+pub struct ExternalTransactionsRepository {
+    transactions: Vec<ExternalTransaction>,
+    by_id: HashMap<Uuid, usize>,
+    by_account: HashMap<Uuid, Vec<usize>>,
+    // Lines already imported per account, so statements can be imported again safely
+    fingerprints: HashSet<(Uuid, String)>,
+}
+
+impl ExternalTransactionsRepository {
+    // Returns false when the same line was already imported for this account
+    fn save_transaction(&mut self, transaction: ExternalTransaction, fingerprint: String) -> bool {
+        if !self.fingerprints.insert((transaction.account_id, fingerprint)) {
+            return false;
+        }
+
+        let position = self.transactions.len();
+        self.by_id.insert(transaction.external_id, position);
+        self.by_account
+            .entry(transaction.account_id)
+            .or_default()
+            .push(position);
+        self.transactions.push(transaction);
+        true
+    }
+
+    pub fn fetch_transaction(&self, external_id: &Uuid) -> Option<&ExternalTransaction> {
+        self.by_id
+            .get(external_id)
+            .map(|position| &self.transactions[*position])
+    }
+
+    // Lines booked from `from` included until `until` excluded, in import order
+    pub fn for_account(
+        &self,
+        account_id: &Uuid,
+        from: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> impl Iterator<Item = &ExternalTransaction> {
+        self.by_account
+            .get(account_id)
+            .into_iter()
+            .flatten()
+            .map(|position| &self.transactions[*position])
+            .filter(move |transaction| {
+                from.is_none_or(|from| transaction.booked_on >= from)
+                    && until.is_none_or(|until| transaction.booked_on < until)
+            })
+    }
+}
+
+// Cents from amounts like `1234.5`, `-12` or `1234,56`, either separator marking decimals
+fn parse_cents(text: &str) -> Option<i128> {
+    let text = text.trim().replace(',', ".");
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(&text)),
+    };
+
+    let (units, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+    let all_digits = |text: &str| text.chars().all(|character| character.is_ascii_digit());
+    if units.is_empty() && fraction.is_empty() || !all_digits(units) || !all_digits(fraction) {
+        return None;
+    }
+
+    // Some banks write more decimals than the currency has, as long as they are zeros
+    let (fraction, extra) = fraction.split_at(fraction.len().min(2));
+    if extra.chars().any(|character| character != '0') {
+        return None;
+    }
+
+    let units: i128 = if units.is_empty() { 0 } else { units.parse().ok()? };
+    let fraction: i128 = format!("{fraction:0<2}").parse().ok()?;
+    let cents = units.checked_mul(100)?.checked_add(fraction)?;
+    Some(if negative { -cents } else { cents })
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+// CAMT.053 elements are looked up by local name, whatever the schema version and namespace
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.tag_name().name() == name)
+}
+
+fn descend<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+    path.iter().try_fold(node, |node, name| child(node, name))
+}
+
+fn text_at(node: Node<'_, '_>, path: &[&str]) -> Option<String> {
+    descend(node, path).and_then(|node| node.text()).and_then(non_empty)
+}
+
+fn camt_date(node: Node<'_, '_>) -> Option<NaiveDate> {
+    let text = text_at(node, &["Dt"]).or_else(|| text_at(node, &["DtTm"]))?;
+    NaiveDate::parse_from_str(text.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn camt_line(entry: Node<'_, '_>, statement_reference: &Option<String>) -> Result<Option<StatementLine>, String> {
+    let position = entry.document().text_pos_at(entry.range().start);
+
+    // Pending entries may still change, only booked ones are reconciled
+    let status = text_at(entry, &["Sts"]).or_else(|| text_at(entry, &["Sts", "Cd"]));
+    if status.as_deref() == Some("PDNG") {
+        return Ok(None);
+    }
+
+    let amount = child(entry, "Amt").ok_or_else(|| format!("Entry at line {} has no amount", position.row))?;
+    let currency = amount
+        .attribute("Ccy")
+        .and_then(accounts::parse_currency)
+        .ok_or_else(|| format!("Entry at line {} has no valid currency", position.row))?;
+    let amount_in_cents = amount
+        .text()
+        .and_then(parse_cents)
+        .and_then(|cents| u64::try_from(cents).ok())
+        .ok_or_else(|| format!("Entry at line {} has an invalid amount", position.row))?;
+
+    let movement_type = match text_at(entry, &["CdtDbtInd"]).as_deref() {
+        Some("CRDT") => MovementType::Credit,
+        Some("DBIT") => MovementType::Debit,
+        _ => {
+            return Err(format!(
+                "Entry at line {} is neither a credit nor a debit",
+                position.row
+            ));
+        },
+    };
+
+    let booked_on = child(entry, "BookgDt")
+        .and_then(camt_date)
+        .ok_or_else(|| format!("Entry at line {} has no booking date", position.row))?;
+    let value_on = child(entry, "ValDt").and_then(camt_date);
+
+    // Batched entries are kept whole, described by their first transaction details
+    let details = descend(entry, &["NtryDtls", "TxDtls"]);
+
+    let reference = details
+        .and_then(|details| text_at(details, &["Refs", "EndToEndId"]))
+        .filter(|reference| reference != "NOTPROVIDED");
+
+    let counterparty_role = match movement_type {
+        MovementType::Credit => "Dbtr",
+        MovementType::Debit => "Cdtr",
+    };
+
+    let counterparty = details.and_then(|details| {
+        text_at(details, &["RltdPties", counterparty_role, "Nm"])
+            .or_else(|| text_at(details, &["RltdPties", counterparty_role, "Pty", "Nm"]))
+    });
+
+    let remittance = details
+        .and_then(|details| child(details, "RmtInf"))
+        .and_then(|remittance| {
+            let unstructured: Vec<&str> = remittance
+                .children()
+                .filter(|child| child.tag_name().name() == "Ustrd")
+                .filter_map(|child| child.text())
+                .collect();
+            non_empty(&unstructured.join(" "))
+        });
+
+    let description = remittance
+        .or_else(|| details.and_then(|details| text_at(details, &["AddtlTxInf"])))
+        .or_else(|| text_at(entry, &["AddtlNtryInf"]))
+        .unwrap_or_default();
+
+    Ok(Some(StatementLine {
+        statement_reference: statement_reference.clone(),
+        bank_reference: text_at(entry, &["AcctSvcrRef"]).or_else(|| text_at(entry, &["NtryRef"])),
+        booked_on,
+        value_on,
+        movement_type,
+        amount_in_cents,
+        currency,
+        reference,
+        counterparty,
+        description,
+    }))
+}
+
+pub fn parse_camt053(content: &str) -> Result<Vec<StatementLine>, String> {
+    let document = roxmltree::Document::parse(content).map_err(|error| format!("Invalid XML, {error}"))?;
+    let mut lines = Vec::new();
+
+    let statements = document.descendants().filter(|node| node.tag_name().name() == "Stmt");
+
+    for statement in statements {
+        let statement_reference = text_at(statement, &["Id"]);
+
+        for entry in statement.children().filter(|node| node.tag_name().name() == "Ntry") {
+            lines.extend(camt_line(entry, &statement_reference)?);
+        }
+    }
+
+    Ok(lines)
+}
+
+// Two digit years, as SWIFT defines them
+fn mt940_date(text: &str) -> Option<NaiveDate> {
+    let year: i32 = text.get(..2)?.parse().ok()?;
+    let year = if year < 80 { 2000 + year } else { 1900 + year };
+    NaiveDate::from_ymd_opt(year, text.get(2..4)?.parse().ok()?, text.get(4..6)?.parse().ok()?)
+}
+
+// Entry dates only carry month and day, and may fall in the year before or after the value date
+fn mt940_entry_date(value_on: NaiveDate, text: &str) -> Option<NaiveDate> {
+    let month = text.get(..2)?.parse().ok()?;
+    let day = text.get(2..4)?.parse().ok()?;
+
+    [value_on.year(), value_on.year() - 1, value_on.year() + 1]
+        .into_iter()
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .min_by_key(|date| (*date - value_on).num_days().abs())
+}
+
+// German banks structure `:86:` as `?`-prefixed subfields, others write free text
+fn mt940_information(text: &str) -> (String, Option<String>) {
+    let bytes = text.as_bytes();
+    let is_structured = bytes.len() > 4 && bytes[..3].iter().all(u8::is_ascii_digit) && bytes[3] == b'?';
+
+    if !is_structured {
+        return (non_empty(text).unwrap_or_default(), None);
+    }
+
+    let mut booking_text = String::new();
+    let mut remittance = String::new();
+    let mut counterparty = String::new();
+
+    for subfield in text[4..].split('?') {
+        let (Some(code), Some(value)) = (subfield.get(..2), subfield.get(2..)) else {
+            continue;
+        };
+
+        match code {
+            "00" => booking_text.push_str(value),
+            "20" | "21" | "22" | "23" | "24" | "25" | "26" | "27" | "28" | "29" | "60" | "61" | "62" | "63" => {
+                remittance.push_str(value)
+            },
+            "32" | "33" => counterparty.push_str(value),
+            _ => {},
+        }
+    }
+
+    let description = non_empty(&remittance)
+        .or_else(|| non_empty(&booking_text))
+        .unwrap_or_default();
+    (description, non_empty(&counterparty))
+}
+
+fn mt940_line(value: &str, statement_reference: &Option<String>, currency: &str) -> Result<StatementLine, String> {
+    let (first, supplementary) = value.split_once('\n').unwrap_or((value, ""));
+
+    let value_on = mt940_date(first).ok_or("Invalid value date")?;
+    let mut rest = &first[6..];
+
+    let booked_on = match rest.get(..4) {
+        Some(entry_date) if entry_date.chars().all(|character| character.is_ascii_digit()) => {
+            rest = &rest[4..];
+            mt940_entry_date(value_on, entry_date).ok_or("Invalid entry date")?
+        },
+        _ => value_on,
+    };
+
+    // Reversals move money the opposite way of what they reverse
+    let (movement_type, mark_length) = if rest.starts_with("RC") {
+        (MovementType::Debit, 2)
+    } else if rest.starts_with("RD") {
+        (MovementType::Credit, 2)
+    } else if rest.starts_with('C') {
+        (MovementType::Credit, 1)
+    } else if rest.starts_with('D') {
+        (MovementType::Debit, 1)
+    } else {
+        return Err("Missing debit or credit mark".to_string());
+    };
+    rest = &rest[mark_length..];
+
+    // The funds code is the last letter of the currency code, when given
+    if rest.starts_with(|character: char| character.is_ascii_alphabetic()) {
+        rest = &rest[1..];
+    }
+
+    let amount_length = rest
+        .find(|character: char| !character.is_ascii_digit() && character != ',')
+        .unwrap_or(rest.len());
+    let amount_in_cents = parse_cents(&rest[..amount_length])
+        .and_then(|cents| u64::try_from(cents).ok())
+        .ok_or("Invalid amount")?;
+    rest = &rest[amount_length..];
+
+    // Transaction type identification, like NTRF, comes before references
+    let references = rest.get(4..).ok_or("Missing transaction type")?;
+    let (customer_reference, bank_reference) = references.split_once("//").unwrap_or((references, ""));
+
+    Ok(StatementLine {
+        statement_reference: statement_reference.clone(),
+        bank_reference: non_empty(bank_reference),
+        booked_on,
+        value_on: Some(value_on),
+        movement_type,
+        amount_in_cents,
+        currency: currency.to_string(),
+        reference: non_empty(customer_reference).filter(|reference| reference != "NONREF"),
+        counterparty: None,
+        description: non_empty(supplementary).unwrap_or_default(),
+    })
+}
+
+pub fn parse_mt940(content: &str) -> Result<Vec<StatementLine>, String> {
+    // Fields start with a `:tag:` and continue on the lines below
+    let mut fields: Vec<(usize, String, String)> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        let line = line.split_once("{4:").map_or(line, |(_, body)| body);
+
+        if line.is_empty() || line.starts_with('{') || line.starts_with('-') {
+            continue;
+        }
+
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| (2..=3).contains(&tag.len()));
+
+        match (tag, fields.last_mut()) {
+            (Some((tag, value)), _) => fields.push((index + 1, tag.to_string(), value.to_string())),
+            (None, Some((_, tag, value))) => {
+                // Statement lines keep supplementary details apart, other fields are wrapped text
+                let separator = if tag == "61" && !value.contains('\n') { "\n" } else { "" };
+                value.push_str(separator);
+                value.push_str(line);
+            },
+            (None, None) => return Err(format!("Line {} is outside of any field", index + 1)),
+        }
+    }
+
+    let mut lines: Vec<StatementLine> = Vec::new();
+    let mut statement_reference = None;
+    let mut currency = None;
+    let mut previous_tag = String::new();
+
+    for (line, tag, value) in fields {
+        match tag.as_str() {
+            "20" => statement_reference = non_empty(&value),
+            "60F" | "60M" => {
+                currency = value.get(7..10).and_then(accounts::parse_currency);
+                if currency.is_none() {
+                    return Err(format!("Line {line} has an invalid opening balance"));
+                }
+            },
+            "61" => {
+                let Some(currency) = &currency else {
+                    return Err(format!("Line {line} comes before any opening balance"));
+                };
+
+                let statement_line = mt940_line(&value, &statement_reference, currency)
+                    .map_err(|error| format!("Line {line} is not a valid statement line, {error}"))?;
+                lines.push(statement_line);
+            },
+            // Information to the account owner describes the statement line right above
+            "86" if previous_tag == "61" => {
+                let (description, counterparty) = mt940_information(&value);
+                if let Some(last) = lines.last_mut() {
+                    if !description.is_empty() {
+                        last.description = description;
+                    }
+                    last.counterparty = counterparty;
+                }
+            },
+            _ => {},
+        }
+
+        previous_tag = tag;
+    }
+
+    Ok(lines)
+}
+
+// Elements and their text, closing tags included, skipping headers and processing instructions
+fn ofx_elements(content: &str) -> Vec<(String, String)> {
+    let mut elements = Vec::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+
+        let tag = rest[start + 1..start + end].trim().to_uppercase();
+        rest = &rest[start + end + 1..];
+
+        let text = rest[..rest.find('<').unwrap_or(rest.len())].trim();
+
+        if !tag.starts_with('?') && !tag.starts_with('!') {
+            elements.push((tag, text.to_string()));
+        }
+    }
+
+    elements
+}
+
+fn ofx_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text.get(..8)?, "%Y%m%d").ok()
+}
+
+fn ofx_line(fields: &HashMap<String, String>, currency: &Option<String>) -> Result<StatementLine, String> {
+    let field = |name: &str| fields.get(name).and_then(|value| non_empty(value));
+
+    let booked_on = field("DTPOSTED")
+        .and_then(|date| ofx_date(&date))
+        .ok_or("Missing or invalid DTPOSTED")?;
+    let cents = field("TRNAMT")
+        .and_then(|amount| parse_cents(&amount))
+        .ok_or("Missing or invalid TRNAMT")?;
+    let currency = field("CURSYM")
+        .or_else(|| currency.clone())
+        .and_then(|code| accounts::parse_currency(&code))
+        .ok_or("Missing currency, neither CURDEF nor CURSYM given")?;
+
+    let movement_type = if cents < 0 {
+        MovementType::Debit
+    } else {
+        MovementType::Credit
+    };
+
+    let counterparty = field("NAME");
+    let description = field("MEMO").or_else(|| counterparty.clone()).unwrap_or_default();
+
+    Ok(StatementLine {
+        statement_reference: None,
+        bank_reference: field("FITID"),
+        booked_on,
+        value_on: field("DTUSER").and_then(|date| ofx_date(&date)),
+        movement_type,
+        amount_in_cents: u64::try_from(cents.abs()).map_err(|_| "Amount out of range")?,
+        currency,
+        reference: field("CHECKNUM").or_else(|| field("REFNUM")),
+        counterparty,
+        description,
+    })
+}
+
+pub fn parse_ofx(content: &str) -> Result<Vec<StatementLine>, String> {
+    let mut lines = Vec::new();
+    let mut currency = None;
+    let mut current: Option<HashMap<String, String>> = None;
+
+    for (tag, text) in ofx_elements(content) {
+        match (tag.as_str(), &mut current) {
+            ("CURDEF", _) => currency = non_empty(&text),
+            ("STMTTRN", _) => current = Some(HashMap::new()),
+            ("/STMTTRN", Some(fields)) => {
+                let line = ofx_line(fields, &currency)
+                    .map_err(|error| format!("Transaction {} is not valid, {error}", lines.len() + 1))?;
+                lines.push(line);
+                current = None;
+            },
+            (_, Some(fields)) if !tag.starts_with('/') => {
+                fields.insert(tag, text);
+            },
+            _ => {},
+        }
+    }
+
+    // SGML files may leave the last transaction unclosed
+    if let Some(fields) = current {
+        let line = ofx_line(&fields, &currency)
+            .map_err(|error| format!("Transaction {} is not valid, {error}", lines.len() + 1))?;
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+pub fn parse(format: BankStatementFormat, content: &str) -> Result<Vec<StatementLine>, String> {
+    match format {
+        BankStatementFormat::Camt053 => parse_camt053(content),
+        BankStatementFormat::Mt940 => parse_mt940(content),
+        BankStatementFormat::Ofx => parse_ofx(content),
+    }
+}
+
+// Identifies a line across imports, by its bank reference when there is one. Identical
+// lines within a statement are told apart by their rank.
+fn fingerprint(line: &StatementLine, occurrences: &mut HashMap<String, usize>) -> String {
+    let key = match &line.bank_reference {
+        Some(bank_reference) => format!("{}|{bank_reference}", line.booked_on),
+        None => format!(
+            "{}|{:?}|{}|{}|{}",
+            line.booked_on,
+            line.movement_type,
+            line.amount_in_cents,
+            line.reference.as_deref().unwrap_or_default(),
+            line.description
+        ),
+    };
+
+    let occurrence = occurrences.entry(key.clone()).or_default();
+    *occurrence += 1;
+    format!("{key}#{occurrence}")
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BankStatementImport {
+    pub format: BankStatementFormat,
+    pub account_id: Uuid,
+    pub imported: usize,
+    // Lines already imported for this account, from this or an overlapping statement
+    pub duplicates: usize,
+    pub transactions: Vec<ExternalTransaction>,
+}
+
+pub fn import_statement(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    account_id: Uuid,
+    format: Option<BankStatementFormat>,
+    content: &str,
+) -> Result<BankStatementImport, ApiError> {
+    let Some(account) = repos
+        .accounts
+        .fetch_by_id(&account_id)
+        .filter(|account| account.ledger_id == ledger_id)
+    else {
+        return Err(ApiError::not_found(format!("Account {account_id} not found")));
+    };
+
+    let Some(format) = format.or_else(|| BankStatementFormat::detect(content)) else {
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Unknown statement format, expected CAMT.053, MT940 or OFX",
+        ));
+    };
+
+    let lines = parse(format, content).map_err(|error| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, error))?;
+
+    if lines.len() > MAX_STATEMENT_LINES {
+        return Err(ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Statements are limited to {MAX_STATEMENT_LINES} lines"),
+        ));
+    }
+
+    if let Some(line) = lines.iter().find(|line| line.currency != account.currency) {
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "Statement holds {}, but account {account_id} holds {}",
+                line.currency, account.currency
+            ),
+        ));
+    }
+
+    let imported_at = Utc::now();
+    let mut occurrences = HashMap::new();
+    let mut imported = Vec::new();
+    let mut duplicates = 0;
+    let mut repository = crate::write_lock(&repos.external_transactions);
+
+    for line in lines {
+        let fingerprint = fingerprint(&line, &mut occurrences);
+
+        let transaction = ExternalTransaction {
+            external_id: Uuid::new_v4(),
+            imported_at,
+            ledger_id,
+            account_id,
+            format,
+            statement_reference: line.statement_reference,
+            bank_reference: line.bank_reference,
+            booked_on: line.booked_on,
+            value_on: line.value_on,
+            movement_type: line.movement_type,
+            amount_in_cents: line.amount_in_cents,
+            currency: line.currency,
+            reference: line.reference,
+            counterparty: line.counterparty,
+            description: line.description,
+        };
+
+        if repository.save_transaction(transaction.clone(), fingerprint) {
+            imported.push(transaction);
+        } else {
+            duplicates += 1;
+        }
+    }
+
+    Ok(BankStatementImport {
+        format,
+        account_id,
+        imported: imported.len(),
+        duplicates,
+        transactions: imported,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BankStatementQuery {
+    format: Option<BankStatementFormat>,
+}
+
+pub async fn import_bank_statement(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(AccountPath { account_id }): Path<AccountPath>,
+    Query(query): Query<BankStatementQuery>,
+    body: String,
+) -> Result<Json<BankStatementImport>, ApiError> {
+    if !principal.owns(&account_id) {
+        tracing::debug!(%account_id, "Account not found");
+        return Err(ApiError::not_found(format!("Account {account_id} not found")));
+    }
+
+    let report = import_statement(&state, ledger_id, account_id, query.format, &body)?;
+
+    tracing::debug!(
+        %account_id,
+        format = ?report.format,
+        imported = report.imported,
+        duplicates = report.duplicates,
+        "Bank statement imported"
+    );
+
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExternalTransactionsQuery {
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+}
+
+pub async fn list_external_transactions(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(AccountPath { account_id }): Path<AccountPath>,
+    Query(ExternalTransactionsQuery { from, until }): Query<ExternalTransactionsQuery>,
+) -> Result<Json<Vec<ExternalTransaction>>, ApiError> {
+    let found = state
+        .accounts
+        .ledger_of(&account_id)
+        .filter(|owner| *owner == ledger_id && principal.owns(&account_id));
+
+    if found.is_none() {
+        tracing::debug!(%account_id, "Account not found");
+        return Err(ApiError::not_found(format!("Account {account_id} not found")));
+    }
+
+    let repository = crate::read_lock(&state.external_transactions);
+    Ok(Json(
+        repository.for_account(&account_id, from, until).cloned().collect(),
+    ))
+}
@@ -0,0 +1,235 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::accounts::AccountPath;
+use crate::auth::Principal;
+use crate::errors::ApiError;
+use crate::ledgers::LedgerScope;
+use crate::merkle::{self, BalanceLeaf, MerkleTree, ProofStep};
+use crate::{Repositories, SharedState};
+use axum::extract::{Path, Query, State};
+use axum::{Extension, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Checkpoint {
+    pub checkpoint_id: Uuid,
+    pub ledger_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub accounts: usize,
+    pub root: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BalanceProof {
+    pub checkpoint: Checkpoint,
+    pub leaf: BalanceLeaf,
+    pub path: Vec<ProofStep>,
+}
+
+impl BalanceProof {
+    // The root must come from where checkpoints are published, not from the proof itself
+    pub fn verify(&self, published_root: &str) -> bool {
+        merkle::verify_inclusion(&self.leaf, &self.path, published_root)
+    }
+}
+
+struct CheckpointTree {
+    checkpoint: Checkpoint,
+    leaves: Vec<BalanceLeaf>,
+    tree: MerkleTree,
+}
+
+const DEFAULT_RETENTION: usize = 100;
+
+// Keeps the latest checkpoints of each ledger, older ones are evicted with their trees
+pub struct CheckpointsRepository {
+    by_ledger: HashMap<Uuid, VecDeque<CheckpointTree>>,
+    retention: usize,
+}
+
+impl Default for CheckpointsRepository {
+    fn default() -> Self {
+        let retention = std::env::var("CHECKPOINT_RETENTION")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|retention| *retention > 0)
+            .unwrap_or(DEFAULT_RETENTION);
+
+        CheckpointsRepository::with_retention(retention)
+    }
+}
+
+impl CheckpointsRepository {
+    pub fn with_retention(retention: usize) -> Self {
+        CheckpointsRepository {
+            by_ledger: HashMap::new(),
+            retention,
+        }
+    }
+
+    fn save_checkpoint(&mut self, checkpoint: CheckpointTree) {
+        let checkpoints = self.by_ledger.entry(checkpoint.checkpoint.ledger_id).or_default();
+        checkpoints.push_back(checkpoint);
+
+        while checkpoints.len() > self.retention {
+            checkpoints.pop_front();
+        }
+    }
+
+    // Oldest first
+    fn checkpoints_of(&self, ledger_id: &Uuid) -> impl DoubleEndedIterator<Item = &CheckpointTree> {
+        self.by_ledger.get(ledger_id).into_iter().flatten()
+    }
+
+    fn fetch(&self, ledger_id: &Uuid, checkpoint_id: Option<&Uuid>) -> Option<&CheckpointTree> {
+        match checkpoint_id {
+            Some(checkpoint_id) => self
+                .checkpoints_of(ledger_id)
+                .find(|checkpoint| checkpoint.checkpoint.checkpoint_id == *checkpoint_id),
+            None => self.checkpoints_of(ledger_id).next_back(),
+        }
+    }
+
+    pub fn fetch_checkpoint(&self, ledger_id: &Uuid, checkpoint_id: Option<&Uuid>) -> Option<Checkpoint> {
+        self.fetch(ledger_id, checkpoint_id)
+            .map(|checkpoint| checkpoint.checkpoint.clone())
+    }
+
+    pub fn list(&self, ledger_id: &Uuid) -> Vec<Checkpoint> {
+        self.checkpoints_of(ledger_id)
+            .map(|checkpoint| checkpoint.checkpoint.clone())
+            .collect()
+    }
+
+    pub fn proof(&self, ledger_id: &Uuid, checkpoint_id: Option<&Uuid>, account_id: &Uuid) -> Option<BalanceProof> {
+        let checkpoint = self.fetch(ledger_id, checkpoint_id)?;
+
+        let position = checkpoint
+            .leaves
+            .binary_search_by_key(account_id, |leaf| leaf.account_id)
+            .ok()?;
+
+        Some(BalanceProof {
+            checkpoint: checkpoint.checkpoint.clone(),
+            leaf: checkpoint.leaves[position].clone(),
+            path: checkpoint.tree.path(position),
+        })
+    }
+}
+
+pub fn create_checkpoint(repos: &Repositories, ledger_id: Uuid) -> Checkpoint {
+    let leaves: Vec<BalanceLeaf> = repos
+        .accounts
+        .snapshot(&ledger_id)
+        .into_iter()
+        .map(|account| BalanceLeaf {
+            account_id: account.account_id,
+            balance: account.balance,
+            salt: Uuid::new_v4().simple().to_string(),
+        })
+        .collect();
+
+    let tree = MerkleTree::build(leaves.iter().map(BalanceLeaf::hash).collect());
+
+    let checkpoint = Checkpoint {
+        checkpoint_id: Uuid::new_v4(),
+        ledger_id,
+        created_at: Utc::now(),
+        accounts: leaves.len(),
+        root: hex::encode(tree.root()),
+    };
+
+    crate::write_lock(&repos.checkpoints).save_checkpoint(CheckpointTree {
+        checkpoint: checkpoint.clone(),
+        leaves,
+        tree,
+    });
+
+    tracing::info!(checkpoint_id = %checkpoint.checkpoint_id, %ledger_id, root = %checkpoint.root, "Checkpoint created");
+    checkpoint
+}
+
+pub fn checkpoint_interval() -> Option<Duration> {
+    std::env::var("CHECKPOINT_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+}
+
+pub async fn run_periodically(state: SharedState, every: Duration) {
+    let mut interval = tokio::time::interval(every);
+
+    loop {
+        interval.tick().await;
+        let ledgers = crate::read_lock(&state.ledgers).list();
+
+        for ledger in ledgers {
+            create_checkpoint(&state, ledger.ledger_id);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckpointPath {
+    checkpoint_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProofQuery {
+    checkpoint: Option<Uuid>,
+}
+
+pub async fn new_checkpoint(State(state): State<SharedState>, LedgerScope(ledger_id): LedgerScope) -> Json<Checkpoint> {
+    Json(create_checkpoint(&state, ledger_id))
+}
+
+pub async fn list_checkpoints(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+) -> Json<Vec<Checkpoint>> {
+    Json(crate::read_lock(&state.checkpoints).list(&ledger_id))
+}
+
+pub async fn checkpoint_details(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(CheckpointPath { checkpoint_id }): Path<CheckpointPath>,
+) -> Result<Json<Checkpoint>, ApiError> {
+    let checkpoints = crate::read_lock(&state.checkpoints);
+
+    let Some(checkpoint) = checkpoints.fetch_checkpoint(&ledger_id, Some(&checkpoint_id)) else {
+        tracing::debug!(%checkpoint_id, "Checkpoint not found");
+        return Err(ApiError::not_found(format!("Checkpoint {checkpoint_id} not found")));
+    };
+
+    Ok(Json(checkpoint))
+}
+
+pub async fn account_proof(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(AccountPath { account_id }): Path<AccountPath>,
+    Query(ProofQuery { checkpoint }): Query<ProofQuery>,
+) -> Result<Json<BalanceProof>, ApiError> {
+    let checkpoints = crate::read_lock(&state.checkpoints);
+
+    let proof = Some(account_id)
+        .filter(|account_id| principal.owns(account_id))
+        .and_then(|account_id| checkpoints.proof(&ledger_id, checkpoint.as_ref(), &account_id));
+
+    let Some(proof) = proof else {
+        tracing::debug!(%account_id, ?checkpoint, "No balance proof");
+        return Err(ApiError::not_found(format!(
+            "No balance proof for account {account_id} at this checkpoint"
+        )));
+    };
+
+    Ok(Json(proof))
+}
@@ -0,0 +1,203 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::auth::API_KEY_HEADER;
+use crate::bank_statements::{BankStatementFormat, BankStatementImport};
+use crate::errors::ErrorBody;
+use crate::exports::ExportFormat;
+use crate::imports::AccountsImportReport;
+use crate::plaintext::{PlainTextFormat, PlainTextImportReport};
+use anyhow::{Context, bail};
+use chrono::{DateTime, Utc};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::io::Write;
+use uuid::Uuid;
+
+pub const DEFAULT_SERVER_URL: &str = "http://127.0.0.1:3000";
+
+// Where CLI commands find a running server, and how they authenticate
+#[derive(Clone, Debug)]
+pub struct ServerOptions {
+    pub url: String,
+    pub api_key: Option<String>,
+    pub ledger_id: Option<Uuid>,
+}
+
+impl ServerOptions {
+    fn endpoint(&self, path: &str) -> String {
+        let url = self.url.trim_end_matches('/');
+        match self.ledger_id {
+            Some(ledger_id) => format!("{url}/ledgers/{ledger_id}{path}"),
+            None => format!("{url}{path}"),
+        }
+    }
+
+    fn authenticated(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.header(API_KEY_HEADER, api_key),
+            None => request,
+        }
+    }
+}
+
+async fn error_from(response: Response) -> anyhow::Error {
+    let status = response.status();
+    let body = response.bytes().await.unwrap_or_default();
+    match serde_json::from_slice::<ErrorBody>(&body) {
+        Ok(error) => anyhow::anyhow!("Server answered {status}, {}", error.message),
+        Err(_) => anyhow::anyhow!("Server answered {status}"),
+    }
+}
+
+// Rejected rows are reported, not failed, so callers can show them
+pub async fn import_accounts(
+    server: &ServerOptions,
+    csv: String,
+    dry_run: bool,
+) -> anyhow::Result<AccountsImportReport> {
+    let request = reqwest::Client::new()
+        .post(server.endpoint("/accounts/import"))
+        .query(&[("dry_run", dry_run)])
+        .header(CONTENT_TYPE, "text/csv")
+        .body(csv);
+
+    let response = server
+        .authenticated(request)
+        .send()
+        .await
+        .with_context(|| format!("Cannot reach nano-ledger at {}", server.url))?;
+
+    if ![StatusCode::OK, StatusCode::UNPROCESSABLE_ENTITY].contains(&response.status()) {
+        return Err(error_from(response).await);
+    }
+
+    let bytes = response.bytes().await?;
+    match serde_json::from_slice(&bytes) {
+        Ok(report) => Ok(report),
+        Err(_) => bail!("Unexpected import report {}", String::from_utf8_lossy(&bytes)),
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub account_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+// Writes the export as the server streams it, `records` being either `journal` or `transactions`
+pub async fn export(
+    server: &ServerOptions,
+    records: &str,
+    options: &ExportOptions,
+    output: &mut impl Write,
+) -> anyhow::Result<()> {
+    let format = serde_json::to_value(options.format)?;
+    let mut query = vec![("format", format.as_str().unwrap_or_default().to_string())];
+    query.extend(
+        options
+            .account_id
+            .map(|account_id| ("account_id", account_id.to_string())),
+    );
+    query.extend(options.from.map(|from| ("from", from.to_rfc3339())));
+    query.extend(options.until.map(|until| ("until", until.to_rfc3339())));
+
+    let request = reqwest::Client::new()
+        .get(server.endpoint(&format!("/{records}/export")))
+        .query(&query);
+
+    let mut response = server
+        .authenticated(request)
+        .send()
+        .await
+        .with_context(|| format!("Cannot reach nano-ledger at {}", server.url))?;
+
+    if response.status() != StatusCode::OK {
+        return Err(error_from(response).await);
+    }
+
+    while let Some(chunk) = response.chunk().await? {
+        output.write_all(&chunk)?;
+    }
+
+    output.flush()?;
+    Ok(())
+}
+
+pub async fn export_plaintext(server: &ServerOptions, format: PlainTextFormat) -> anyhow::Result<String> {
+    let format = serde_json::to_value(format)?;
+    let request = reqwest::Client::new()
+        .get(server.endpoint("/plaintext/export"))
+        .query(&[("format", format.as_str().unwrap_or_default())]);
+
+    let response = server
+        .authenticated(request)
+        .send()
+        .await
+        .with_context(|| format!("Cannot reach nano-ledger at {}", server.url))?;
+
+    if response.status() != StatusCode::OK {
+        return Err(error_from(response).await);
+    }
+
+    Ok(response.text().await?)
+}
+
+// Lines that could not be imported are reported, not failed, so callers can show them
+pub async fn import_plaintext(server: &ServerOptions, journal: String) -> anyhow::Result<PlainTextImportReport> {
+    let request = reqwest::Client::new()
+        .post(server.endpoint("/plaintext/import"))
+        .header(CONTENT_TYPE, "text/plain")
+        .body(journal);
+
+    let response = server
+        .authenticated(request)
+        .send()
+        .await
+        .with_context(|| format!("Cannot reach nano-ledger at {}", server.url))?;
+
+    if ![StatusCode::OK, StatusCode::UNPROCESSABLE_ENTITY].contains(&response.status()) {
+        return Err(error_from(response).await);
+    }
+
+    let bytes = response.bytes().await?;
+    match serde_json::from_slice(&bytes) {
+        Ok(report) => Ok(report),
+        Err(_) => bail!("Unexpected import report {}", String::from_utf8_lossy(&bytes)),
+    }
+}
+
+// The format is detected by the server when not given
+pub async fn import_bank_statement(
+    server: &ServerOptions,
+    account_id: Uuid,
+    format: Option<BankStatementFormat>,
+    statement: String,
+) -> anyhow::Result<BankStatementImport> {
+    let mut request = reqwest::Client::new()
+        .post(server.endpoint(&format!("/accounts/{account_id}/external-transactions/import")))
+        .body(statement);
+
+    if let Some(format) = format {
+        let format = serde_json::to_value(format)?;
+        request = request.query(&[("format", format.as_str().unwrap_or_default())]);
+    }
+
+    let response = server
+        .authenticated(request)
+        .send()
+        .await
+        .with_context(|| format!("Cannot reach nano-ledger at {}", server.url))?;
+
+    if response.status() != StatusCode::OK {
+        return Err(error_from(response).await);
+    }
+
+    let bytes = response.bytes().await?;
+    match serde_json::from_slice(&bytes) {
+        Ok(report) => Ok(report),
+        Err(_) => bail!("Unexpected import report {}", String::from_utf8_lossy(&bytes)),
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::telemetry::REQUEST_ID_HEADER;
+use axum::Json;
+use axum::body::to_bytes;
+use axum::extract::Request;
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+
+const MAX_REJECTION_BODY_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ErrorBody {
+    pub status: u16,
+    pub error: String,
+    pub message: String,
+    pub request_id: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct ErrorMessage(String);
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::NOT_FOUND, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::new(StatusCode::CONFLICT, message)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        // The body is rendered by `structured_errors`, which knows about the current request
+        let mut response = self.status.into_response();
+        response.extensions_mut().insert(ErrorMessage(self.message));
+        response
+    }
+}
+
+pub async fn structured_errors(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+
+    let response = next.run(request).await;
+    let status = response.status();
+
+    if !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+
+    // Keep error bodies already rendered as JSON, like integrity reports
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+
+    if is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let reason = status.canonical_reason().unwrap_or("Unknown error").to_string();
+
+    // Plain-text bodies usually come from extractor rejections and are good enough as messages
+    let message = match parts.extensions.get::<ErrorMessage>() {
+        Some(ErrorMessage(text)) => text.clone(),
+        None => to_bytes(body, MAX_REJECTION_BODY_SIZE)
+            .await
+            .ok()
+            .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+            .filter(|text| !text.is_empty())
+            .unwrap_or_else(|| reason.clone()),
+    };
+
+    let body = ErrorBody {
+        status: status.as_u16(),
+        error: reason,
+        message,
+        request_id,
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    Response::from_parts(parts, Json(body).into_response().into_body())
+}
@@ -0,0 +1,255 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::SharedState;
+use crate::accounts::Account;
+use crate::auth::Principal;
+use crate::errors::ApiError;
+use crate::ledgers::LedgerScope;
+use crate::transactions::Transaction;
+use axum::Extension;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+pub const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+pub const EVENT_TYPES: [&str; 4] = [
+    "account.created",
+    "transaction.posted",
+    "transaction.reversed",
+    "balance.changed",
+];
+
+const DEFAULT_BUFFER_SIZE: usize = 10_000;
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum EventPayload {
+    #[serde(rename = "account.created")]
+    AccountCreated { account: Account },
+    #[serde(rename = "transaction.posted")]
+    TransactionPosted { transaction: Transaction },
+    #[serde(rename = "transaction.reversed")]
+    TransactionReversed {
+        reversed_transaction_id: Uuid,
+        transaction: Transaction,
+    },
+    #[serde(rename = "balance.changed")]
+    BalanceChanged {
+        account_id: Uuid,
+        transaction_id: Uuid,
+        balance: i64,
+        version: u64,
+    },
+}
+
+impl EventPayload {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventPayload::AccountCreated { .. } => "account.created",
+            EventPayload::TransactionPosted { .. } => "transaction.posted",
+            EventPayload::TransactionReversed { .. } => "transaction.reversed",
+            EventPayload::BalanceChanged { .. } => "balance.changed",
+        }
+    }
+
+    pub fn account_ids(&self) -> Vec<Uuid> {
+        match self {
+            EventPayload::AccountCreated { account } => vec![account.account_id],
+            EventPayload::TransactionPosted { transaction } | EventPayload::TransactionReversed { transaction, .. } => {
+                vec![transaction.lhs_account_id, transaction.rhs_account_id]
+            },
+            EventPayload::BalanceChanged { account_id, .. } => vec![*account_id],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LedgerEvent {
+    pub sequence: u64,
+    pub emitted_at: DateTime<Utc>,
+    pub ledger_id: Uuid,
+    #[serde(flatten)]
+    pub payload: EventPayload,
+}
+
+impl LedgerEvent {
+    pub fn concerns(&self, account_id: &Uuid) -> bool {
+        self.payload.account_ids().contains(account_id)
+    }
+}
+
+struct EventLog {
+    next_sequence: u64,
+    recent: VecDeque<LedgerEvent>,
+    capacity: usize,
+}
+
+// Keeps the most recent events for replays, and fans out new ones to live subscribers
+pub struct EventBus {
+    log: Mutex<EventLog>,
+    sender: broadcast::Sender<LedgerEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let capacity = std::env::var("EVENTS_BUFFER_SIZE")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|capacity| *capacity > 0)
+            .unwrap_or(DEFAULT_BUFFER_SIZE);
+
+        EventBus::with_capacity(capacity)
+    }
+}
+
+pub struct Subscription {
+    pub replay: Vec<LedgerEvent>,
+    pub live: broadcast::Receiver<LedgerEvent>,
+}
+
+impl EventBus {
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        EventBus {
+            log: Mutex::new(EventLog {
+                next_sequence: 1,
+                recent: VecDeque::with_capacity(capacity),
+                capacity,
+            }),
+            sender,
+        }
+    }
+
+    // Sequence numbers are assigned and events sent under the same lock, so they go out in order
+    pub fn publish(&self, ledger_id: Uuid, payloads: Vec<EventPayload>) -> Vec<LedgerEvent> {
+        let mut log = self.log.lock();
+        let mut published = Vec::with_capacity(payloads.len());
+
+        for payload in payloads {
+            let event = LedgerEvent {
+                sequence: log.next_sequence,
+                emitted_at: Utc::now(),
+                ledger_id,
+                payload,
+            };
+
+            log.next_sequence += 1;
+
+            if log.recent.len() == log.capacity {
+                log.recent.pop_front();
+            }
+
+            log.recent.push_back(event.clone());
+
+            // Nobody listening is fine
+            let _ = self.sender.send(event.clone());
+            published.push(event);
+        }
+
+        published
+    }
+
+    // Replays buffered events after `last_seen`, or fails with the oldest sequence still buffered.
+    // Subscribing under the log lock means live events pick up exactly where the replay ends
+    pub fn subscribe(&self, last_seen: Option<u64>) -> Result<Subscription, u64> {
+        let log = self.log.lock();
+        let live = self.sender.subscribe();
+
+        let Some(last_seen) = last_seen else {
+            return Ok(Subscription {
+                replay: Vec::new(),
+                live,
+            });
+        };
+
+        let oldest = log.recent.front().map_or(log.next_sequence, |event| event.sequence);
+
+        if last_seen.saturating_add(1) < oldest {
+            return Err(oldest);
+        }
+
+        let replay = log
+            .recent
+            .iter()
+            .filter(|event| event.sequence > last_seen)
+            .cloned()
+            .collect();
+
+        Ok(Subscription { replay, live })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    account_id: Option<Uuid>,
+}
+
+fn last_event_id(headers: &HeaderMap) -> Result<Option<u64>, ApiError> {
+    let Some(value) = headers.get(LAST_EVENT_ID_HEADER) else {
+        return Ok(None);
+    };
+
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .map(Some)
+        .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "Invalid Last-Event-ID header"))
+}
+
+fn to_sse(event: &LedgerEvent) -> Event {
+    Event::default()
+        .id(event.sequence.to_string())
+        .event(event.payload.name())
+        .json_data(event)
+        .expect("Cannot serialize ledger event")
+}
+
+pub async fn stream_events(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Query(EventsQuery { account_id }): Query<EventsQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let last_seen = last_event_id(&headers)?;
+
+    let Subscription { replay, live } = state.events.subscribe(last_seen).map_err(|oldest| {
+        tracing::debug!(?last_seen, oldest, "Events no longer buffered");
+        ApiError::new(
+            StatusCode::GONE,
+            format!(
+                "Events after {} are no longer available, oldest is {oldest}",
+                last_seen.unwrap_or_default()
+            ),
+        )
+    })?;
+
+    // A lagging subscriber missed events, ending the stream makes the client resume with Last-Event-ID
+    let live = BroadcastStream::new(live)
+        .take_while(|received| received.is_ok())
+        .filter_map(|received| received.ok());
+
+    let events = tokio_stream::iter(replay)
+        .chain(live)
+        .filter(move |event| event.ledger_id == ledger_id)
+        .filter(move |event| account_id.is_none_or(|account_id| event.concerns(&account_id)))
+        .filter(move |event| event.payload.account_ids().iter().any(|id| principal.owns(id)))
+        .map(|event| Ok(to_sse(&event)));
+
+    tracing::debug!(%ledger_id, ?account_id, ?last_seen, "Streaming events");
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
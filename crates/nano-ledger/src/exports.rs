@@ -0,0 +1,283 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::SharedState;
+use crate::auth::Principal;
+use crate::journal::{JournalEntry, JournalFilter};
+use crate::ledgers::LedgerScope;
+use crate::transactions::{MovementType, Transaction, TransactionFilter};
+use axum::Extension;
+use axum::body::{Body, Bytes};
+use axum::extract::{Query, State};
+use axum::http::{HeaderName, header};
+use axum::response::IntoResponse;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use uuid::Uuid;
+
+// Bumped whenever columns are added, removed or change meaning
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub const SCHEMA_VERSION_HEADER: &str = "x-schema-version";
+
+// Records read under a single lock acquisition, and written as a single body chunk
+pub const EXPORT_CHUNK_SIZE: usize = 500;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+// A record exported with a stable set of columns, led by the schema version
+pub trait Exported: Send + 'static {
+    const NAME: &'static str;
+    const COLUMNS: &'static [&'static str];
+    type Row: Serialize;
+
+    fn into_row(self) -> Self::Row;
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct JournalEntryRow {
+    pub schema_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub entry_id: Uuid,
+    pub transaction_id: Uuid,
+    pub ledger_id: Uuid,
+    pub account_id: Uuid,
+    pub movement_type: MovementType,
+    pub amount_in_cents: u64,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+impl Exported for JournalEntry {
+    const NAME: &'static str = "journal";
+    const COLUMNS: &'static [&'static str] = &[
+        "schema_version",
+        "created_at",
+        "entry_id",
+        "transaction_id",
+        "ledger_id",
+        "account_id",
+        "movement_type",
+        "amount_in_cents",
+        "previous_hash",
+        "hash",
+    ];
+    type Row = JournalEntryRow;
+
+    fn into_row(self) -> JournalEntryRow {
+        JournalEntryRow {
+            schema_version: SCHEMA_VERSION,
+            created_at: self.created_at,
+            entry_id: self.entry_id,
+            transaction_id: self.transaction_id,
+            ledger_id: self.ledger_id,
+            account_id: self.account_id,
+            movement_type: self.movement_type,
+            amount_in_cents: self.amount_in_cents,
+            previous_hash: self.previous_hash,
+            hash: self.hash,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransactionRow {
+    pub schema_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub transaction_id: Uuid,
+    pub ledger_id: Uuid,
+    pub movement_type: MovementType,
+    pub lhs_account_id: Uuid,
+    pub rhs_account_id: Uuid,
+    pub description: String,
+    pub amount_in_cents: u64,
+}
+
+impl Exported for Transaction {
+    const NAME: &'static str = "transactions";
+    const COLUMNS: &'static [&'static str] = &[
+        "schema_version",
+        "created_at",
+        "transaction_id",
+        "ledger_id",
+        "movement_type",
+        "lhs_account_id",
+        "rhs_account_id",
+        "description",
+        "amount_in_cents",
+    ];
+    type Row = TransactionRow;
+
+    fn into_row(self) -> TransactionRow {
+        TransactionRow {
+            schema_version: SCHEMA_VERSION,
+            created_at: self.created_at,
+            transaction_id: self.transaction_id,
+            ledger_id: self.ledger_id,
+            movement_type: self.movement_type,
+            lhs_account_id: self.lhs_account_id,
+            rhs_account_id: self.rhs_account_id,
+            description: self.description,
+            amount_in_cents: self.amount_in_cents,
+        }
+    }
+}
+
+// CSV chunks are written without headers, the first one is preceded by the header row
+fn encode<T: Exported>(format: ExportFormat, records: Vec<T>, with_header: bool) -> Bytes {
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+
+            if with_header {
+                writer.write_record(T::COLUMNS).expect("Cannot write CSV header");
+            }
+
+            for record in records {
+                writer.serialize(record.into_row()).expect("Cannot write CSV row");
+            }
+
+            Bytes::from(writer.into_inner().expect("Cannot flush CSV rows"))
+        },
+        ExportFormat::Ndjson => {
+            let mut buffer = Vec::new();
+
+            for record in records {
+                serde_json::to_writer(&mut buffer, &record.into_row()).expect("Cannot write NDJSON line");
+                buffer.push(b'\n');
+            }
+
+            Bytes::from(buffer)
+        },
+    }
+}
+
+struct ExportProgress {
+    next_position: Option<usize>,
+    with_header: bool,
+}
+
+// Streams records chunk by chunk, fetching each chunk from the given position only when
+// the previous one was consumed, so exports never hold the whole ledger in memory
+fn streamed<T, F>(format: ExportFormat, fetch_chunk: F) -> impl IntoResponse
+where
+    T: Exported,
+    F: Fn(usize) -> (Vec<T>, Option<usize>) + Send + Sync + 'static,
+{
+    let progress = ExportProgress {
+        next_position: Some(0),
+        with_header: format == ExportFormat::Csv,
+    };
+
+    let chunks = futures_util::stream::unfold(progress, move |progress| {
+        let chunk = progress.next_position.map(|position| {
+            let (records, next_position) = fetch_chunk(position);
+            let bytes = encode(format, records, progress.with_header);
+
+            let progress = ExportProgress {
+                next_position,
+                with_header: false,
+            };
+
+            (Ok::<_, Infallible>(bytes), progress)
+        });
+
+        async move { chunk }
+    });
+
+    let disposition = format!("attachment; filename=\"{}.{}\"", T::NAME, format.extension());
+
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+            (
+                HeaderName::from_static(SCHEMA_VERSION_HEADER),
+                SCHEMA_VERSION.to_string(),
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+    account_id: Option<Uuid>,
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+// Records appended after the export started are left out, so exports are a consistent cut
+pub async fn export_journal(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Query(query): Query<ExportQuery>,
+) -> impl IntoResponse {
+    let filter = JournalFilter {
+        ledger_id,
+        account_id: query.account_id,
+        from: query.from,
+        until: query.until,
+        ..JournalFilter::default()
+    };
+
+    let end = crate::read_lock(&state.journal).count();
+    tracing::debug!(?filter, end, format = ?query.format, "Exporting journal");
+
+    streamed(query.format, move |position| {
+        crate::read_lock(&state.journal).entries_within(&filter, position..end, EXPORT_CHUNK_SIZE, |entry| {
+            principal.owns(&entry.account_id)
+        })
+    })
+}
+
+pub async fn export_transactions(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Query(query): Query<ExportQuery>,
+) -> impl IntoResponse {
+    let filter = TransactionFilter {
+        ledger_id,
+        account_id: query.account_id,
+        from: query.from,
+        until: query.until,
+        ..TransactionFilter::default()
+    };
+
+    let end = crate::read_lock(&state.transactions).count();
+    tracing::debug!(?filter, end, format = ?query.format, "Exporting transactions");
+
+    streamed(query.format, move |position| {
+        crate::read_lock(&state.transactions).transactions_within(&filter, position..end, EXPORT_CHUNK_SIZE, |tx| {
+            principal.owns(&tx.lhs_account_id) || principal.owns(&tx.rhs_account_id)
+        })
+    })
+}
@@ -0,0 +1,340 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::accounts::{self, Account, AccountType};
+use crate::errors::ApiError;
+use crate::ledgers::LedgerScope;
+use crate::transactions::{self, CreateNewTransaction};
+use crate::{Repositories, SharedState};
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub const MAX_IMPORTED_ROWS: usize = 5000;
+
+pub const OPENING_BALANCE_DESCRIPTION: &str = "Opening balance";
+
+// Opening balances are transferred from one equity account per ledger and currency
+pub fn opening_balances_alias(currency: &str) -> String {
+    format!("equity.opening-balances.{}", currency.to_lowercase())
+}
+
+// Columns of an accounts CSV, only `alias` is mandatory
+#[derive(Debug, Deserialize)]
+struct AccountRow {
+    alias: String,
+    #[serde(default, rename = "type")]
+    account_type: Option<String>,
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default)]
+    parent: Option<String>,
+    #[serde(default)]
+    opening_balance_in_cents: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImportedRow {
+    // Line in the CSV file, the header being line 1
+    pub line: u64,
+    pub alias: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opening_transaction_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AccountsImportReport {
+    pub dry_run: bool,
+    pub imported: usize,
+    pub rejected: usize,
+    pub rows: Vec<ImportedRow>,
+}
+
+// A row that passed validation, with the account it will create
+struct ValidRow {
+    account: Account,
+    opening_balance: u64,
+}
+
+fn parse_account_type(name: &str) -> Option<AccountType> {
+    serde_json::from_value(serde_json::Value::String(name.trim().to_lowercase())).ok()
+}
+
+// Validates every row against the ledger and the rows above it. Parents must
+// already exist in the ledger or be declared in an earlier row.
+// Stops reading as soon as the file holds more rows than an import may
+fn validate(repos: &Repositories, ledger_id: Uuid, csv: &str) -> Result<(Vec<ImportedRow>, Vec<ValidRow>), ApiError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(csv.as_bytes());
+    let mut rows = Vec::new();
+    let mut valid = Vec::new();
+    let mut declared: HashMap<String, (Uuid, String)> = HashMap::new();
+
+    for (index, record) in reader.records().enumerate() {
+        if index == MAX_IMPORTED_ROWS {
+            return Err(ApiError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Imports are limited to {MAX_IMPORTED_ROWS} accounts"),
+            ));
+        }
+
+        let (line, parsed) = match record {
+            Ok(record) => {
+                let line = record.position().map(|position| position.line()).unwrap_or_default();
+                (line, record.deserialize::<AccountRow>(None))
+            },
+            Err(error) => {
+                let line = error.position().map(|position| position.line()).unwrap_or_default();
+                (line, Err(error))
+            },
+        };
+
+        let row = match parsed {
+            Ok(row) => row,
+            Err(error) => {
+                rows.push(ImportedRow {
+                    line,
+                    alias: String::new(),
+                    account_id: None,
+                    opening_transaction_id: None,
+                    errors: vec![format!("Malformed row, {error}")],
+                });
+                continue;
+            },
+        };
+
+        let mut errors = Vec::new();
+
+        if row.alias.is_empty() {
+            errors.push("Missing alias".to_string());
+        } else if declared.contains_key(&row.alias) {
+            errors.push(format!("Alias {} repeated in this file", row.alias));
+        } else if repos.accounts.fetch_by_alias(&ledger_id, &row.alias).is_some() {
+            errors.push(format!("Alias {} already taken by another account", row.alias));
+        }
+
+        let account_type = match row.account_type.as_deref() {
+            None => AccountType::default(),
+            Some(name) => parse_account_type(name).unwrap_or_else(|| {
+                errors.push(format!("Invalid account type {name}"));
+                AccountType::default()
+            }),
+        };
+
+        let currency = match row.currency.as_deref() {
+            None => accounts::DEFAULT_CURRENCY.to_string(),
+            Some(code) => accounts::parse_currency(code).unwrap_or_else(|| {
+                errors.push(format!("Invalid currency {code}"));
+                code.to_string()
+            }),
+        };
+
+        let parent = row.parent.as_ref().map(|parent_alias| {
+            declared.get(parent_alias).cloned().or_else(|| {
+                repos
+                    .accounts
+                    .fetch_by_alias(&ledger_id, parent_alias)
+                    .map(|parent| (parent.account_id, parent.currency))
+            })
+        });
+
+        let parent_id = match (&row.parent, parent) {
+            (Some(parent_alias), Some(None)) => {
+                errors.push(format!("Parent account {parent_alias} not found"));
+                None
+            },
+            (Some(parent_alias), Some(Some((_, parent_currency)))) if parent_currency != currency => {
+                errors.push(format!("Parent account {parent_alias} holds {parent_currency}"));
+                None
+            },
+            (_, parent) => parent.flatten().map(|(parent_id, _)| parent_id),
+        };
+
+        let opening_balance = row.opening_balance_in_cents.unwrap_or_default();
+
+        if i64::try_from(opening_balance).is_err() {
+            errors.push(format!("Opening balance {opening_balance} out of range"));
+        }
+
+        if opening_balance > 0 && row.alias == opening_balances_alias(&currency) {
+            errors.push("Opening balances equity account can't have an opening balance".to_string());
+        }
+
+        if errors.is_empty() {
+            let account = Account {
+                account_type,
+                currency,
+                parent_id,
+                ..Account::new(&row.alias, 0).within(ledger_id)
+            };
+
+            declared.insert(row.alias.clone(), (account.account_id, account.currency.clone()));
+            valid.push(ValidRow {
+                account,
+                opening_balance,
+            });
+        }
+
+        rows.push(ImportedRow {
+            line,
+            alias: row.alias,
+            account_id: None,
+            opening_transaction_id: None,
+            errors,
+        });
+    }
+
+    Ok((rows, valid))
+}
+
+// Finds the opening balances equity account of a currency, among the ledger accounts or the
+// imported ones, or prepares a new one
+pub fn opening_balances_account(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    imported: &[Account],
+    currency: &str,
+) -> (Uuid, Option<Account>) {
+    let alias = opening_balances_alias(currency);
+
+    let existing = imported
+        .iter()
+        .find(|account| account.alias == alias)
+        .cloned()
+        .or_else(|| repos.accounts.fetch_by_alias(&ledger_id, &alias));
+
+    match existing {
+        Some(existing) => (existing.account_id, None),
+        None => {
+            let equity = Account {
+                account_type: AccountType::Equity,
+                currency: currency.to_string(),
+                ..Account::new(&alias, 0).within(ledger_id)
+            };
+
+            (equity.account_id, Some(equity))
+        },
+    }
+}
+
+pub fn import_accounts(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    csv: &str,
+    dry_run: bool,
+) -> Result<AccountsImportReport, ApiError> {
+    let (mut rows, valid) = validate(repos, ledger_id, csv)?;
+
+    let rejected = rows.iter().filter(|row| !row.errors.is_empty()).count();
+
+    // Nothing is applied while a single row is rejected
+    if dry_run || rejected > 0 {
+        return Ok(AccountsImportReport {
+            dry_run,
+            imported: 0,
+            rejected,
+            rows,
+        });
+    }
+
+    let mut new_accounts: Vec<Account> = valid.iter().map(|row| row.account.clone()).collect();
+    let mut equity_accounts = HashMap::new();
+    let opening: Vec<&ValidRow> = valid.iter().filter(|row| row.opening_balance > 0).collect();
+
+    // Opening balances are booked as transfers from the equity account of their currency
+    for row in &opening {
+        let currency = row.account.currency.as_str();
+
+        if !equity_accounts.contains_key(currency) {
+            let (account_id, created) = opening_balances_account(repos, ledger_id, &new_accounts, currency);
+            new_accounts.extend(created);
+            equity_accounts.insert(currency, account_id);
+        }
+    }
+
+    let transactions = opening
+        .iter()
+        .map(|row| {
+            CreateNewTransaction::opening_balance(
+                equity_accounts[row.account.currency.as_str()],
+                row.account.account_id,
+                OPENING_BALANCE_DESCRIPTION,
+                row.opening_balance,
+            )
+        })
+        .collect();
+
+    // Accounts and opening balances are applied together, or not at all
+    let report = transactions::post_batch_with_accounts(repos, ledger_id, new_accounts, transactions)?;
+
+    if let Some(error) = report.results.iter().find_map(|result| result.error.as_ref()) {
+        tracing::debug!(failed = report.failed, %error, "Opening balances not booked");
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Opening balances could not be booked, {error}"),
+        ));
+    }
+
+    let opening_transactions: HashMap<Uuid, Uuid> = opening
+        .iter()
+        .zip(report.results)
+        .filter_map(|(row, result)| Some((row.account.account_id, result.transaction?.transaction_id)))
+        .collect();
+
+    let accounts_by_alias: HashMap<&str, Uuid> = valid
+        .iter()
+        .map(|row| (row.account.alias.as_str(), row.account.account_id))
+        .collect();
+
+    for row in &mut rows {
+        row.account_id = accounts_by_alias.get(row.alias.as_str()).copied();
+        row.opening_transaction_id = row
+            .account_id
+            .and_then(|account_id| opening_transactions.get(&account_id).copied());
+    }
+
+    Ok(AccountsImportReport {
+        dry_run,
+        imported: valid.len(),
+        rejected,
+        rows,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+pub async fn import_accounts_csv(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Query(query): Query<ImportQuery>,
+    body: String,
+) -> Result<(StatusCode, Json<AccountsImportReport>), ApiError> {
+    let report = import_accounts(&state, ledger_id, &body, query.dry_run)?;
+
+    tracing::debug!(
+        dry_run = report.dry_run,
+        imported = report.imported,
+        rejected = report.rejected,
+        "Accounts imported"
+    );
+
+    let status = if report.rejected > 0 {
+        StatusCode::UNPROCESSABLE_ENTITY
+    } else {
+        StatusCode::OK
+    };
+
+    Ok((status, Json(report)))
+}
@@ -0,0 +1,287 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::journal::BrokenLink;
+use crate::metrics::METRICS;
+use crate::transactions::MovementType;
+use crate::{AppState, Repositories};
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use uuid::Uuid;
+
+// Totals can outgrow JSON numbers, so they're written as decimal strings
+mod decimal_total {
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    pub fn serialize<S: Serializer>(total: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(total)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IntegrityViolation {
+    UnbalancedTransaction {
+        transaction_id: Uuid,
+        #[serde(with = "decimal_total")]
+        debits_in_cents: u128,
+        #[serde(with = "decimal_total")]
+        credits_in_cents: u128,
+    },
+    MissingEntries {
+        transaction_id: Uuid,
+    },
+    UnknownAccount {
+        transaction_id: Uuid,
+        account_id: Uuid,
+    },
+    UnknownTransaction {
+        entry_id: Uuid,
+        transaction_id: Uuid,
+    },
+    CrossLedger {
+        transaction_id: Uuid,
+        account_id: Uuid,
+    },
+    UnbalancedJournal {
+        #[serde(with = "decimal_total")]
+        debits_in_cents: u128,
+        #[serde(with = "decimal_total")]
+        credits_in_cents: u128,
+    },
+    BrokenJournalChain {
+        position: usize,
+        entry_id: Uuid,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IntegrityReport {
+    pub checked_at: DateTime<Utc>,
+    pub transactions_checked: usize,
+    pub entries_checked: usize,
+    pub violations: Vec<IntegrityViolation>,
+}
+
+impl IntegrityReport {
+    pub fn is_healthy(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct Totals {
+    debits_in_cents: u128,
+    credits_in_cents: u128,
+}
+
+// Sums are wide enough for every entry to hold the largest amount, so they never overflow
+impl Totals {
+    fn register(&mut self, movement_type: MovementType, amount_in_cents: u64) {
+        match movement_type {
+            MovementType::Debit => self.debits_in_cents += u128::from(amount_in_cents),
+            MovementType::Credit => self.credits_in_cents += u128::from(amount_in_cents),
+        }
+    }
+}
+
+pub fn verify(repos: &Repositories) -> IntegrityReport {
+    // Same order as postings, accounts are only looked up through the index
+    let transactions = crate::read_lock(&repos.transactions);
+    let journal_entries = crate::read_lock(&repos.journal);
+
+    let mut violations = Vec::new();
+    let mut per_transaction: HashMap<Uuid, Totals> = HashMap::new();
+    let mut journal = Totals::default();
+    let mut entries_checked = 0;
+
+    for entry in journal_entries.iter() {
+        entries_checked += 1;
+        journal.register(entry.movement_type, entry.amount_in_cents);
+        per_transaction
+            .entry(entry.transaction_id)
+            .or_default()
+            .register(entry.movement_type, entry.amount_in_cents);
+
+        if repos.accounts.ledger_of(&entry.account_id).is_none() {
+            violations.push(IntegrityViolation::UnknownAccount {
+                transaction_id: entry.transaction_id,
+                account_id: entry.account_id,
+            });
+        }
+    }
+
+    let mut transactions_checked = 0;
+
+    for transaction in transactions.iter() {
+        transactions_checked += 1;
+        let transaction_id = transaction.transaction_id;
+
+        for account_id in [transaction.lhs_account_id, transaction.rhs_account_id] {
+            match repos.accounts.ledger_of(&account_id) {
+                None => violations.push(IntegrityViolation::UnknownAccount {
+                    transaction_id,
+                    account_id,
+                }),
+                Some(ledger_id) if ledger_id != transaction.ledger_id => {
+                    violations.push(IntegrityViolation::CrossLedger {
+                        transaction_id,
+                        account_id,
+                    })
+                },
+                Some(_) => {},
+            }
+        }
+
+        match per_transaction.remove(&transaction_id) {
+            None => violations.push(IntegrityViolation::MissingEntries { transaction_id }),
+            Some(totals) if totals.debits_in_cents != totals.credits_in_cents => {
+                violations.push(IntegrityViolation::UnbalancedTransaction {
+                    transaction_id,
+                    debits_in_cents: totals.debits_in_cents,
+                    credits_in_cents: totals.credits_in_cents,
+                })
+            },
+            Some(_) => {},
+        }
+    }
+
+    // Whatever remains refers to transactions we don't know about
+    for entry in journal_entries.iter() {
+        if per_transaction.contains_key(&entry.transaction_id) {
+            violations.push(IntegrityViolation::UnknownTransaction {
+                entry_id: entry.entry_id,
+                transaction_id: entry.transaction_id,
+            });
+        }
+    }
+
+    if journal.debits_in_cents != journal.credits_in_cents {
+        violations.push(IntegrityViolation::UnbalancedJournal {
+            debits_in_cents: journal.debits_in_cents,
+            credits_in_cents: journal.credits_in_cents,
+        });
+    }
+
+    match journal_entries.verify_chain().broken_link {
+        Some(
+            BrokenLink::PreviousHashMismatch { position, entry_id, .. }
+            | BrokenLink::HashMismatch { position, entry_id, .. },
+        ) => violations.push(IntegrityViolation::BrokenJournalChain { position, entry_id }),
+        None => {},
+    }
+
+    IntegrityReport {
+        checked_at: Utc::now(),
+        transactions_checked,
+        entries_checked,
+        violations,
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IntegrityStatus {
+    pub runs: u64,
+    pub failed_runs: u64,
+    pub last_report: Option<IntegrityReport>,
+}
+
+#[derive(Default)]
+pub struct IntegrityMonitor {
+    runs: AtomicU64,
+    failed_runs: AtomicU64,
+    last_report: Mutex<Option<IntegrityReport>>,
+}
+
+impl IntegrityMonitor {
+    pub fn record(&self, report: &IntegrityReport) {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+
+        if !report.is_healthy() {
+            self.failed_runs.fetch_add(1, Ordering::Relaxed);
+        }
+
+        *self.last_report.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(report.clone());
+    }
+
+    pub fn runs(&self) -> u64 {
+        self.runs.load(Ordering::Relaxed)
+    }
+
+    pub fn failed_runs(&self) -> u64 {
+        self.failed_runs.load(Ordering::Relaxed)
+    }
+
+    pub fn last_report(&self) -> Option<IntegrityReport> {
+        self.last_report
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn status(&self) -> IntegrityStatus {
+        IntegrityStatus {
+            runs: self.runs(),
+            failed_runs: self.failed_runs(),
+            last_report: self.last_report(),
+        }
+    }
+}
+
+pub fn run_check(state: &AppState) -> IntegrityReport {
+    let report = verify(&state.repos);
+
+    state.integrity.record(&report);
+    METRICS.record_integrity_check(report.violations.len());
+
+    if !report.is_healthy() {
+        tracing::warn!(violations = ?report.violations, "Integrity violations found");
+    }
+
+    report
+}
+
+pub fn check_interval() -> Option<Duration> {
+    std::env::var("INTEGRITY_CHECK_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+}
+
+pub async fn run_periodically(state: AppState, every: Duration) {
+    let mut interval = tokio::time::interval(every);
+
+    loop {
+        interval.tick().await;
+        let report = run_check(&state);
+        tracing::debug!(healthy = report.is_healthy(), "Integrity check finished");
+    }
+}
+
+pub async fn integrity_check(State(state): State<AppState>) -> (StatusCode, Json<IntegrityReport>) {
+    let report = run_check(&state);
+
+    let status = if report.is_healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    (status, Json(report))
+}
+
+pub async fn integrity_status(State(state): State<AppState>) -> Json<IntegrityStatus> {
+    Json(state.integrity.status())
+}
@@ -2,12 +2,19 @@
 // SPDX-License-Identifier: MIT
 
 use crate::SharedState;
-use crate::transactions::MovementType;
-use axum::Json;
-use axum::extract::{Path, State};
+use crate::auth::Principal;
+use crate::errors::ApiError;
+use crate::ledgers::LedgerScope;
+use crate::pagination::{self, Cursor};
+use crate::transactions::{MovementType, TransactionPath};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
+use axum::{Extension, Json};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::ops::{Bound, Range};
 use uuid::Uuid;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -15,42 +22,448 @@ pub struct JournalEntry {
     pub created_at: DateTime<Utc>,
     pub entry_id: Uuid,
     pub transaction_id: Uuid,
+    pub ledger_id: Uuid,
     pub account_id: Uuid,
     pub movement_type: MovementType,
     pub amount_in_cents: u64,
+    // Filled in when the entry is appended to the journal
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Every field but the entry hash itself, in a fixed order
+#[derive(Serialize)]
+struct CanonicalEntry<'a> {
+    previous_hash: &'a str,
+    created_at: &'a DateTime<Utc>,
+    entry_id: &'a Uuid,
+    transaction_id: &'a Uuid,
+    ledger_id: &'a Uuid,
+    account_id: &'a Uuid,
+    movement_type: &'a MovementType,
+    amount_in_cents: u64,
+}
+
+// The canonical form opens with the previous hash, which is plain hex and needs no escaping
+const CANONICAL_OPENING: &[u8] = br#"{"previous_hash":""#;
+
+impl JournalEntry {
+    pub fn compute_hash(&self) -> String {
+        chain_hash(&self.previous_hash, &self.canonical_tail())
+    }
+
+    // The canonical form after the previous hash, which doesn't depend on the chain
+    fn canonical_tail(&self) -> Vec<u8> {
+        let canonical = CanonicalEntry {
+            previous_hash: "",
+            created_at: &self.created_at,
+            entry_id: &self.entry_id,
+            transaction_id: &self.transaction_id,
+            ledger_id: &self.ledger_id,
+            account_id: &self.account_id,
+            movement_type: &self.movement_type,
+            amount_in_cents: self.amount_in_cents,
+        };
+
+        let mut serialized = serde_json::to_vec(&canonical).expect("Cannot serialize journal entry");
+        serialized.drain(..CANONICAL_OPENING.len());
+        serialized
+    }
+}
+
+fn chain_hash(previous_hash: &str, canonical_tail: &[u8]) -> String {
+    let digest = Sha256::new()
+        .chain_update(CANONICAL_OPENING)
+        .chain_update(previous_hash)
+        .chain_update(canonical_tail);
+
+    hex::encode(digest.finalize())
+}
+
+// An entry serialized ahead of appending, so the journal only hashes it onto the chain
+pub struct PreparedEntry {
+    entry: JournalEntry,
+    canonical_tail: Vec<u8>,
+}
+
+impl From<JournalEntry> for PreparedEntry {
+    fn from(entry: JournalEntry) -> Self {
+        PreparedEntry {
+            canonical_tail: entry.canonical_tail(),
+            entry,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BrokenLink {
+    PreviousHashMismatch {
+        position: usize,
+        entry_id: Uuid,
+        expected_previous_hash: String,
+        previous_hash: String,
+    },
+    HashMismatch {
+        position: usize,
+        entry_id: Uuid,
+        expected_hash: String,
+        hash: String,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChainHead {
+    pub entries: usize,
+    pub head_hash: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChainVerification {
+    pub verified_at: DateTime<Utc>,
+    pub entries_checked: usize,
+    pub head_hash: String,
+    pub broken_link: Option<BrokenLink>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct JournalFilter {
+    pub ledger_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub movement_type: Option<MovementType>,
+    pub min_amount: Option<u64>,
+    pub max_amount: Option<u64>,
+    pub from: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub transaction_ids: Vec<Uuid>,
+}
+
+impl JournalFilter {
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        entry.ledger_id == self.ledger_id
+            && self.account_id.is_none_or(|account_id| entry.account_id == account_id)
+            && self
+                .movement_type
+                .is_none_or(|movement_type| entry.movement_type == movement_type)
+            && self.min_amount.is_none_or(|min| entry.amount_in_cents >= min)
+            && self.max_amount.is_none_or(|max| entry.amount_in_cents <= max)
+            && self.from.is_none_or(|from| entry.created_at >= from)
+            && self.until.is_none_or(|until| entry.created_at < until)
+            && (self.transaction_ids.is_empty() || self.transaction_ids.contains(&entry.transaction_id))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JournalPage {
+    pub entries: Vec<JournalEntry>,
+    pub next_cursor: String,
+    pub has_more: bool,
 }
 
 #[derive(Default)]
 pub struct JournalRepository {
     entries: Vec<JournalEntry>,
+    by_transaction: HashMap<Uuid, Vec<usize>>,
+    by_account: HashMap<Uuid, Vec<usize>>,
+    by_time: BTreeSet<(DateTime<Utc>, usize)>,
 }
 
 impl JournalRepository {
     pub fn save_entries(&mut self, entries: Vec<JournalEntry>) {
-        self.entries.extend(entries);
+        self.append_prepared(entries.into_iter().map(PreparedEntry::from).collect());
+    }
+
+    pub fn append_prepared(&mut self, entries: Vec<PreparedEntry>) {
+        for PreparedEntry {
+            mut entry,
+            canonical_tail,
+        } in entries
+        {
+            entry.previous_hash = self.head_hash().to_string();
+            entry.hash = chain_hash(&entry.previous_hash, &canonical_tail);
+
+            let position = self.entries.len();
+            self.by_transaction
+                .entry(entry.transaction_id)
+                .or_default()
+                .push(position);
+            self.by_account.entry(entry.account_id).or_default().push(position);
+            self.by_time.insert((entry.created_at, position));
+            self.entries.push(entry);
+        }
+    }
+
+    fn at_positions<'a>(&'a self, positions: Option<&'a Vec<usize>>) -> impl Iterator<Item = &'a JournalEntry> {
+        positions.into_iter().flatten().map(|position| &self.entries[*position])
     }
 
     pub fn fetch_by_transaction(&self, transaction_id: &Uuid) -> Vec<JournalEntry> {
-        self.entries
-            .iter()
-            .filter(|entry| entry.transaction_id == *transaction_id)
+        self.at_positions(self.by_transaction.get(transaction_id))
             .cloned()
             .collect()
     }
+
+    pub fn fetch_by_account(&self, account_id: &Uuid) -> impl Iterator<Item = &JournalEntry> {
+        self.at_positions(self.by_account.get(account_id))
+    }
+
+    // Positions of entries created within [from, until), either bound being optional
+    fn within_period(
+        &self,
+        from: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let lower = from.map_or(Bound::Unbounded, |from| Bound::Included((from, 0)));
+        let upper = until.map_or(Bound::Unbounded, |until| Bound::Excluded((until, 0)));
+        let empty = from.zip(until).is_some_and(|(from, until)| from > until);
+
+        (!empty)
+            .then(|| self.by_time.range((lower, upper)))
+            .into_iter()
+            .flatten()
+            .map(|(_, position)| *position)
+    }
+
+    // Pages follow the append order, so entries appended meanwhile only ever show up on later pages
+    pub fn query(
+        &self,
+        filter: &JournalFilter,
+        cursor: Cursor,
+        limit: usize,
+        is_visible: impl Fn(&JournalEntry) -> bool,
+    ) -> JournalPage {
+        let (entries, next_position) = self.entries_within(filter, cursor.0..self.entries.len(), limit, is_visible);
+
+        JournalPage {
+            entries,
+            next_cursor: Cursor(next_position.unwrap_or(self.entries.len())).encode(),
+            has_more: next_position.is_some(),
+        }
+    }
+
+    // Up to `limit` matching entries at the given positions, in append order, and where
+    // to continue from when more entries match
+    pub fn entries_within(
+        &self,
+        filter: &JournalFilter,
+        positions: Range<usize>,
+        limit: usize,
+        is_visible: impl Fn(&JournalEntry) -> bool,
+    ) -> (Vec<JournalEntry>, Option<usize>) {
+        let end = positions.end.min(self.entries.len());
+        let start = positions.start.min(end);
+
+        let candidates: Box<dyn Iterator<Item = usize> + '_> = if !filter.transaction_ids.is_empty() {
+            let mut positions: Vec<usize> = filter
+                .transaction_ids
+                .iter()
+                .flat_map(|transaction_id| self.by_transaction.get(transaction_id).into_iter().flatten())
+                .copied()
+                .filter(|position| (start..end).contains(position))
+                .collect();
+            positions.sort_unstable();
+            positions.dedup();
+            Box::new(positions.into_iter())
+        } else if let Some(account_id) = &filter.account_id {
+            let positions = self.by_account.get(account_id).map_or(&[][..], Vec::as_slice);
+            let first = positions.partition_point(|position| *position < start);
+            let last = positions.partition_point(|position| *position < end);
+            Box::new(positions[first..last].iter().copied())
+        } else if filter.from.is_some() || filter.until.is_some() {
+            // Time windows come from the time index, back in append order
+            let mut positions: Vec<usize> = self
+                .within_period(filter.from, filter.until)
+                .filter(|position| (start..end).contains(position))
+                .collect();
+            positions.sort_unstable();
+            Box::new(positions.into_iter())
+        } else {
+            Box::new(start..end)
+        };
+
+        let mut matching = candidates.filter(|position| {
+            let entry = &self.entries[*position];
+            filter.matches(entry) && is_visible(entry)
+        });
+
+        let positions: Vec<usize> = matching.by_ref().take(limit).collect();
+        let has_more = matching.next().is_some();
+
+        let next_position = match positions.last() {
+            Some(last) if has_more => Some(last + 1),
+            _ => None,
+        };
+
+        let entries = positions
+            .into_iter()
+            .map(|position| self.entries[position].clone())
+            .collect();
+
+        (entries, next_position)
+    }
+
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn head_hash(&self) -> &str {
+        self.entries.last().map_or(GENESIS_HASH, |entry| entry.hash.as_str())
+    }
+
+    pub fn head(&self) -> ChainHead {
+        ChainHead {
+            entries: self.entries.len(),
+            head_hash: self.head_hash().to_string(),
+        }
+    }
+
+    // Walks the chain from the genesis hash, stopping at the first broken link, which counts as checked
+    pub fn verify_chain(&self) -> ChainVerification {
+        let mut expected_previous_hash = GENESIS_HASH;
+        let mut broken_link = None;
+        let mut entries_checked = 0;
+
+        for (position, entry) in self.entries.iter().enumerate() {
+            entries_checked = position + 1;
+
+            if entry.previous_hash != expected_previous_hash {
+                broken_link = Some(BrokenLink::PreviousHashMismatch {
+                    position,
+                    entry_id: entry.entry_id,
+                    expected_previous_hash: expected_previous_hash.to_string(),
+                    previous_hash: entry.previous_hash.clone(),
+                });
+                break;
+            }
+
+            let expected_hash = entry.compute_hash();
+
+            if entry.hash != expected_hash {
+                broken_link = Some(BrokenLink::HashMismatch {
+                    position,
+                    entry_id: entry.entry_id,
+                    expected_hash,
+                    hash: entry.hash.clone(),
+                });
+                break;
+            }
+
+            expected_previous_hash = &entry.hash;
+        }
+
+        ChainVerification {
+            verified_at: Utc::now(),
+            entries_checked,
+            head_hash: self.head_hash().to_string(),
+            broken_link,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn entry_mut(&mut self, position: usize) -> &mut JournalEntry {
+        &mut self.entries[position]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter()
+    }
 }
 
 pub async fn entries_for_transaction(
     State(state): State<SharedState>,
-    Path(transaction_id): Path<Uuid>,
-) -> Result<Json<Vec<JournalEntry>>, StatusCode> {
-    let repos = state.read().expect("Cannot acquire shared state");
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(TransactionPath { transaction_id }): Path<TransactionPath>,
+) -> Result<Json<Vec<JournalEntry>>, ApiError> {
+    let entries: Vec<JournalEntry> = crate::read_lock(&state.journal)
+        .fetch_by_transaction(&transaction_id)
+        .into_iter()
+        .filter(|entry| entry.ledger_id == ledger_id)
+        .collect();
 
-    let entries = repos.journal.fetch_by_transaction(&transaction_id);
+    let owned = entries.iter().any(|entry| principal.owns(&entry.account_id));
 
-    if entries.is_empty() {
-        tracing::debug!("No entries for transaction -> transaction_id = {:?}", &transaction_id);
-        return Err(StatusCode::NOT_FOUND);
+    if !owned {
+        tracing::debug!(%transaction_id, "No entries for transaction");
+        return Err(ApiError::not_found(format!(
+            "No entries for transaction {transaction_id}"
+        )));
     }
 
     Ok(Json(entries))
 }
+
+#[derive(Debug, Deserialize)]
+pub struct JournalQuery {
+    account_id: Option<Uuid>,
+    movement_type: Option<MovementType>,
+    min_amount: Option<u64>,
+    max_amount: Option<u64>,
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    // Comma-separated
+    transaction_ids: Option<String>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+fn parse_transaction_ids(raw: Option<&str>) -> Result<Vec<Uuid>, ApiError> {
+    raw.into_iter()
+        .flat_map(|raw| raw.split(','))
+        .filter(|raw_id| !raw_id.trim().is_empty())
+        .map(|raw_id| {
+            Uuid::parse_str(raw_id.trim())
+                .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid transaction id {raw_id}")))
+        })
+        .collect()
+}
+
+pub async fn query_entries(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Query(query): Query<JournalQuery>,
+) -> Result<Json<JournalPage>, ApiError> {
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
+    let limit = pagination::page_size(query.limit)?;
+
+    let filter = JournalFilter {
+        ledger_id,
+        account_id: query.account_id,
+        movement_type: query.movement_type,
+        min_amount: query.min_amount,
+        max_amount: query.max_amount,
+        from: query.from,
+        until: query.until,
+        transaction_ids: parse_transaction_ids(query.transaction_ids.as_deref())?,
+    };
+
+    let page =
+        crate::read_lock(&state.journal).query(&filter, cursor, limit, |entry| principal.owns(&entry.account_id));
+
+    tracing::debug!(
+        ?filter,
+        returned = page.entries.len(),
+        has_more = page.has_more,
+        "Journal queried"
+    );
+    Ok(Json(page))
+}
+
+pub async fn chain_head(State(state): State<SharedState>) -> Json<ChainHead> {
+    Json(crate::read_lock(&state.journal).head())
+}
+
+pub async fn verify_chain(State(state): State<SharedState>) -> (StatusCode, Json<ChainVerification>) {
+    let verification = crate::read_lock(&state.journal).verify_chain();
+
+    match &verification.broken_link {
+        None => (StatusCode::OK, Json(verification)),
+        Some(broken_link) => {
+            tracing::error!(?broken_link, "Journal hash chain is broken");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(verification))
+        },
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::auth::{Ownership, Principal, Scope};
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use std::collections::{BTreeSet, HashSet};
+use uuid::Uuid;
+
+pub const SERVICE_SCOPE: &str = "ledger:service";
+const DEFAULT_ACCOUNTS_CLAIM: &str = "accounts";
+
+struct VerificationKey {
+    key_id: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+pub struct JwtVerifier {
+    keys: Vec<VerificationKey>,
+    accounts_claim: String,
+}
+
+impl JwtVerifier {
+    pub fn with_key(algorithm: Algorithm, key: DecodingKey) -> Self {
+        JwtVerifier {
+            keys: vec![VerificationKey {
+                key_id: None,
+                algorithm,
+                key,
+            }],
+            accounts_claim: DEFAULT_ACCOUNTS_CLAIM.to_string(),
+        }
+    }
+
+    pub fn with_jwks(jwks: &JwkSet) -> Result<Self, String> {
+        let mut keys = Vec::new();
+
+        for jwk in &jwks.keys {
+            let algorithm = match &jwk.algorithm {
+                AlgorithmParameters::RSA(_) => Algorithm::RS256,
+                AlgorithmParameters::OctetKeyPair(params) if params.curve == EllipticCurve::Ed25519 => Algorithm::EdDSA,
+                AlgorithmParameters::OctetKey(_) => Algorithm::HS256,
+                _ => return Err(format!("Unsupported JWK | kid = {:?}", jwk.common.key_id)),
+            };
+
+            let key = DecodingKey::from_jwk(jwk).map_err(|error| error.to_string())?;
+
+            keys.push(VerificationKey {
+                key_id: jwk.common.key_id.clone(),
+                algorithm,
+                key,
+            });
+        }
+
+        if keys.is_empty() {
+            return Err("JWKS without keys".to_string());
+        }
+
+        Ok(JwtVerifier {
+            keys,
+            accounts_claim: DEFAULT_ACCOUNTS_CLAIM.to_string(),
+        })
+    }
+
+    pub fn with_accounts_claim(self, accounts_claim: &str) -> Self {
+        JwtVerifier {
+            accounts_claim: accounts_claim.to_string(),
+            ..self
+        }
+    }
+
+    pub fn from_env() -> Option<Result<Self, String>> {
+        let verifier = match (std::env::var("JWT_JWKS_FILE"), std::env::var("JWT_KEY_FILE")) {
+            (Ok(jwks_file), _) => load_jwks(&jwks_file),
+            (_, Ok(key_file)) => load_key(&key_file, &std::env::var("JWT_ALGORITHM").unwrap_or_default()),
+            _ => return None,
+        };
+
+        let accounts_claim = std::env::var("JWT_ACCOUNTS_CLAIM").unwrap_or_else(|_| DEFAULT_ACCOUNTS_CLAIM.to_string());
+        Some(verifier.map(|verifier| verifier.with_accounts_claim(&accounts_claim)))
+    }
+
+    pub fn verify(&self, token: &str) -> Option<Principal> {
+        let header = jsonwebtoken::decode_header(token).ok()?;
+
+        let verification_key = self
+            .keys
+            .iter()
+            .filter(|candidate| candidate.algorithm == header.alg)
+            .find(|candidate| header.kid.is_none() || candidate.key_id.is_none() || candidate.key_id == header.kid)?;
+
+        let validation = Validation::new(verification_key.algorithm);
+
+        let claims = jsonwebtoken::decode::<serde_json::Map<String, serde_json::Value>>(
+            token,
+            &verification_key.key,
+            &validation,
+        )
+        .inspect_err(|error| tracing::debug!(%error, "Invalid JWT"))
+        .ok()?
+        .claims;
+
+        let scope_claim = claims.get("scope").and_then(|value| value.as_str());
+        let granted: HashSet<&str> = scope_claim.unwrap_or_default().split_whitespace().collect();
+
+        if granted.contains(SERVICE_SCOPE) {
+            return Some(Principal {
+                key_id: None,
+                scopes: Scope::all(),
+                ownership: Ownership::Unrestricted,
+            });
+        }
+
+        let owned_accounts = claims
+            .get(&self.accounts_claim)
+            .and_then(|value| value.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_str())
+                    .filter_map(|value| Uuid::parse_str(value).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // End users only ever read their own data, a scope claim can narrow that further
+        let scopes: BTreeSet<Scope> = Scope::reads()
+            .into_iter()
+            .filter(|scope| scope_claim.is_none() || granted.contains(scope.name()))
+            .collect();
+
+        Some(Principal {
+            key_id: None,
+            scopes,
+            ownership: Ownership::Accounts(owned_accounts),
+        })
+    }
+}
+
+fn load_jwks(path: &str) -> Result<JwtVerifier, String> {
+    let contents = std::fs::read_to_string(path).map_err(|error| format!("Cannot read {path} : {error}"))?;
+    let jwks: JwkSet = serde_json::from_str(&contents).map_err(|error| format!("Invalid JWKS at {path} : {error}"))?;
+    JwtVerifier::with_jwks(&jwks)
+}
+
+fn load_key(path: &str, algorithm: &str) -> Result<JwtVerifier, String> {
+    let contents = std::fs::read(path).map_err(|error| format!("Cannot read {path} : {error}"))?;
+
+    let (algorithm, key) = match algorithm {
+        "HS256" => (Algorithm::HS256, Ok(DecodingKey::from_secret(contents.trim_ascii()))),
+        "RS256" => (Algorithm::RS256, DecodingKey::from_rsa_pem(&contents)),
+        "EdDSA" => (Algorithm::EdDSA, DecodingKey::from_ed_pem(&contents)),
+        other => return Err(format!("Unsupported JWT_ALGORITHM : {other:?}")),
+    };
+
+    let key = key.map_err(|error| format!("Invalid key at {path} : {error}"))?;
+    Ok(JwtVerifier::with_key(algorithm, key))
+}
@@ -0,0 +1,127 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::SharedState;
+use crate::errors::ApiError;
+use axum::Json;
+use axum::extract::{FromRef, FromRequestParts, Path, RawPathParams, State};
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const DEFAULT_LEDGER_ID: Uuid = Uuid::nil();
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateNewLedger {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Ledger {
+    pub ledger_id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct LedgersRepository {
+    ledgers: Vec<Ledger>,
+}
+
+impl Default for LedgersRepository {
+    fn default() -> Self {
+        let default_ledger = Ledger {
+            ledger_id: DEFAULT_LEDGER_ID,
+            name: "default".to_string(),
+            created_at: Utc::now(),
+        };
+
+        LedgersRepository {
+            ledgers: vec![default_ledger],
+        }
+    }
+}
+
+impl LedgersRepository {
+    pub fn save_ledger(&mut self, ledger: Ledger) {
+        self.ledgers.push(ledger);
+    }
+
+    pub fn fetch_by_id(&self, ledger_id: &Uuid) -> Option<&Ledger> {
+        self.ledgers.iter().find(|ledger| ledger.ledger_id == *ledger_id)
+    }
+
+    pub fn list(&self) -> Vec<Ledger> {
+        self.ledgers.clone()
+    }
+}
+
+// Resolves the ledger from the `ledger_id` path parameter, falling back to the default ledger
+#[derive(Clone, Copy, Debug)]
+pub struct LedgerScope(pub Uuid);
+
+impl<S> FromRequestParts<S> for LedgerScope
+where
+    SharedState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let params = RawPathParams::from_request_parts(parts, state)
+            .await
+            .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, "Invalid path parameters"))?;
+
+        let Some((_, raw_ledger_id)) = params.iter().find(|(name, _)| *name == "ledger_id") else {
+            return Ok(LedgerScope(DEFAULT_LEDGER_ID));
+        };
+
+        let ledger_id = Uuid::parse_str(raw_ledger_id)
+            .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid ledger id {raw_ledger_id}")))?;
+
+        let shared_state = SharedState::from_ref(state);
+
+        if crate::read_lock(&shared_state.ledgers)
+            .fetch_by_id(&ledger_id)
+            .is_none()
+        {
+            tracing::debug!(%ledger_id, "Ledger not found");
+            return Err(ApiError::not_found(format!("Ledger {ledger_id} not found")));
+        }
+
+        Ok(LedgerScope(ledger_id))
+    }
+}
+
+pub async fn new_ledger(
+    State(state): State<SharedState>,
+    Json(payload): Json<CreateNewLedger>,
+) -> Result<Json<Ledger>, ApiError> {
+    let ledger = Ledger {
+        ledger_id: Uuid::new_v4(),
+        name: payload.name,
+        created_at: Utc::now(),
+    };
+
+    crate::write_lock(&state.ledgers).save_ledger(ledger.clone());
+
+    tracing::debug!(ledger_id = %ledger.ledger_id, "Ledger created");
+    Ok(Json(ledger))
+}
+
+pub async fn list_ledgers(State(state): State<SharedState>) -> Json<Vec<Ledger>> {
+    Json(crate::read_lock(&state.ledgers).list())
+}
+
+pub async fn ledger_details(
+    State(state): State<SharedState>,
+    Path(ledger_id): Path<Uuid>,
+) -> Result<Json<Ledger>, ApiError> {
+    let Some(ledger) = crate::read_lock(&state.ledgers).fetch_by_id(&ledger_id).cloned() else {
+        tracing::debug!(%ledger_id, "Ledger not found");
+        return Err(ApiError::not_found(format!("Ledger {ledger_id} not found")));
+    };
+
+    Ok(Json(ledger))
+}
@@ -0,0 +1,4139 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+pub mod accounts;
+pub mod auth;
+pub mod balances;
+pub mod bank_statements;
+pub mod checkpoints;
+pub mod client;
+pub mod errors;
+pub mod events;
+pub mod exports;
+pub mod imports;
+pub mod integrity;
+pub mod journal;
+pub mod jwt;
+pub mod ledgers;
+pub mod merkle;
+pub mod metrics;
+pub mod outbox;
+pub mod pagination;
+pub mod plaintext;
+pub mod probes;
+pub mod reconciliation;
+pub mod shutdown;
+pub mod statements;
+pub mod telemetry;
+pub mod transactions;
+pub mod webhooks;
+
+use crate::accounts::AccountsRepository;
+use crate::auth::{Authenticator, Scope};
+use crate::bank_statements::ExternalTransactionsRepository;
+use crate::checkpoints::CheckpointsRepository;
+use crate::events::{EventBus, EventPayload};
+use crate::integrity::IntegrityMonitor;
+use crate::journal::JournalRepository;
+use crate::ledgers::LedgersRepository;
+use crate::metrics::{LockMode, METRICS};
+use crate::outbox::OutboxRepository;
+use crate::probes::Lifecycle;
+use crate::reconciliation::ReconciliationRepository;
+use crate::statements::StatementSigner;
+use crate::transactions::TransactionsRepository;
+use crate::webhooks::{WebhookDispatcher, WebhooksRepository};
+use axum::Router;
+use axum::extract::FromRef;
+use axum::middleware;
+use axum::routing::{delete, get, post};
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::Arc;
+use std::time::Instant;
+use tower::ServiceBuilder;
+use tower_http::catch_panic::CatchPanicLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use uuid::Uuid;
+
+pub type SharedState = Arc<Repositories>;
+
+// Accounts are locked individually, postings acquire them in id order, append to
+// transactions and journal, release those and then record events, always in that order
+#[derive(Default)]
+pub struct Repositories {
+    pub ledgers: RwLock<LedgersRepository>,
+    pub accounts: AccountsRepository,
+    pub transactions: RwLock<TransactionsRepository>,
+    pub journal: RwLock<JournalRepository>,
+    pub checkpoints: RwLock<CheckpointsRepository>,
+    pub events: EventBus,
+    pub outbox: RwLock<OutboxRepository>,
+    pub webhooks: RwLock<WebhooksRepository>,
+    pub external_transactions: RwLock<ExternalTransactionsRepository>,
+    pub reconciliations: RwLock<ReconciliationRepository>,
+}
+
+impl Repositories {
+    // Holding the outbox while publishing keeps it in sequence order
+    pub fn record_events(&self, ledger_id: Uuid, payloads: Vec<EventPayload>) {
+        let mut outbox = write_lock(&self.outbox);
+        let events = self.events.publish(ledger_id, payloads);
+        outbox.append(events);
+    }
+}
+
+fn read_lock<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    let started = Instant::now();
+    let guard = lock.read();
+    METRICS.record_lock_wait(LockMode::Read, started.elapsed());
+    guard
+}
+
+fn write_lock<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    let started = Instant::now();
+    let guard = lock.write();
+    METRICS.record_lock_wait(LockMode::Write, started.elapsed());
+    guard
+}
+
+#[derive(Clone, Default)]
+pub struct AppState {
+    pub repos: SharedState,
+    pub lifecycle: Arc<Lifecycle>,
+    pub integrity: Arc<IntegrityMonitor>,
+    pub auth: Arc<Authenticator>,
+    pub signer: Option<Arc<StatementSigner>>,
+    pub webhooks: Arc<WebhookDispatcher>,
+}
+
+impl From<SharedState> for AppState {
+    fn from(repos: SharedState) -> Self {
+        AppState {
+            repos,
+            ..AppState::default()
+        }
+    }
+}
+
+impl FromRef<AppState> for SharedState {
+    fn from_ref(state: &AppState) -> Self {
+        state.repos.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Lifecycle> {
+    fn from_ref(state: &AppState) -> Self {
+        state.lifecycle.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Authenticator> {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth.clone()
+    }
+}
+
+pub fn app(state: AppState) -> Router {
+    let requires = |scope| middleware::from_fn_with_state(scope, auth::require_scope);
+
+    let ledger_routes = || {
+        Router::new()
+            .route(
+                "/accounts/new",
+                post(accounts::new_account).route_layer(requires(Scope::AccountsWrite)),
+            )
+            .route(
+                "/accounts/import",
+                post(imports::import_accounts_csv).route_layer(requires(Scope::AccountsWrite)),
+            )
+            .route(
+                "/accounts/{account_id}",
+                get(accounts::account_details).route_layer(requires(Scope::AccountsRead)),
+            )
+            .route(
+                "/accounts/{account_id}/proof",
+                get(checkpoints::account_proof).route_layer(requires(Scope::AccountsRead)),
+            )
+            .route(
+                "/accounts/{account_id}/statement",
+                get(statements::account_statement).route_layer(requires(Scope::AccountsRead)),
+            )
+            .route(
+                "/accounts/{account_id}/external-transactions",
+                get(bank_statements::list_external_transactions).route_layer(requires(Scope::AccountsRead)),
+            )
+            .route(
+                "/accounts/{account_id}/external-transactions/import",
+                post(bank_statements::import_bank_statement).route_layer(requires(Scope::AccountsWrite)),
+            )
+            .route(
+                "/accounts/{account_id}/reconciliation",
+                get(reconciliation::reconciliation_report).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/accounts/{account_id}/reconciliation/run",
+                post(reconciliation::run_reconciliation).route_layer(requires(Scope::AccountsWrite)),
+            )
+            .route(
+                "/accounts/{account_id}/reconciliation/matches",
+                post(reconciliation::new_match).route_layer(requires(Scope::AccountsWrite)),
+            )
+            .route(
+                "/accounts/{account_id}/reconciliation/matches/{match_id}",
+                delete(reconciliation::delete_match).route_layer(requires(Scope::AccountsWrite)),
+            )
+            .route(
+                "/balances/live",
+                get(balances::balance_updates).route_layer(requires(Scope::AccountsRead)),
+            )
+            .route(
+                "/checkpoints",
+                get(checkpoints::list_checkpoints).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/checkpoints/new",
+                post(checkpoints::new_checkpoint).route_layer(requires(Scope::LedgersWrite)),
+            )
+            .route(
+                "/checkpoints/{checkpoint_id}",
+                get(checkpoints::checkpoint_details).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/plaintext/export",
+                get(plaintext::export_plaintext).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/plaintext/import",
+                post(plaintext::import_plaintext).route_layer(requires(Scope::LedgersWrite)),
+            )
+            .route(
+                "/transactions",
+                get(transactions::list_transactions).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/transactions/new",
+                post(transactions::new_transaction).route_layer(requires(Scope::TransactionsWrite)),
+            )
+            .route(
+                "/transactions/export",
+                get(exports::export_transactions).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/transactions/batch",
+                post(transactions::new_transactions_batch).route_layer(requires(Scope::TransactionsWrite)),
+            )
+            .route(
+                "/transactions/{transaction_id}",
+                get(transactions::transaction_details).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/transactions/{transaction_id}/reverse",
+                post(transactions::reverse_transaction).route_layer(requires(Scope::TransactionsWrite)),
+            )
+            .route(
+                "/events",
+                get(events::stream_events).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/journal",
+                get(journal::query_entries).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/journal/export",
+                get(exports::export_journal).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/journal/{transaction_id}",
+                get(journal::entries_for_transaction).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/outbox",
+                get(outbox::pending_outbox).route_layer(requires(Scope::ReportsRead)),
+            )
+            .route(
+                "/webhooks",
+                get(webhooks::list_webhooks).route_layer(requires(Scope::WebhooksAdmin)),
+            )
+            .route(
+                "/webhooks/new",
+                post(webhooks::new_webhook).route_layer(requires(Scope::WebhooksAdmin)),
+            )
+            .route(
+                "/webhooks/{webhook_id}",
+                get(webhooks::webhook_details)
+                    .delete(webhooks::delete_webhook)
+                    .route_layer(requires(Scope::WebhooksAdmin)),
+            )
+            .route(
+                "/webhooks/{webhook_id}/deliveries",
+                get(webhooks::webhook_deliveries).route_layer(requires(Scope::WebhooksAdmin)),
+            )
+            .route(
+                "/webhooks/{webhook_id}/dead-letters",
+                get(webhooks::webhook_dead_letters).route_layer(requires(Scope::WebhooksAdmin)),
+            )
+            .route(
+                "/webhooks/{webhook_id}/dead-letters/{delivery_id}/redeliver",
+                post(webhooks::redeliver_dead_letter).route_layer(requires(Scope::WebhooksAdmin)),
+            )
+    };
+
+    let protected = Router::new()
+        .route(
+            "/integrity",
+            get(integrity::integrity_check).route_layer(requires(Scope::JournalAdmin)),
+        )
+        .route(
+            "/integrity/status",
+            get(integrity::integrity_status).route_layer(requires(Scope::ReportsRead)),
+        )
+        .route(
+            "/journal/head",
+            get(journal::chain_head).route_layer(requires(Scope::JournalAdmin)),
+        )
+        .route(
+            "/journal/verify",
+            get(journal::verify_chain).route_layer(requires(Scope::JournalAdmin)),
+        )
+        .route(
+            "/ledgers",
+            get(ledgers::list_ledgers).route_layer(requires(Scope::ReportsRead)),
+        )
+        .route(
+            "/ledgers/new",
+            post(ledgers::new_ledger).route_layer(requires(Scope::LedgersWrite)),
+        )
+        .route(
+            "/ledgers/{ledger_id}",
+            get(ledgers::ledger_details).route_layer(requires(Scope::ReportsRead)),
+        )
+        .merge(ledger_routes())
+        .nest("/ledgers/{ledger_id}", ledger_routes())
+        .route(
+            "/admin/keys",
+            post(auth::new_api_key)
+                .get(auth::list_api_keys)
+                .route_layer(requires(Scope::KeysAdmin)),
+        )
+        .route(
+            "/admin/keys/{key_id}",
+            delete(auth::revoke_api_key).route_layer(requires(Scope::KeysAdmin)),
+        )
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::authenticate));
+
+    Router::new()
+        .route("/healthz", get(probes::liveness))
+        .route("/readyz", get(probes::readiness))
+        .route("/metrics", get(metrics::export_metrics))
+        .route("/keys", get(statements::public_keys))
+        .merge(protected)
+        .layer(
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(telemetry::request_span)
+                        .on_response(telemetry::record_response),
+                )
+                .layer(PropagateRequestIdLayer::x_request_id())
+                .layer(middleware::from_fn(errors::structured_errors))
+                .layer(middleware::from_fn(metrics::track_requests))
+                .layer(CatchPanicLayer::new()),
+        )
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::accounts::{Account, AccountType, AccountsRepository, CreateNewAccount};
+    use crate::auth::{ApiKey, Authenticator, CreateNewApiKey, CreatedApiKey, Scope};
+    use crate::balances::BalanceMessage;
+    use crate::bank_statements::{self, BankStatementFormat, BankStatementImport, ExternalTransaction};
+    use crate::checkpoints::{BalanceProof, Checkpoint, CheckpointsRepository};
+    use crate::client::{self, ExportOptions, ServerOptions};
+    use crate::errors::ErrorBody;
+    use crate::events::{EventBus, EventPayload, LAST_EVENT_ID_HEADER, LedgerEvent};
+    use crate::exports::{self, ExportFormat, Exported, JournalEntryRow, TransactionRow};
+    use crate::imports::{self, AccountsImportReport};
+    use crate::integrity::{IntegrityReport, IntegrityStatus, IntegrityViolation};
+    use crate::journal::{
+        BrokenLink, ChainHead, ChainVerification, JournalEntry, JournalFilter, JournalPage, JournalRepository,
+    };
+    use crate::jwt::JwtVerifier;
+    use crate::ledgers::{CreateNewLedger, DEFAULT_LEDGER_ID, Ledger};
+    use crate::outbox::{self, OutboxRepository, OutboxSink, PendingOutbox};
+    use crate::plaintext::PlainTextImportReport;
+    use crate::reconciliation::{
+        self, CreateNewMatch, MatchKind, MatchMethod, ReconciliationMatch, ReconciliationReport,
+    };
+    use crate::shutdown::{self, ShutdownOutcome};
+    use crate::statements::{PublicKeys, SignedStatement, StatementSigner, verify_statement};
+    use crate::transactions::{
+        self, BatchItemOutcome, BatchReport, CreateNewTransaction, CreateTransactionsBatch, CreatedTransaction,
+        MovementType, TransactionsPage,
+    };
+    use crate::webhooks::{
+        self, CreateNewWebhook, DeadLetter, DeliveryAttempt, RetryPolicy, Webhook, WebhookDispatcher,
+    };
+    use crate::{AppState, Repositories, SharedState, app};
+    use axum::body::{Body, to_bytes};
+    use chrono::{NaiveDate, SecondsFormat, TimeDelta, Utc};
+    use ed25519_dalek::SigningKey;
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
+    use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+    use futures_util::SinkExt;
+    use http::{Method, Request, StatusCode, header};
+    use jsonwebtoken::jwk::JwkSet;
+    use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header};
+    use serde::Serialize;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::oneshot;
+    use tokio::task::JoinHandle;
+    use tokio_stream::StreamExt;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn post_request(endpoint: &str, payload: impl Serialize) -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .header(header::CONTENT_TYPE, "application/json")
+            .uri(endpoint)
+            .body(Body::from(json!(payload).to_string()))
+            .unwrap()
+    }
+
+    fn with_api_key(mut request: Request<Body>, api_key: &str) -> Request<Body> {
+        request.headers_mut().insert("x-api-key", api_key.parse().unwrap());
+        request
+    }
+
+    fn with_bearer(mut request: Request<Body>, token: &str) -> Request<Body> {
+        let authorization = format!("Bearer {token}");
+        request
+            .headers_mut()
+            .insert(header::AUTHORIZATION, authorization.parse().unwrap());
+        request
+    }
+
+    fn signed_token(header: &Header, secret: &[u8], claims: serde_json::Value) -> String {
+        let expires_at = (Utc::now() + chrono::Duration::hours(1)).timestamp();
+        let mut claims = claims;
+        claims["exp"] = json!(expires_at);
+        jsonwebtoken::encode(header, &claims, &EncodingKey::from_secret(secret)).unwrap()
+    }
+
+    fn get_request(endpoint: &str) -> Request<Body> {
+        Request::builder()
+            .method(Method::GET)
+            .header(header::CONTENT_TYPE, "application/json")
+            .uri(endpoint)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    async fn spawn_server(
+        state: impl Into<AppState>,
+        drain_timeout: Duration,
+    ) -> (
+        SocketAddr,
+        oneshot::Sender<()>,
+        JoinHandle<std::io::Result<ShutdownOutcome>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (trigger, signal) = oneshot::channel::<()>();
+
+        let server = tokio::spawn(shutdown::serve(
+            listener,
+            app(state.into()),
+            async move {
+                let _ = signal.await;
+            },
+            drain_timeout,
+        ));
+
+        (address, trigger, server)
+    }
+
+    fn raw_post_request_head(endpoint: &str, content_length: usize) -> String {
+        format!(
+            "POST {endpoint} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {content_length}\r\nConnection: close\r\n\r\n"
+        )
+    }
+
+    #[tokio::test]
+    async fn should_report_account_not_found() {
+        // Given
+        let shared_state = SharedState::default();
+        let app = app(shared_state.into());
+
+        // When
+        let get_account = format!("/accounts/{}", Uuid::new_v4());
+        let request = get_request(&get_account);
+        let response = app.oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn should_create_new_account_with_success() {
+        // Given
+        let shared_state = SharedState::default();
+        let app = app(shared_state.into());
+
+        // When
+        let new_account = json!(CreateNewAccount {
+            alias: "ufs.main".to_string(),
+            balance: Some(100000),
+            ..CreateNewAccount::default()
+        });
+
+        let request = post_request("/accounts/new", new_account);
+        let response = app.oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_not_create_account_with_existing_alias() {
+        // Given
+        let existing_account = Account::new("ufs.savings", 10000);
+
+        let accounts_repository = AccountsRepository::from(vec![existing_account.clone()]);
+
+        let repos = Repositories {
+            accounts: accounts_repository,
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+        let app = app(shared_state.into());
+
+        // When
+        let new_account = json!(CreateNewAccount {
+            alias: "ufs.savings".to_string(),
+            balance: None,
+            ..CreateNewAccount::default()
+        });
+
+        let request = post_request("/accounts/new", new_account);
+        let response = app.oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn should_report_transaction_not_found() {
+        // Given
+        let shared_state = SharedState::default();
+        let app = app(shared_state.into());
+
+        // When
+        let transaction_by_id = format!("/transactions/{}", Uuid::new_v4());
+        let request = get_request(&transaction_by_id);
+        let response = app.oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn should_create_new_transaction_with_success() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let accounts_repository = AccountsRepository::from(vec![savings_account, main_account]);
+
+        let repos = Repositories {
+            accounts: accounts_repository,
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+        let app = app(shared_state.clone().into());
+
+        // When
+        let new_transaction = json!(CreateNewTransaction::new_debit(
+            savings_account_id,
+            main_account_id,
+            "emergency",
+            10000
+        ));
+
+        let request = post_request("/transactions/new", new_transaction);
+        let response = app.oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Given
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let tx: CreatedTransaction = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        // When
+        let entries_by_transaction = format!("/journal/{}", tx.transaction_id);
+        let request = get_request(&entries_by_transaction);
+
+        let app = crate::app(shared_state.into());
+        let response = app.oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let entries: Vec<JournalEntry> = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_reject_transaction_over_non_existing_account() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+
+        let savings_account_id = savings_account.account_id;
+        let non_existing_account_id = Uuid::new_v4();
+
+        let accounts_repository = AccountsRepository::from(vec![savings_account]);
+
+        let repos = Repositories {
+            accounts: accounts_repository,
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+        let app = app(shared_state.into());
+
+        // When
+        let new_transaction = json!(CreateNewTransaction::new_credit(
+            savings_account_id,
+            non_existing_account_id,
+            "deposit",
+            10000
+        ));
+
+        let request = post_request("/transactions/new", new_transaction);
+        let response = app.oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn should_reject_transaction_with_insufficient_balance() {
+        // Given
+        let amount_to_transfer = 100000;
+        let amount_available = 100;
+
+        let savings_account = Account::new("ufs.savings", amount_available);
+        let main_account = Account::new("ufs.main", 0);
+
+        // Save these IDs for later
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let accounts_repository = AccountsRepository::from(vec![savings_account, main_account]);
+
+        let repos = Repositories {
+            accounts: accounts_repository,
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+        let app = app(shared_state.into());
+
+        // When
+        let new_transaction = json!(CreateNewTransaction::new_debit(
+            savings_account_id,
+            main_account_id,
+            "emergency",
+            amount_to_transfer
+        ));
+
+        let request = post_request("/transactions/new", new_transaction);
+        let response = app.oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn should_commit_transaction_posted_while_draining() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let accounts_repository = AccountsRepository::from(vec![savings_account, main_account]);
+
+        let repos = Repositories {
+            accounts: accounts_repository,
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+        let (address, trigger, server) = spawn_server(shared_state.clone(), Duration::from_secs(5)).await;
+
+        let payload = json!(CreateNewTransaction::new_debit(
+            savings_account_id,
+            main_account_id,
+            "emergency",
+            10000
+        ))
+        .to_string();
+
+        let (first_half, second_half) = payload.split_at(payload.len() / 2);
+
+        // When
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let head = raw_post_request_head("/transactions/new", payload.len());
+        client.write_all(head.as_bytes()).await.unwrap();
+        client.write_all(first_half.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        trigger.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        client.write_all(second_half.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+
+        // Then
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(server.await.unwrap().unwrap(), ShutdownOutcome::Drained);
+        assert!(TcpStream::connect(address).await.is_err());
+
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let tx: CreatedTransaction = serde_json::from_str(body).unwrap();
+
+        assert_eq!(
+            shared_state
+                .journal
+                .read()
+                .fetch_by_transaction(&tx.transaction_id)
+                .len(),
+            2
+        );
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&savings_account_id).unwrap().balance,
+            90000
+        );
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&main_account_id).unwrap().balance,
+            60000
+        );
+    }
+
+    #[tokio::test]
+    async fn should_abort_transaction_not_completed_within_drain_timeout() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let accounts_repository = AccountsRepository::from(vec![savings_account, main_account]);
+
+        let repos = Repositories {
+            accounts: accounts_repository,
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+        let (address, trigger, server) = spawn_server(shared_state.clone(), Duration::from_millis(200)).await;
+
+        let payload = json!(CreateNewTransaction::new_debit(
+            savings_account_id,
+            main_account_id,
+            "emergency",
+            10000
+        ))
+        .to_string();
+
+        let (first_half, _) = payload.split_at(payload.len() / 2);
+
+        // When
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let head = raw_post_request_head("/transactions/new", payload.len());
+        client.write_all(head.as_bytes()).await.unwrap();
+        client.write_all(first_half.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        trigger.send(()).unwrap();
+
+        // Then
+        assert_eq!(server.await.unwrap().unwrap(), ShutdownOutcome::TimedOut);
+
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&savings_account_id).unwrap().balance,
+            100000
+        );
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&main_account_id).unwrap().balance,
+            50000
+        );
+    }
+
+    #[tokio::test]
+    async fn should_report_readiness_according_to_lifecycle() {
+        // Given
+        let app_state = AppState::default();
+        let app = app(app_state.clone());
+
+        // Then
+        let response = app.clone().oneshot(get_request("/healthz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone().oneshot(get_request("/readyz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // When
+        app_state.lifecycle.mark_ready();
+
+        // Then
+        let response = app.clone().oneshot(get_request("/readyz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // When
+        app_state.lifecycle.mark_shutting_down();
+
+        // Then
+        let response = app.oneshot(get_request("/readyz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let response = crate::app(app_state).oneshot(get_request("/healthz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_verify_ledger_integrity_after_transactions() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let accounts_repository = AccountsRepository::from(vec![savings_account, main_account]);
+
+        let repos = Repositories {
+            accounts: accounts_repository,
+            ..Repositories::default()
+        };
+
+        let app_state = AppState::from(Arc::new(repos));
+
+        let new_transaction = json!(CreateNewTransaction::new_debit(
+            savings_account_id,
+            main_account_id,
+            "emergency",
+            10000
+        ));
+
+        let request = post_request("/transactions/new", new_transaction);
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // When
+        let response = app(app_state.clone()).oneshot(get_request("/integrity")).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: IntegrityReport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(report.transactions_checked, 1);
+        assert_eq!(report.entries_checked, 2);
+        assert!(report.violations.is_empty());
+
+        // When
+        let response = app(app_state).oneshot(get_request("/integrity/status")).await.unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let status: IntegrityStatus = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(status.runs, 1);
+        assert_eq!(status.failed_runs, 0);
+        assert!(status.last_report.is_some());
+    }
+
+    #[tokio::test]
+    async fn should_report_integrity_violations() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let dangling_entry = JournalEntry {
+            created_at: Utc::now(),
+            entry_id: Uuid::new_v4(),
+            transaction_id: Uuid::new_v4(),
+            ledger_id: DEFAULT_LEDGER_ID,
+            account_id: savings_account.account_id,
+            movement_type: MovementType::Debit,
+            amount_in_cents: 10000,
+            previous_hash: String::new(),
+            hash: String::new(),
+        };
+
+        let mut repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account]),
+            ..Repositories::default()
+        };
+
+        repos.journal.get_mut().save_entries(vec![dangling_entry.clone()]);
+        let app_state = AppState::from(Arc::new(repos));
+
+        // When
+        let response = app(app_state.clone()).oneshot(get_request("/integrity")).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: IntegrityReport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        let expected = vec![
+            IntegrityViolation::UnknownTransaction {
+                entry_id: dangling_entry.entry_id,
+                transaction_id: dangling_entry.transaction_id,
+            },
+            IntegrityViolation::UnbalancedJournal {
+                debits_in_cents: 10000,
+                credits_in_cents: 0,
+            },
+        ];
+
+        assert_eq!(report.violations, expected);
+        assert_eq!(app_state.integrity.failed_runs(), 1);
+
+        // When
+        app_state.repos.journal.write().save_entries(vec![
+            JournalEntry {
+                amount_in_cents: u64::MAX,
+                ..dangling_entry.clone()
+            },
+            JournalEntry {
+                amount_in_cents: u64::MAX,
+                ..dangling_entry
+            },
+        ]);
+        let response = app(app_state.clone()).oneshot(get_request("/integrity")).await.unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = std::str::from_utf8(&bytes).unwrap();
+        assert!(body.contains(r#""debits_in_cents":"36893488147419113230""#));
+
+        let report: IntegrityReport = serde_json::from_slice(&bytes).unwrap();
+        assert!(report.violations.contains(&IntegrityViolation::UnbalancedJournal {
+            debits_in_cents: 2 * u128::from(u64::MAX) + 10000,
+            credits_in_cents: 0,
+        }));
+    }
+
+    #[tokio::test]
+    async fn should_export_prometheus_metrics() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100);
+        let main_account = Account::new("ufs.main", 0);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let new_transaction = json!(CreateNewTransaction::new_debit(
+            savings_account_id,
+            main_account_id,
+            "emergency",
+            100000
+        ));
+
+        let request = post_request("/transactions/new", new_transaction);
+        let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        // When
+        let response = app(shared_state.into()).oneshot(get_request("/metrics")).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let exported = String::from_utf8(bytes.to_vec()).unwrap();
+
+        let expected_lines = [
+            r#"nano_ledger_http_requests_total{method="POST",route="/transactions/new",status="409"}"#,
+            r#"nano_ledger_http_request_duration_seconds_bucket{method="POST",route="/transactions/new","#,
+            r#"nano_ledger_transaction_postings_total{outcome="insufficient_balance"}"#,
+            r#"nano_ledger_state_lock_wait_seconds_bucket{mode="write","#,
+            "nano_ledger_accounts ",
+            "nano_ledger_journal_entries ",
+        ];
+
+        for expected in expected_lines {
+            assert!(exported.contains(expected), "missing metric: {expected}");
+        }
+    }
+
+    #[tokio::test]
+    async fn should_echo_generated_request_id_in_error_body() {
+        // Given
+        let shared_state = SharedState::default();
+        let account_id = Uuid::new_v4();
+
+        // When
+        let get_account = format!("/accounts/{account_id}");
+        let response = app(shared_state.into())
+            .oneshot(get_request(&get_account))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let request_id = response.headers()["x-request-id"].to_str().unwrap().to_string();
+        assert!(Uuid::parse_str(&request_id).is_ok());
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: ErrorBody = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(error.status, 404);
+        assert_eq!(error.message, format!("Account {account_id} not found"));
+        assert_eq!(error.request_id, Some(request_id));
+    }
+
+    #[tokio::test]
+    async fn should_propagate_incoming_request_id() {
+        // Given
+        let shared_state = SharedState::default();
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-request-id", "support-ticket-42")
+            .uri("/accounts/new")
+            .body(Body::from("{ not json"))
+            .unwrap();
+
+        // When
+        let response = app(shared_state.into()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()["x-request-id"], "support-ticket-42");
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: ErrorBody = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(error.request_id.as_deref(), Some("support-ticket-42"));
+    }
+
+    #[tokio::test]
+    async fn should_reject_requests_without_valid_api_key() {
+        // Given
+        let app_state = AppState {
+            auth: Arc::new(Authenticator::with_admin_key("admin-secret")),
+            ..AppState::default()
+        };
+
+        let new_account = json!(CreateNewAccount {
+            alias: "ufs.main".to_string(),
+            balance: None,
+            ..CreateNewAccount::default()
+        });
+
+        // When
+        let request = post_request("/accounts/new", &new_account);
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: ErrorBody = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(error.message, "Missing or invalid credentials");
+
+        // When
+        let request = with_api_key(post_request("/accounts/new", &new_account), "wrong-secret");
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // When
+        let response = app(app_state).oneshot(get_request("/healthz")).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_enforce_api_key_scopes_and_revocation() {
+        // Given
+        let app_state = AppState {
+            auth: Arc::new(Authenticator::with_admin_key("admin-secret")),
+            ..AppState::default()
+        };
+
+        let new_key = json!(CreateNewApiKey {
+            name: "dashboard".to_string(),
+            scopes: [Scope::AccountsRead].into(),
+        });
+
+        let request = with_api_key(post_request("/admin/keys", new_key), "admin-secret");
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: CreatedApiKey = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        // When
+        let get_account = format!("/accounts/{}", Uuid::new_v4());
+        let request = with_api_key(get_request(&get_account), &created.api_key);
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // When
+        let new_account = json!(CreateNewAccount {
+            alias: "ufs.main".to_string(),
+            balance: None,
+            ..CreateNewAccount::default()
+        });
+
+        let request = with_api_key(post_request("/accounts/new", new_account), &created.api_key);
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // When
+        let request = with_api_key(get_request("/admin/keys"), &created.api_key);
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // When
+        let revoke_key = Request::builder()
+            .method(Method::DELETE)
+            .uri(format!("/admin/keys/{}", created.key_id))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app(app_state.clone())
+            .oneshot(with_api_key(revoke_key, "admin-secret"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = with_api_key(get_request(&get_account), &created.api_key);
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = with_api_key(get_request("/admin/keys"), "admin-secret");
+        let response = app(app_state).oneshot(request).await.unwrap();
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let keys: Vec<ApiKey> = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert!(
+            keys.iter()
+                .any(|key| key.key_id == created.key_id && key.revoked_at.is_some())
+        );
+    }
+
+    #[tokio::test]
+    async fn should_restrict_jwt_end_users_to_owned_accounts() {
+        // Given
+        let owned_account = Account::new("customer.main", 100000);
+        let foreign_account = Account::new("someone.else", 100000);
+        let merchant_account = Account::new("merchant", 0);
+
+        let owned_account_id = owned_account.account_id;
+        let foreign_account_id = foreign_account.account_id;
+        let merchant_account_id = merchant_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![owned_account, foreign_account, merchant_account]),
+            ..Repositories::default()
+        };
+
+        let secret = b"hs256-secret-for-tests";
+        let verifier = JwtVerifier::with_key(Algorithm::HS256, DecodingKey::from_secret(secret));
+
+        let app_state = AppState {
+            repos: Arc::new(repos),
+            auth: Arc::new(Authenticator::default().with_jwt(verifier)),
+            ..AppState::default()
+        };
+
+        let user_token = signed_token(
+            &Header::default(),
+            secret,
+            json!({ "sub": "customer", "accounts": [owned_account_id] }),
+        );
+
+        let service_token = signed_token(
+            &Header::default(),
+            secret,
+            json!({ "sub": "backoffice", "scope": "ledger:service" }),
+        );
+
+        let new_transaction = json!(CreateNewTransaction::new_debit(
+            foreign_account_id,
+            merchant_account_id,
+            "groceries",
+            1000
+        ));
+
+        let request = with_bearer(post_request("/transactions/new", &new_transaction), &service_token);
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let tx: CreatedTransaction = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        let expectations = [
+            (format!("/accounts/{owned_account_id}"), &user_token, StatusCode::OK),
+            (
+                format!("/accounts/{foreign_account_id}"),
+                &user_token,
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                format!("/transactions/{}", tx.transaction_id),
+                &user_token,
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                format!("/journal/{}", tx.transaction_id),
+                &user_token,
+                StatusCode::NOT_FOUND,
+            ),
+            ("/journal/head".to_string(), &user_token, StatusCode::FORBIDDEN),
+            ("/journal/verify".to_string(), &user_token, StatusCode::FORBIDDEN),
+            ("/integrity".to_string(), &user_token, StatusCode::FORBIDDEN),
+            (
+                format!("/accounts/{foreign_account_id}"),
+                &service_token,
+                StatusCode::OK,
+            ),
+            (
+                format!("/transactions/{}", tx.transaction_id),
+                &service_token,
+                StatusCode::OK,
+            ),
+            (
+                format!("/journal/{}", tx.transaction_id),
+                &service_token,
+                StatusCode::OK,
+            ),
+        ];
+
+        for (endpoint, token, expected_status) in expectations {
+            // When
+            let request = with_bearer(get_request(&endpoint), token);
+            let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), expected_status, "GET {endpoint}");
+        }
+
+        // When
+        let request = with_bearer(get_request("/plaintext/export"), &user_token);
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let exported = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(exported.contains("Assets:Customer:Main"));
+        assert!(!exported.contains("Someone") && !exported.contains("Merchant") && !exported.contains("groceries"));
+
+        for (token, expected_pending) in [(&user_token, 0), (&service_token, 3)] {
+            // When
+            let request = with_bearer(get_request("/outbox"), token);
+            let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let outbox: serde_json::Value = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+            assert_eq!(outbox["pending"], expected_pending);
+            assert_eq!(outbox["items"].as_array().unwrap().len(), expected_pending);
+        }
+
+        let greedy_token = signed_token(
+            &Header::default(),
+            secret,
+            json!({
+                "sub": "customer",
+                "accounts": [owned_account_id],
+                "scope": "accounts:read transactions:write keys:admin webhooks:admin ledgers:write"
+            }),
+        );
+
+        let own_transaction = json!(CreateNewTransaction::new_debit(
+            owned_account_id,
+            merchant_account_id,
+            "groceries",
+            1000
+        ));
+
+        for token in [&user_token, &greedy_token] {
+            // When
+            let request = with_bearer(post_request("/transactions/new", &new_transaction), token);
+            let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            // When
+            let request = with_bearer(post_request("/transactions/new", &own_transaction), token);
+            let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            // When
+            let request = with_bearer(get_request("/admin/keys"), token);
+            let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+
+        // When
+        let request = with_bearer(get_request(&format!("/accounts/{owned_account_id}")), &greedy_token);
+        let response = app(app_state).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_verify_jwt_against_keys_from_jwks() {
+        // Given
+        let jwks: JwkSet = serde_json::from_value(json!({
+            "keys": [{ "kty": "oct", "kid": "primary", "k": "andrcy1zaGFyZWQtc2VjcmV0LWZvci10ZXN0cw" }]
+        }))
+        .unwrap();
+
+        let app_state = AppState {
+            auth: Arc::new(Authenticator::default().with_jwt(JwtVerifier::with_jwks(&jwks).unwrap())),
+            ..AppState::default()
+        };
+
+        let header = Header {
+            kid: Some("primary".to_string()),
+            ..Header::default()
+        };
+
+        let claims = json!({ "sub": "backoffice", "scope": "ledger:service" });
+        let valid_token = signed_token(&header, b"jwks-shared-secret-for-tests", claims.clone());
+        let forged_token = signed_token(&header, b"some-other-secret", claims);
+        let get_account = format!("/accounts/{}", Uuid::new_v4());
+
+        // When
+        let request = with_bearer(get_request(&get_account), &valid_token);
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // When
+        let request = with_bearer(get_request(&get_account), &forged_token);
+        let response = app(app_state).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn should_isolate_accounts_per_ledger() {
+        // Given
+        let app_state = AppState::default();
+        let mut ledgers = Vec::new();
+
+        for name in ["acme.gmbh", "acme.inc"] {
+            let new_ledger = json!(CreateNewLedger { name: name.to_string() });
+            let response = app(app_state.clone())
+                .oneshot(post_request("/ledgers/new", new_ledger))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let ledger: Ledger = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+            ledgers.push(ledger.ledger_id);
+        }
+
+        let new_account = json!(CreateNewAccount {
+            alias: "cash".to_string(),
+            balance: Some(1000),
+            ..CreateNewAccount::default()
+        });
+
+        let mut accounts = Vec::new();
+
+        // When
+        for ledger_id in &ledgers {
+            let endpoint = format!("/ledgers/{ledger_id}/accounts/new");
+            let response = app(app_state.clone())
+                .oneshot(post_request(&endpoint, &new_account))
+                .await
+                .unwrap();
+
+            // Then
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let account: serde_json::Value = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+            assert_eq!(account["ledger_id"], json!(ledger_id));
+            accounts.push(account["account_id"].as_str().unwrap().to_string());
+        }
+
+        // When
+        let endpoint = format!("/ledgers/{}/accounts/new", ledgers[0]);
+        let response = app(app_state.clone())
+            .oneshot(post_request(&endpoint, &new_account))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let expectations = [
+            (
+                format!("/ledgers/{}/accounts/{}", ledgers[0], accounts[0]),
+                StatusCode::OK,
+            ),
+            (
+                format!("/ledgers/{}/accounts/{}", ledgers[0], accounts[1]),
+                StatusCode::NOT_FOUND,
+            ),
+            (format!("/accounts/{}", accounts[0]), StatusCode::NOT_FOUND),
+            (
+                format!("/ledgers/{}/accounts/{}", Uuid::new_v4(), accounts[0]),
+                StatusCode::NOT_FOUND,
+            ),
+        ];
+
+        for (endpoint, expected_status) in expectations {
+            // When
+            let response = app(app_state.clone()).oneshot(get_request(&endpoint)).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), expected_status, "GET {endpoint}");
+        }
+
+        // When
+        let response = app(app_state).oneshot(get_request("/ledgers")).await.unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let listed: Vec<Ledger> = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(listed.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn should_reject_transactions_crossing_ledgers() {
+        // Given
+        let other_ledger_id = Uuid::new_v4();
+        let local_account = Account::new("cash", 100000);
+        let foreign_account = Account::new("cash", 100000).within(other_ledger_id);
+
+        let local_account_id = local_account.account_id;
+        let foreign_account_id = foreign_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![local_account, foreign_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        // When
+        let new_transaction = json!(CreateNewTransaction::new_debit(
+            local_account_id,
+            foreign_account_id,
+            "intercompany",
+            10000
+        ));
+
+        let request = post_request("/transactions/new", new_transaction);
+        let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&local_account_id).unwrap().balance,
+            100000
+        );
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&foreign_account_id).unwrap().balance,
+            100000
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn should_keep_balances_consistent_under_concurrent_postings() {
+        // Given
+        let accounts: Vec<Account> = (0..4)
+            .map(|index| Account::new(&format!("account.{index}"), 10000))
+            .collect();
+        let account_ids: Vec<Uuid> = accounts.iter().map(|account| account.account_id).collect();
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(accounts),
+            ..Repositories::default()
+        };
+
+        let app_state = AppState::from(Arc::new(repos));
+
+        // When
+        let postings = (0..200).map(|index| {
+            // Opposite directions over overlapping pairs, so lock ordering matters
+            let lhs_account_id = account_ids[index % 4];
+            let rhs_account_id = account_ids[(index + 1 + index % 2) % 4];
+            let payload = match index % 3 {
+                0 => CreateNewTransaction::new_credit(lhs_account_id, rhs_account_id, "concurrent", 100),
+                _ => CreateNewTransaction::new_debit(lhs_account_id, rhs_account_id, "concurrent", 100),
+            };
+
+            let app = app(app_state.clone());
+            tokio::spawn(async move { app.oneshot(post_request("/transactions/new", payload)).await.unwrap() })
+        });
+
+        let mut created = 0;
+
+        for posting in postings.collect::<Vec<_>>() {
+            let response = posting.await.unwrap();
+            assert!([StatusCode::OK, StatusCode::CONFLICT].contains(&response.status()));
+            created += usize::from(response.status() == StatusCode::OK);
+        }
+
+        // Then
+        let total_balance: i64 = account_ids
+            .iter()
+            .map(|account_id| app_state.repos.accounts.fetch_by_id(account_id).unwrap().balance)
+            .sum();
+
+        assert_eq!(total_balance, 40000);
+        assert_eq!(app_state.repos.journal.read().count(), created * 2);
+
+        let response = app(app_state).oneshot(get_request("/integrity")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_guard_transactions_with_account_etags() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 0);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+        let endpoint = format!("/accounts/{savings_account_id}");
+
+        // When
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request(&endpoint))
+            .await
+            .unwrap();
+
+        // Then
+        let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(etag, format!("\"{savings_account_id}:1\""));
+
+        let new_transaction = json!(CreateNewTransaction::new_debit(
+            savings_account_id,
+            main_account_id,
+            "Transfer",
+            10000
+        ));
+
+        for expected_status in [StatusCode::OK, StatusCode::PRECONDITION_FAILED] {
+            // When
+            let mut request = post_request("/transactions/new", &new_transaction);
+            request.headers_mut().insert(header::IF_MATCH, etag.parse().unwrap());
+            let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), expected_status);
+        }
+
+        let savings_account = shared_state.accounts.fetch_by_id(&savings_account_id).unwrap();
+        assert_eq!(savings_account.balance, 90000);
+        assert_eq!(savings_account.version, 2);
+
+        // When
+        let response = app(shared_state.into()).oneshot(get_request(&endpoint)).await.unwrap();
+
+        // Then
+        assert_eq!(response.headers()[header::ETAG], format!("\"{savings_account_id}:2\""));
+    }
+
+    #[tokio::test]
+    async fn should_reject_transactions_expecting_stale_versions() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 0);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let expectations = [
+            (Some(1), Some(2), StatusCode::PRECONDITION_FAILED),
+            (Some(1), Some(1), StatusCode::OK),
+            (Some(1), None, StatusCode::PRECONDITION_FAILED),
+            (None, Some(2), StatusCode::OK),
+        ];
+
+        for (lhs_expected_version, rhs_expected_version, expected_status) in expectations {
+            // When
+            let new_transaction =
+                CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Transfer", 10000)
+                    .expecting_versions(lhs_expected_version, rhs_expected_version);
+
+            let request = post_request("/transactions/new", new_transaction);
+            let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), expected_status);
+        }
+
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&savings_account_id).unwrap().balance,
+            80000
+        );
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&main_account_id).unwrap().balance,
+            20000
+        );
+    }
+
+    #[test]
+    fn should_index_journal_entries_by_transaction_account_and_time() {
+        // Given
+        let (savings_account_id, main_account_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let epoch = Utc::now();
+        let mut journal = JournalRepository::default();
+
+        for (offset, account_id) in [(3, savings_account_id), (1, main_account_id), (2, savings_account_id)] {
+            journal.save_entries(vec![JournalEntry {
+                created_at: epoch + TimeDelta::seconds(offset),
+                entry_id: Uuid::new_v4(),
+                transaction_id: Uuid::new_v4(),
+                ledger_id: DEFAULT_LEDGER_ID,
+                account_id,
+                movement_type: MovementType::Debit,
+                amount_in_cents: offset as u64,
+                previous_hash: String::new(),
+                hash: String::new(),
+            }]);
+        }
+
+        // When
+        let by_account: Vec<u64> = journal
+            .fetch_by_account(&savings_account_id)
+            .map(|entry| entry.amount_in_cents)
+            .collect();
+
+        let first_transaction_id = journal.iter().next().unwrap().transaction_id;
+
+        let within = |from: i64, until: i64, positions: std::ops::Range<usize>| -> Vec<u64> {
+            let filter = JournalFilter {
+                ledger_id: DEFAULT_LEDGER_ID,
+                from: Some(epoch + TimeDelta::seconds(from)),
+                until: Some(epoch + TimeDelta::seconds(until)),
+                ..JournalFilter::default()
+            };
+            let (entries, _) = journal.entries_within(&filter, positions, 10, |_| true);
+            entries.iter().map(|entry| entry.amount_in_cents).collect()
+        };
+
+        // Then
+        assert_eq!(by_account, vec![3, 2]);
+        assert_eq!(within(1, 3, 0..3), vec![1, 2]);
+        assert_eq!(within(2, 4, 0..3), vec![3, 2]);
+        assert_eq!(within(2, 4, 1..3), vec![2]);
+        assert!(within(3, 1, 0..3).is_empty());
+        assert_eq!(journal.fetch_by_transaction(&first_transaction_id).len(), 1);
+        assert!(journal.fetch_by_account(&Uuid::new_v4()).next().is_none());
+    }
+
+    #[tokio::test]
+    async fn should_detect_tampered_journal_entries() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        for amount in [10000, 20000] {
+            let new_transaction =
+                CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Transfer", amount);
+            let request = post_request("/transactions/new", new_transaction);
+            let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // When
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request("/journal/head"))
+            .await
+            .unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let head: ChainHead = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(head.entries, 4);
+        assert_eq!(head.head_hash, shared_state.journal.read().iter().last().unwrap().hash);
+
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request("/journal/verify"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // When
+        let tampered_entry_id = {
+            let mut journal = shared_state.journal.write();
+            let entry = journal.entry_mut(2);
+            entry.amount_in_cents = 1;
+            entry.entry_id
+        };
+
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request("/journal/verify"))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let verification: ChainVerification = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        assert!(matches!(
+            verification.broken_link,
+            Some(BrokenLink::HashMismatch { position: 2, entry_id, .. }) if entry_id == tampered_entry_id
+        ));
+        assert_eq!(verification.entries_checked, 3);
+
+        // When
+        let next_entry_id = {
+            let mut journal = shared_state.journal.write();
+            let entry = journal.entry_mut(2);
+            entry.hash = entry.compute_hash();
+            journal.entry_mut(3).entry_id
+        };
+
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request("/integrity"))
+            .await
+            .unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: IntegrityReport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        let expected = IntegrityViolation::BrokenJournalChain {
+            position: 3,
+            entry_id: next_entry_id,
+        };
+
+        assert!(report.violations.contains(&expected));
+    }
+
+    #[tokio::test]
+    async fn should_page_through_filtered_journal_entries() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let post = |amount| {
+            let new_transaction =
+                CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Transfer", amount);
+            app(shared_state.clone().into()).oneshot(post_request("/transactions/new", new_transaction))
+        };
+
+        for amount in [1000, 2000, 3000] {
+            assert_eq!(post(amount).await.unwrap().status(), StatusCode::OK);
+        }
+
+        // When
+        let first_page = format!("/journal?account_id={savings_account_id}&movement_type=Debit&limit=2");
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request(&first_page))
+            .await
+            .unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: JournalPage = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        let amounts: Vec<u64> = page.entries.iter().map(|entry| entry.amount_in_cents).collect();
+        assert_eq!(amounts, vec![1000, 2000]);
+        assert!(page.has_more);
+
+        // When
+        assert_eq!(post(4000).await.unwrap().status(), StatusCode::OK);
+
+        let next_page = format!("{first_page}&cursor={}", page.next_cursor);
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request(&next_page))
+            .await
+            .unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: JournalPage = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        let amounts: Vec<u64> = page.entries.iter().map(|entry| entry.amount_in_cents).collect();
+        assert_eq!(amounts, vec![3000, 4000]);
+        assert!(!page.has_more);
+
+        // When
+        let by_amount = "/journal?min_amount=2000&max_amount=3000&movement_type=Credit";
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request(by_amount))
+            .await
+            .unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: JournalPage = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert!(page.entries.iter().all(|entry| entry.account_id == main_account_id));
+        assert_eq!(page.entries.len(), 2);
+
+        // When
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request("/journal?cursor=not-a-cursor"))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn should_search_and_page_through_transactions() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+        let card_account = Account::new("ufs.card", 0);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+        let card_account_id = card_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account, card_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let new_transactions = [
+            CreateNewTransaction::new_debit(savings_account_id, main_account_id, "SEPA Transfer", 3000),
+            CreateNewTransaction::new_debit(main_account_id, card_account_id, "Card payment", 2000),
+            CreateNewTransaction::new_debit(savings_account_id, main_account_id, "SEPA direct debit", 1000),
+        ];
+
+        for new_transaction in new_transactions {
+            let request = post_request("/transactions/new", new_transaction);
+            let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let list = |endpoint: String| {
+            let shared_state = shared_state.clone();
+            async move {
+                let response = app(shared_state.into()).oneshot(get_request(&endpoint)).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<TransactionsPage>(bytes.iter().as_slice()).unwrap()
+            }
+        };
+
+        let descriptions = |page: &TransactionsPage| -> Vec<String> {
+            page.transactions.iter().map(|tx| tx.description.clone()).collect()
+        };
+
+        // When
+        let page = list("/transactions?q=sepa%20transf".to_string()).await;
+
+        // Then
+        assert_eq!(descriptions(&page), vec!["SEPA Transfer"]);
+
+        // When
+        let page = list(format!(
+            "/transactions?account_id={main_account_id}&sort=amount&limit=2"
+        ))
+        .await;
+
+        // Then
+        assert_eq!(page.total, Some(3));
+        assert_eq!(descriptions(&page), vec!["SEPA direct debit", "Card payment"]);
+
+        // When a transaction sorting before the cursor is posted in between
+        let new_transaction = CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Top up", 500);
+        let response = app(shared_state.clone().into())
+            .oneshot(post_request("/transactions/new", new_transaction))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let cursor = page.next_cursor.unwrap();
+        let page = list(format!(
+            "/transactions?account_id={main_account_id}&sort=amount&limit=2&cursor={cursor}"
+        ))
+        .await;
+
+        // Then the next page neither repeats nor skips anything
+        assert_eq!(descriptions(&page), vec!["SEPA Transfer"]);
+        assert!(page.total.is_none());
+        assert!(page.next_cursor.is_none());
+
+        // When
+        let page = list("/transactions?sort=-created_at&limit=2".to_string()).await;
+        let cursor = page.next_cursor.clone().unwrap();
+        let next_page = list(format!("/transactions?sort=-created_at&limit=2&cursor={cursor}")).await;
+
+        // Then
+        assert_eq!(descriptions(&page), vec!["Top up", "SEPA direct debit"]);
+        assert_eq!(descriptions(&next_page), vec!["Card payment", "SEPA Transfer"]);
+
+        // When
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request(&format!("/transactions?sort=amount&cursor={cursor}")))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // When
+        let page = list(format!("/transactions?account_id={card_account_id}&min_amount=1500")).await;
+
+        // Then
+        assert_eq!(descriptions(&page), vec!["Card payment"]);
+
+        // When
+        let page = list("/transactions?q=sepa&sort=-amount&max_amount=2999".to_string()).await;
+
+        // Then
+        assert_eq!(descriptions(&page), vec!["SEPA direct debit"]);
+
+        // When
+        let page = list("/transactions?q=%3B%20-".to_string()).await;
+
+        // Then searching without any word filters nothing
+        assert_eq!(page.total, Some(4));
+    }
+
+    #[tokio::test]
+    async fn should_stream_and_replay_ledger_events() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            events: EventBus::with_capacity(3),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let post = |amount| {
+            let new_transaction =
+                CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Transfer", amount);
+            app(shared_state.clone().into()).oneshot(post_request("/transactions/new", new_transaction))
+        };
+
+        for amount in [1000, 2000] {
+            assert_eq!(post(amount).await.unwrap().status(), StatusCode::OK);
+        }
+
+        let subscribe = |last_event_id: &str| {
+            let mut request = get_request(&format!("/events?account_id={main_account_id}"));
+            request
+                .headers_mut()
+                .insert(LAST_EVENT_ID_HEADER, last_event_id.parse().unwrap());
+            app(shared_state.clone().into()).oneshot(request)
+        };
+
+        // When
+        let response = subscribe("0").await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::GONE);
+
+        // When
+        let response = subscribe(&u64::MAX.to_string()).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // When
+        let response = subscribe("4").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+
+        assert_eq!(post(3000).await.unwrap().status(), StatusCode::OK);
+
+        let mut body = response.into_body().into_data_stream();
+        let mut received = String::new();
+
+        while received.matches("\n\n").count() < 3 {
+            let chunk = tokio::time::timeout(Duration::from_secs(5), body.next())
+                .await
+                .expect("No event received in time")
+                .unwrap()
+                .unwrap();
+            received.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+
+        // Then
+        let events: Vec<LedgerEvent> = received
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+
+        let sequences: Vec<u64> = events.iter().map(|event| event.sequence).collect();
+        assert_eq!(sequences, vec![6, 7, 9]);
+
+        assert!(matches!(
+            &events[1].payload,
+            EventPayload::TransactionPosted { transaction } if transaction.amount_in_cents == 3000
+        ));
+
+        assert!(matches!(
+            events[2].payload,
+            EventPayload::BalanceChanged { account_id, balance, .. } if account_id == main_account_id && balance == 56000
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_reverse_transactions_once() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let new_transaction = CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Rent", 30000);
+        let response = app(shared_state.clone().into())
+            .oneshot(post_request("/transactions/new", new_transaction))
+            .await
+            .unwrap();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let posted: CreatedTransaction = serde_json::from_slice(&bytes).unwrap();
+
+        let reverse = |transaction_id: Uuid| {
+            let request = Request::builder()
+                .method("POST")
+                .uri(format!("/transactions/{transaction_id}/reverse"))
+                .body(Body::empty())
+                .unwrap();
+            app(shared_state.clone().into()).oneshot(request)
+        };
+
+        // When
+        let response = reverse(posted.transaction_id).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let reversal: CreatedTransaction = serde_json::from_slice(&bytes).unwrap();
+
+        let balance = |account_id| shared_state.accounts.fetch_by_id(&account_id).unwrap().balance;
+        assert_eq!((balance(savings_account_id), balance(main_account_id)), (100000, 50000));
+
+        let events = shared_state.events.subscribe(Some(0)).unwrap().replay;
+        assert!(matches!(
+            &events[3].payload,
+            EventPayload::TransactionReversed { reversed_transaction_id, transaction }
+                if *reversed_transaction_id == posted.transaction_id
+                    && transaction.transaction_id == reversal.transaction_id
+                    && transaction.description == "Reversal of Rent"
+        ));
+
+        // When
+        let response = reverse(posted.transaction_id).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        // When
+        let request = get_request("/transactions?status=reversed");
+        let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: TransactionsPage = serde_json::from_slice(&bytes).unwrap();
+        let listed: Vec<Uuid> = page.transactions.iter().map(|tx| tx.transaction_id).collect();
+        assert_eq!(listed, vec![posted.transaction_id]);
+
+        // When
+        let response = reverse(reversal.transaction_id).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn should_push_balances_to_websocket_subscribers() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            ..Repositories::default()
+        };
+
+        let app_state = AppState {
+            repos: Arc::new(repos),
+            auth: Arc::new(Authenticator::with_admin_key("admin-secret")),
+            ..AppState::default()
+        };
+
+        let (address, trigger, _) = spawn_server(app_state.clone(), Duration::from_millis(200)).await;
+        let endpoint = format!("ws://{address}/balances/live");
+
+        // When
+        let unauthenticated = tokio_tungstenite::connect_async(endpoint.as_str()).await;
+
+        // Then
+        assert!(matches!(
+            unauthenticated,
+            Err(WsError::Http(response)) if response.status() == StatusCode::UNAUTHORIZED
+        ));
+
+        // When
+        let mut request = endpoint.into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("x-api-key", "admin-secret".parse().unwrap());
+        let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+        let unknown_account_id = Uuid::new_v4();
+        let subscribe = json!({ "action": "subscribe", "account_ids": [main_account_id, unknown_account_id] });
+        socket.send(WsMessage::text(subscribe.to_string())).await.unwrap();
+
+        let mut next_message = async || loop {
+            let received = tokio::time::timeout(Duration::from_secs(5), socket.next())
+                .await
+                .expect("No message received in time")
+                .unwrap()
+                .unwrap();
+
+            if let WsMessage::Text(text) = received {
+                break serde_json::from_str::<BalanceMessage>(&text).unwrap();
+            }
+        };
+
+        // Then
+        let initial_balance = BalanceMessage::Balance {
+            account_id: main_account_id,
+            balance: 50000,
+            version: 1,
+        };
+
+        assert_eq!(next_message().await, initial_balance);
+        assert!(matches!(next_message().await, BalanceMessage::Error { .. }));
+
+        // When
+        let new_transaction = CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Transfer", 1000);
+        let request = with_api_key(post_request("/transactions/new", new_transaction), "admin-secret");
+        let response = app(app_state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Then
+        let changed_balance = BalanceMessage::Balance {
+            account_id: main_account_id,
+            balance: 51000,
+            version: 2,
+        };
+
+        assert_eq!(next_message().await, changed_balance);
+
+        trigger.send(()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_deliver_signed_webhooks_with_retries_and_dead_letters() {
+        // Given
+        let received = Arc::new(parking_lot::Mutex::new(Vec::<(http::HeaderMap, String)>::new()));
+
+        let receiver = {
+            let received = received.clone();
+            axum::Router::new().route(
+                "/hook",
+                axum::routing::post(move |headers: http::HeaderMap, body: String| async move {
+                    let mut received = received.lock();
+                    received.push((headers, body));
+
+                    match received.len() {
+                        1 | 2 => StatusCode::INTERNAL_SERVER_ERROR,
+                        _ => StatusCode::NO_CONTENT,
+                    }
+                }),
+            )
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let receiver_address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            ..Repositories::default()
+        };
+
+        let retry_policy = RetryPolicy {
+            max_attempts: 2,
+            initial_backoff: Duration::from_millis(10),
+            ..RetryPolicy::default()
+        };
+
+        let app_state = AppState {
+            repos: Arc::new(repos),
+            webhooks: Arc::new(WebhookDispatcher::with_policy(retry_policy)),
+            ..AppState::default()
+        };
+
+        tokio::spawn(outbox::run_relay(app_state.clone(), Duration::from_millis(10)));
+        tokio::task::yield_now().await;
+
+        let new_webhook = CreateNewWebhook {
+            url: format!("http://{receiver_address}/hook"),
+            event_types: ["transaction.posted".to_string()].into(),
+            secret: "webhook-secret".to_string(),
+        };
+
+        let response = app(app_state.clone())
+            .oneshot(post_request("/webhooks/new", new_webhook))
+            .await
+            .unwrap();
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let webhook: Webhook = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        let eventually = async |endpoint: String, expected: usize| {
+            for _ in 0..500 {
+                let response = app(app_state.clone()).oneshot(get_request(&endpoint)).await.unwrap();
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let items: Vec<serde_json::Value> = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+                if items.len() == expected {
+                    return items;
+                }
+
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            panic!("{endpoint} never listed {expected} items");
+        };
+
+        // When
+        let new_transaction = CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Transfer", 1000);
+        let response = app(app_state.clone())
+            .oneshot(post_request("/transactions/new", new_transaction))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Then
+        let dead_letters_endpoint = format!("/webhooks/{}/dead-letters", webhook.webhook_id);
+        let dead_letters = eventually(dead_letters_endpoint.clone(), 1).await;
+        let dead_letter: DeadLetter = serde_json::from_value(dead_letters[0].clone()).unwrap();
+        assert_eq!(dead_letter.attempts, 2);
+
+        // When
+        let redeliver = format!(
+            "/webhooks/{}/dead-letters/{}/redeliver",
+            webhook.webhook_id, dead_letter.delivery_id
+        );
+        let response = app(app_state.clone())
+            .oneshot(post_request(&redeliver, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        // Then
+        let deliveries_endpoint = format!("/webhooks/{}/deliveries", webhook.webhook_id);
+        let attempts = eventually(deliveries_endpoint, 3).await;
+        let attempts: Vec<DeliveryAttempt> = serde_json::from_value(json!(attempts)).unwrap();
+        let outcomes: Vec<bool> = attempts.iter().map(|attempt| attempt.delivered).collect();
+        assert_eq!(outcomes, vec![false, false, true]);
+        assert!(
+            attempts
+                .iter()
+                .all(|attempt| attempt.delivery_id == dead_letter.delivery_id)
+        );
+
+        eventually(dead_letters_endpoint, 0).await;
+
+        let (headers, body) = received.lock().last().cloned().unwrap();
+        let timestamp: i64 = headers[webhooks::TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        let expected_signature = webhooks::sign_payload("webhook-secret", timestamp, body.as_bytes());
+        assert_eq!(headers[webhooks::SIGNATURE_HEADER], expected_signature.as_str());
+        assert_eq!(headers[webhooks::EVENT_HEADER], "transaction.posted");
+
+        let event: LedgerEvent = serde_json::from_str(&body).unwrap();
+        assert!(matches!(
+            event.payload,
+            EventPayload::TransactionPosted { transaction } if transaction.amount_in_cents == 1000
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_relay_outbox_items_in_order_once_sinks_recover() {
+        // Given
+        let sink_directory = std::env::temp_dir().join(format!("nano-ledger-outbox-{}", Uuid::new_v4()));
+        let sink_file = sink_directory.join("events.ndjson");
+
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            outbox: OutboxRepository::with_sinks(vec![OutboxSink::File(sink_file.clone())]).into(),
+            ..Repositories::default()
+        };
+
+        let app_state = AppState::from(Arc::new(repos));
+
+        for amount in [1000, 2000, 500000] {
+            let new_transaction =
+                CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Transfer", amount);
+            app(app_state.clone())
+                .oneshot(post_request("/transactions/new", new_transaction))
+                .await
+                .unwrap();
+        }
+
+        let pending_outbox = async || {
+            let response = app(app_state.clone()).oneshot(get_request("/outbox")).await.unwrap();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<PendingOutbox>(bytes.iter().as_slice()).unwrap()
+        };
+
+        // When
+        outbox::relay_pending(&app_state).await;
+
+        // Then
+        let outbox = pending_outbox().await;
+        assert_eq!(outbox.pending, 6);
+
+        let attempts: Vec<u32> = outbox.items.iter().map(|item| item.attempts).collect();
+        assert_eq!(attempts, vec![1, 0, 0, 0, 0, 0]);
+        assert!(outbox.items[0].last_error.is_some());
+
+        // When
+        std::fs::create_dir_all(&sink_directory).unwrap();
+        outbox::relay_pending(&app_state).await;
+
+        // Then
+        assert_eq!(pending_outbox().await.pending, 0);
+
+        let published: Vec<LedgerEvent> = std::fs::read_to_string(&sink_file)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let sequences: Vec<u64> = published.iter().map(|event| event.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3, 4, 5, 6]);
+
+        std::fs::remove_dir_all(&sink_directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_keep_webhook_outbox_items_pending_until_delivered() {
+        // Given
+        let received = Arc::new(parking_lot::Mutex::new(Vec::<u64>::new()));
+
+        let receiver = {
+            let received = received.clone();
+            axum::Router::new().route(
+                "/hook",
+                axum::routing::post(move |axum::Json(event): axum::Json<LedgerEvent>| async move {
+                    let mut received = received.lock();
+                    received.push(event.sequence);
+
+                    match received.len() {
+                        1 | 2 => StatusCode::SERVICE_UNAVAILABLE,
+                        _ => StatusCode::NO_CONTENT,
+                    }
+                }),
+            )
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let receiver_address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 0);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            outbox: OutboxRepository::with_sinks(vec![OutboxSink::Webhooks]).into(),
+            ..Repositories::default()
+        };
+
+        let retry_policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            ..RetryPolicy::default()
+        };
+
+        let app_state = AppState {
+            repos: Arc::new(repos),
+            webhooks: Arc::new(WebhookDispatcher::with_policy(retry_policy)),
+            ..AppState::default()
+        };
+
+        let new_webhook = CreateNewWebhook {
+            url: format!("http://{receiver_address}/hook"),
+            event_types: ["transaction.posted".to_string()].into(),
+            secret: "webhook-secret".to_string(),
+        };
+
+        app(app_state.clone())
+            .oneshot(post_request("/webhooks/new", new_webhook))
+            .await
+            .unwrap();
+
+        for amount in [1000, 2000] {
+            let new_transaction =
+                CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Transfer", amount);
+            app(app_state.clone())
+                .oneshot(post_request("/transactions/new", new_transaction))
+                .await
+                .unwrap();
+        }
+
+        let pending_sequences = || -> Vec<u64> {
+            app_state
+                .repos
+                .outbox
+                .read()
+                .pending(&DEFAULT_LEDGER_ID)
+                .map(|item| item.event.sequence)
+                .collect()
+        };
+
+        // When
+        outbox::relay_pending(&app_state).await;
+
+        // Then later events of the same accounts wait behind the first one
+        assert_eq!(*received.lock(), vec![1]);
+        assert_eq!(pending_sequences(), vec![1, 2, 3, 4, 5, 6]);
+
+        // When
+        outbox::relay_pending(&app_state).await;
+
+        // Then nothing is attempted before the backoff is over
+        assert_eq!(*received.lock(), vec![1]);
+
+        // When
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        outbox::relay_pending(&app_state).await;
+        tokio::time::sleep(Duration::from_millis(110)).await;
+        outbox::relay_pending(&app_state).await;
+
+        // Then
+        assert_eq!(*received.lock(), vec![1, 1, 1, 4]);
+        assert!(pending_sequences().is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_keep_delivering_other_accounts_while_a_webhook_fails() {
+        // Given
+        let received = Arc::new(parking_lot::Mutex::new(Vec::<u64>::new()));
+
+        let receiver = {
+            let received = received.clone();
+            axum::Router::new().route(
+                "/hook",
+                axum::routing::post(move |axum::Json(event): axum::Json<LedgerEvent>| async move {
+                    received.lock().push(event.sequence);
+
+                    match event.sequence {
+                        1 => StatusCode::SERVICE_UNAVAILABLE,
+                        _ => StatusCode::NO_CONTENT,
+                    }
+                }),
+            )
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let receiver_address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+        let accounts: Vec<Account> = ["ufs.savings", "ufs.main", "ufs.travel", "ufs.groceries"]
+            .into_iter()
+            .map(|name| Account::new(name, 100000))
+            .collect();
+
+        let account_ids: Vec<Uuid> = accounts.iter().map(|account| account.account_id).collect();
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(accounts),
+            outbox: OutboxRepository::with_sinks(vec![OutboxSink::Webhooks]).into(),
+            ..Repositories::default()
+        };
+
+        let app_state = AppState {
+            repos: Arc::new(repos),
+            ..AppState::default()
+        };
+
+        let new_webhook = CreateNewWebhook {
+            url: format!("http://{receiver_address}/hook"),
+            event_types: ["transaction.posted".to_string()].into(),
+            secret: "webhook-secret".to_string(),
+        };
+
+        app(app_state.clone())
+            .oneshot(post_request("/webhooks/new", new_webhook))
+            .await
+            .unwrap();
+
+        for pair in account_ids.chunks(2) {
+            let new_transaction = CreateNewTransaction::new_debit(pair[0], pair[1], "Transfer", 1000);
+            app(app_state.clone())
+                .oneshot(post_request("/transactions/new", new_transaction))
+                .await
+                .unwrap();
+        }
+
+        // When
+        outbox::relay_pending(&app_state).await;
+
+        // Then only the events of the failing accounts stay behind
+        let mut delivered = received.lock().clone();
+        delivered.sort_unstable();
+        assert_eq!(delivered, vec![1, 4]);
+
+        let pending: Vec<u64> = app_state
+            .repos
+            .outbox
+            .read()
+            .pending(&DEFAULT_LEDGER_ID)
+            .map(|item| item.event.sequence)
+            .collect();
+        assert_eq!(pending, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn should_prove_balances_against_checkpoint_roots() {
+        // Given
+        let accounts: Vec<Account> = (0..5)
+            .map(|index| Account::new(&format!("account.{index}"), 1000 * index))
+            .collect();
+
+        let account_ids: Vec<Uuid> = accounts.iter().map(|account| account.account_id).collect();
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(accounts),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        // When
+        let response = app(shared_state.clone().into())
+            .oneshot(post_request("/checkpoints/new", json!({})))
+            .await
+            .unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let first_checkpoint: Checkpoint = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!(first_checkpoint.accounts, 5);
+
+        for (index, account_id) in account_ids.iter().enumerate() {
+            // When
+            let endpoint = format!("/accounts/{account_id}/proof");
+            let response = app(shared_state.clone().into())
+                .oneshot(get_request(&endpoint))
+                .await
+                .unwrap();
+
+            // Then
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let proof: BalanceProof = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+            assert_eq!(proof.leaf.balance, 1000 * index as i64);
+            assert!(proof.verify(&first_checkpoint.root));
+
+            let mut forged = proof.clone();
+            forged.leaf.balance += 1;
+            assert!(!forged.verify(&first_checkpoint.root));
+        }
+
+        // When
+        let new_transaction = CreateNewTransaction::new_debit(account_ids[4], account_ids[0], "Transfer", 500);
+        app(shared_state.clone().into())
+            .oneshot(post_request("/transactions/new", new_transaction))
+            .await
+            .unwrap();
+
+        let response = app(shared_state.clone().into())
+            .oneshot(post_request("/checkpoints/new", json!({})))
+            .await
+            .unwrap();
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let second_checkpoint: Checkpoint = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        let endpoint = format!(
+            "/accounts/{}/proof?checkpoint={}",
+            account_ids[0], first_checkpoint.checkpoint_id
+        );
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request(&endpoint))
+            .await
+            .unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let proof: BalanceProof = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        assert_ne!(first_checkpoint.root, second_checkpoint.root);
+        assert_eq!(proof.leaf.balance, 0);
+        assert!(proof.verify(&first_checkpoint.root));
+        assert!(!proof.verify(&second_checkpoint.root));
+
+        // When
+        let endpoint = format!("/accounts/{}/proof", Uuid::new_v4());
+        let response = app(shared_state.into()).oneshot(get_request(&endpoint)).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn should_evict_checkpoints_past_retention() {
+        // Given
+        let account = Account::new("ufs.main", 1000);
+        let account_id = account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![account]),
+            checkpoints: CheckpointsRepository::with_retention(2).into(),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+        let mut created = Vec::new();
+
+        // When
+        for _ in 0..3 {
+            let response = app(shared_state.clone().into())
+                .oneshot(post_request("/checkpoints/new", json!({})))
+                .await
+                .unwrap();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            created.push(serde_json::from_slice::<Checkpoint>(&bytes).unwrap().checkpoint_id);
+        }
+
+        // Then only the latest two are kept
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request("/checkpoints"))
+            .await
+            .unwrap();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let listed: Vec<Uuid> = serde_json::from_slice::<Vec<Checkpoint>>(&bytes)
+            .unwrap()
+            .into_iter()
+            .map(|checkpoint| checkpoint.checkpoint_id)
+            .collect();
+        assert_eq!(listed, created[1..]);
+
+        for endpoint in [
+            format!("/checkpoints/{}", created[0]),
+            format!("/accounts/{account_id}/proof?checkpoint={}", created[0]),
+        ] {
+            let response = app(shared_state.clone().into())
+                .oneshot(get_request(&endpoint))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_issue_signed_account_statements() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 50000);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account]),
+            ..Repositories::default()
+        };
+
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let key_file = std::env::temp_dir().join(format!("statements-{}.pem", Uuid::new_v4()));
+        std::fs::write(&key_file, signing_key.to_pkcs8_pem(LineEnding::LF).unwrap().as_bytes()).unwrap();
+
+        let signer = StatementSigner::from_key_file(key_file.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&key_file).unwrap();
+
+        let app_state = AppState {
+            repos: Arc::new(repos),
+            signer: Some(Arc::new(signer)),
+            ..AppState::default()
+        };
+
+        let before_transactions = Utc::now();
+
+        for (lhs_account_id, rhs_account_id, amount) in [
+            (savings_account_id, main_account_id, 30000),
+            (main_account_id, savings_account_id, 5000),
+        ] {
+            let new_transaction = CreateNewTransaction::new_debit(lhs_account_id, rhs_account_id, "Transfer", amount);
+            let request = post_request("/transactions/new", new_transaction);
+            app(app_state.clone()).oneshot(request).await.unwrap();
+        }
+
+        let response = app(app_state.clone()).oneshot(get_request("/keys")).await.unwrap();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let public_keys: PublicKeys = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        // When
+        let endpoint = format!("/accounts/{savings_account_id}/statement");
+        let response = app(app_state.clone()).oneshot(get_request(&endpoint)).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let signed: SignedStatement = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        assert_eq!(signed.statement.opening_balance, 100000);
+        assert_eq!(signed.statement.closing_balance, 75000);
+        assert_eq!(signed.statement.entries.len(), 2);
+        assert!(verify_statement(&signed, &public_keys));
+
+        let mut tampered = signed.clone();
+        tampered.statement.closing_balance = 175000;
+        assert!(!verify_statement(&tampered, &public_keys));
+        assert!(!verify_statement(&signed, &PublicKeys::default()));
+
+        // When
+        let until = before_transactions.to_rfc3339_opts(SecondsFormat::Nanos, true);
+        let endpoint = format!(
+            "/accounts/{savings_account_id}/statement?until={}",
+            until.replace('+', "%2B")
+        );
+        let response = app(app_state.clone()).oneshot(get_request(&endpoint)).await.unwrap();
+
+        // Then
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let signed: SignedStatement = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        assert_eq!(signed.statement.closing_balance, 100000);
+        assert!(signed.statement.entries.is_empty());
+        assert!(verify_statement(&signed, &public_keys));
+
+        // Given
+        let first_entry = app_state.repos.journal.read().iter().next().unwrap().clone();
+        app_state.repos.journal.write().save_entries(vec![JournalEntry {
+            entry_id: Uuid::new_v4(),
+            account_id: savings_account_id,
+            movement_type: MovementType::Credit,
+            amount_in_cents: 500000,
+            ..first_entry
+        }]);
+
+        // When
+        let endpoint = format!("/accounts/{savings_account_id}/statement");
+        let response = app(app_state.clone()).oneshot(get_request(&endpoint)).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        // When
+        let unsigned_state = AppState {
+            signer: None,
+            ..app_state
+        };
+        let endpoint = format!("/accounts/{savings_account_id}/statement");
+        let response = app(unsigned_state).oneshot(get_request(&endpoint)).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn should_post_batches_atomically_or_item_by_item() {
+        // Given
+        let payroll_account = Account::new("payroll", 5000);
+        let alice_account = Account::new("alice", 0);
+        let bob_account = Account::new("bob", 0);
+
+        let payroll_account_id = payroll_account.account_id;
+        let alice_account_id = alice_account.account_id;
+        let bob_account_id = bob_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![payroll_account, alice_account, bob_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let batch = |atomic: bool| CreateTransactionsBatch {
+            atomic,
+            transactions: vec![
+                CreateNewTransaction::new_debit(payroll_account_id, alice_account_id, "Salary", 3000),
+                CreateNewTransaction::new_debit(payroll_account_id, Uuid::new_v4(), "Salary", 1000),
+                CreateNewTransaction::new_debit(payroll_account_id, bob_account_id, "Salary", 3000),
+                CreateNewTransaction::new_debit(payroll_account_id, bob_account_id, "Bonus", 2000),
+            ],
+        };
+
+        let post_batch = |batch: CreateTransactionsBatch| {
+            let shared_state = shared_state.clone();
+            async move {
+                let request = post_request("/transactions/batch", batch);
+                let response = app(shared_state.into()).oneshot(request).await.unwrap();
+                let status = response.status();
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<BatchReport>(bytes.iter().as_slice()).unwrap(),
+                )
+            }
+        };
+
+        let outcomes = |report: &BatchReport| -> Vec<(BatchItemOutcome, u16)> {
+            report
+                .results
+                .iter()
+                .map(|result| (result.outcome, result.status))
+                .collect()
+        };
+
+        // When
+        let (status, report) = post_batch(batch(true)).await;
+
+        // Then
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!((report.created, report.failed), (0, 2));
+        assert_eq!(
+            outcomes(&report),
+            vec![
+                (BatchItemOutcome::RolledBack, 424),
+                (BatchItemOutcome::Failed, 404),
+                (BatchItemOutcome::Failed, 409),
+                (BatchItemOutcome::RolledBack, 424),
+            ]
+        );
+
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&payroll_account_id).unwrap().balance,
+            5000
+        );
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&payroll_account_id).unwrap().version,
+            1
+        );
+        assert_eq!(shared_state.journal.read().count(), 0);
+
+        // When
+        let (status, report) = post_batch(batch(false)).await;
+
+        // Then
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((report.created, report.failed), (2, 2));
+        assert_eq!(
+            outcomes(&report),
+            vec![
+                (BatchItemOutcome::Created, 200),
+                (BatchItemOutcome::Failed, 404),
+                (BatchItemOutcome::Failed, 409),
+                (BatchItemOutcome::Created, 200),
+            ]
+        );
+
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&alice_account_id).unwrap().balance,
+            3000
+        );
+        assert_eq!(
+            shared_state.accounts.fetch_by_id(&bob_account_id).unwrap().balance,
+            2000
+        );
+        assert_eq!(shared_state.journal.read().count(), 4);
+
+        let bonus_id = report.results[3].transaction.as_ref().unwrap().transaction_id;
+        let bonus = shared_state.transactions.read().fetch_transaction(&bonus_id).cloned();
+        assert_eq!(bonus.unwrap().description, "Bonus");
+
+        let response = app(shared_state.into())
+            .oneshot(get_request("/integrity"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_import_accounts_with_opening_balances() {
+        // Given
+        let existing_account = Account::new("assets", 0);
+        let existing_account_id = existing_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![existing_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let rejected_csv = "alias,type,currency,parent,opening_balance_in_cents
+assets.bank,asset,EUR,assets,150000
+assets.bank,asset,EUR,assets,100
+assets.brokerage,asset,EURO,,100
+assets.petty-cash,gadget,EUR,assets.safe,100
+";
+
+        let import = |csv: &'static str, dry_run: bool| {
+            let shared_state = shared_state.clone();
+            async move {
+                let endpoint = format!("/accounts/import?dry_run={dry_run}");
+                let request = Request::builder()
+                    .method(Method::POST)
+                    .header(header::CONTENT_TYPE, "text/csv")
+                    .uri(endpoint)
+                    .body(Body::from(csv))
+                    .unwrap();
+
+                let response = app(shared_state.into()).oneshot(request).await.unwrap();
+                let status = response.status();
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<AccountsImportReport>(bytes.iter().as_slice()).unwrap(),
+                )
+            }
+        };
+
+        // When
+        let (status, report) = import(rejected_csv, false).await;
+
+        // Then
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!((report.imported, report.rejected), (0, 3));
+
+        let errors: Vec<(u64, Vec<String>)> = report.rows.into_iter().map(|row| (row.line, row.errors)).collect();
+        assert_eq!(
+            errors,
+            vec![
+                (2, vec![]),
+                (3, vec!["Alias assets.bank repeated in this file".to_string()]),
+                (4, vec!["Invalid currency EURO".to_string()]),
+                (
+                    5,
+                    vec![
+                        "Invalid account type gadget".to_string(),
+                        "Parent account assets.safe not found".to_string(),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(shared_state.accounts.count(), 1);
+
+        // Given
+        let accepted_csv = "alias,type,currency,parent,opening_balance_in_cents
+assets.bank,asset,EUR,assets,150000
+assets.bank.savings,asset,eur,assets.bank,50000
+liabilities.card,liability,EUR,,
+";
+
+        // When
+        let (status, report) = import(accepted_csv, true).await;
+
+        // Then
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((report.dry_run, report.imported, report.rejected), (true, 0, 0));
+        assert_eq!(shared_state.accounts.count(), 1);
+
+        // When
+        let (address, trigger, server) = spawn_server(shared_state.clone(), Duration::from_secs(5)).await;
+        let server_options = ServerOptions {
+            url: format!("http://{address}"),
+            api_key: None,
+            ledger_id: Some(DEFAULT_LEDGER_ID),
+        };
+        let report = client::import_accounts(&server_options, accepted_csv.to_string(), false)
+            .await
+            .unwrap();
+        trigger.send(()).unwrap();
+        server.await.unwrap().unwrap();
+
+        // Then
+        assert_eq!((report.dry_run, report.imported, report.rejected), (false, 3, 0));
+
+        let bank_id = report.rows[0].account_id.unwrap();
+        let savings_id = report.rows[1].account_id.unwrap();
+        let bank = shared_state.accounts.fetch_by_id(&bank_id).unwrap();
+        let savings = shared_state.accounts.fetch_by_id(&savings_id).unwrap();
+        let card = shared_state
+            .accounts
+            .fetch_by_alias(&DEFAULT_LEDGER_ID, "liabilities.card")
+            .unwrap();
+
+        assert_eq!((bank.balance, bank.parent_id), (150000, Some(existing_account_id)));
+        assert_eq!((savings.balance, savings.parent_id), (50000, Some(bank_id)));
+        assert_eq!(savings.currency, "EUR");
+        assert_eq!((card.balance, card.account_type), (0, AccountType::Liability));
+        assert!(report.rows[2].opening_transaction_id.is_none());
+
+        let equity = shared_state
+            .accounts
+            .fetch_by_alias(&DEFAULT_LEDGER_ID, &imports::opening_balances_alias("EUR"))
+            .unwrap();
+        assert_eq!(equity.account_type, AccountType::Equity);
+
+        let opening_transaction_id = report.rows[0].opening_transaction_id.unwrap();
+        let entries = shared_state
+            .journal
+            .read()
+            .fetch_by_transaction(&opening_transaction_id);
+        let accounts: Vec<(Uuid, MovementType)> = entries
+            .iter()
+            .map(|entry| (entry.account_id, entry.movement_type))
+            .collect();
+        assert_eq!(
+            accounts,
+            vec![
+                (equity.account_id, MovementType::Debit),
+                (bank_id, MovementType::Credit)
+            ]
+        );
+
+        // Then the equity account carries the contra balance, explained by the journal
+        let equity_entries: i64 = shared_state
+            .journal
+            .read()
+            .iter()
+            .filter(|entry| entry.account_id == equity.account_id)
+            .map(|entry| match entry.movement_type {
+                MovementType::Credit => entry.amount_in_cents as i64,
+                MovementType::Debit => -(entry.amount_in_cents as i64),
+            })
+            .sum();
+        assert_eq!((equity.balance, equity_entries), (-200000, -200000));
+
+        // Given
+        let failing_csv = "alias,type,currency,parent,opening_balance_in_cents
+equity.opening-balances.usd,asset,USD,,
+assets.usd,asset,USD,,1000
+";
+
+        // When
+        let accounts_before = shared_state.accounts.count();
+        let error = imports::import_accounts(&shared_state, DEFAULT_LEDGER_ID, failing_csv, false).unwrap_err();
+
+        // Then
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(shared_state.accounts.count(), accounts_before);
+        assert!(
+            shared_state
+                .accounts
+                .fetch_by_alias(&DEFAULT_LEDGER_ID, "assets.usd")
+                .is_none()
+        );
+
+        // Given
+        let oversized_csv: String = std::iter::once("alias\n".to_string())
+            .chain((0..=imports::MAX_IMPORTED_ROWS).map(|index| format!("assets.bulk.{index}\n")))
+            .collect();
+
+        // When
+        let error = imports::import_accounts(&shared_state, DEFAULT_LEDGER_ID, &oversized_csv, true).unwrap_err();
+
+        // Then
+        assert_eq!(error.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = app(shared_state.into())
+            .oneshot(get_request("/integrity"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_stream_journal_and_transaction_exports() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 0);
+        let card_account = Account::new("ufs.card", 0);
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+        let card_account_id = card_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account, card_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        // Enough entries to span several export chunks
+        let transfers = CreateTransactionsBatch {
+            atomic: true,
+            transactions: (0..300)
+                .map(|index| {
+                    CreateNewTransaction::new_debit(
+                        savings_account_id,
+                        main_account_id,
+                        &format!("Transfer, #{index}"),
+                        100,
+                    )
+                })
+                .collect(),
+        };
+        let report = transactions::post_batch(&shared_state, DEFAULT_LEDGER_ID, transfers);
+        assert_eq!(report.created, 300);
+
+        let before_card_payment = Utc::now();
+        let payment = CreateNewTransaction::new_debit(main_account_id, card_account_id, "Card \"payment\"", 2500);
+        transactions::post_transaction(&shared_state, DEFAULT_LEDGER_ID, payment).unwrap();
+
+        // When
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request("/journal/export?format=csv"))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv; charset=utf-8");
+        assert_eq!(response.headers()[exports::SCHEMA_VERSION_HEADER], "1");
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut reader = csv::Reader::from_reader(bytes.iter().as_slice());
+        assert_eq!(reader.headers().unwrap(), JournalEntry::COLUMNS);
+
+        let rows: Vec<JournalEntryRow> = reader.deserialize().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 602);
+        assert!(rows.iter().all(|row| row.schema_version == exports::SCHEMA_VERSION));
+        assert_eq!(rows[601].account_id, card_account_id);
+        assert_eq!(rows[601].hash, shared_state.journal.read().head_hash());
+
+        // When
+        let (address, trigger, server) = spawn_server(shared_state.clone(), Duration::from_secs(5)).await;
+        let server_options = ServerOptions {
+            url: format!("http://{address}"),
+            api_key: None,
+            ledger_id: None,
+        };
+        let options = ExportOptions {
+            format: ExportFormat::Ndjson,
+            account_id: Some(main_account_id),
+            from: Some(before_card_payment),
+            until: None,
+        };
+        let mut output = Vec::new();
+        client::export(&server_options, "transactions", &options, &mut output)
+            .await
+            .unwrap();
+        trigger.send(()).unwrap();
+        server.await.unwrap().unwrap();
+
+        // Then
+        let lines: Vec<TransactionRow> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].description, "Card \"payment\"");
+        assert_eq!(lines[0].amount_in_cents, 2500);
+        assert_eq!(lines[0].schema_version, exports::SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn should_round_trip_ledgers_through_plaintext_journals() {
+        // Given
+        let savings_account = Account::new("ufs.savings", 100000);
+        let main_account = Account::new("ufs.main", 20000);
+        let rent_account = Account {
+            account_type: AccountType::Expense,
+            ..Account::new("expenses.rent", 0)
+        };
+
+        let savings_account_id = savings_account.account_id;
+        let main_account_id = main_account.account_id;
+        let rent_account_id = rent_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![savings_account, main_account, rent_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let postings = [
+            CreateNewTransaction::new_debit(savings_account_id, main_account_id, "Monthly \"top-up\"", 50000),
+            CreateNewTransaction::new_credit(rent_account_id, main_account_id, "Rent; June", 32050),
+        ];
+
+        for payload in postings {
+            transactions::post_transaction(&shared_state, DEFAULT_LEDGER_ID, payload).unwrap();
+        }
+
+        let balances = |ledger_id: Uuid| -> Vec<(String, i64)> {
+            ["ufs.savings", "ufs.main", "rent"]
+                .into_iter()
+                .map(|alias| {
+                    shared_state
+                        .accounts
+                        .fetch_by_alias(&ledger_id, alias)
+                        .map(|account| account.balance)
+                })
+                .zip(["ufs.savings", "ufs.main", "rent"])
+                .map(|(balance, alias)| (alias.to_string(), balance.unwrap_or_default()))
+                .collect()
+        };
+
+        for format in ["ledger", "beancount"] {
+            // When
+            let endpoint = format!("/plaintext/export?format={format}");
+            let response = app(shared_state.clone().into())
+                .oneshot(get_request(&endpoint))
+                .await
+                .unwrap();
+
+            // Then
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let journal = String::from_utf8(bytes.to_vec()).unwrap();
+
+            assert!(journal.contains("Assets:Ufs:Savings"));
+            assert!(journal.contains("Expenses:Rent"));
+            assert!(journal.contains("Equity:Initial-Balances:EUR"));
+            assert!(journal.contains("-320.50 EUR"));
+
+            // Given
+            let ledger_id = Uuid::new_v4();
+            shared_state.ledgers.write().save_ledger(Ledger {
+                ledger_id,
+                name: format.to_string(),
+                created_at: Utc::now(),
+            });
+
+            // When
+            let request = Request::builder()
+                .method(Method::POST)
+                .header(header::CONTENT_TYPE, "text/plain")
+                .uri(format!("/ledgers/{ledger_id}/plaintext/import"))
+                .body(Body::from(journal))
+                .unwrap();
+            let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let report: PlainTextImportReport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+            assert_eq!((report.accounts_created, report.transactions_posted), (4, 4));
+
+            assert_eq!(
+                balances(ledger_id),
+                vec![
+                    ("ufs.savings".to_string(), 50000),
+                    ("ufs.main".to_string(), 37950),
+                    ("rent".to_string(), 32050)
+                ]
+            );
+
+            let rent = shared_state.accounts.fetch_by_alias(&ledger_id, "rent").unwrap();
+            assert_eq!(rent.account_type, AccountType::Expense);
+
+            let transactions = shared_state.transactions.read();
+            let descriptions: Vec<&str> = transactions
+                .iter()
+                .filter(|tx| tx.ledger_id == ledger_id)
+                .map(|tx| tx.description.as_str())
+                .collect();
+            assert_eq!(
+                descriptions,
+                vec![
+                    "Initial balances",
+                    "Initial balances",
+                    "Monthly \"top-up\"",
+                    "Rent; June"
+                ]
+            );
+        }
+
+        // When
+        let journal = "2025-06-01 * Split bill
+    Expenses:Food  10.00 EUR
+    Expenses:Drinks  5.00 EUR
+    Assets:Cash
+
+2025-06-02 Refund
+    Assets:Cash  5 EUR
+    Somewhere:Else
+";
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/plaintext/import")
+            .body(Body::from(journal))
+            .unwrap();
+        let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: PlainTextImportReport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        let lines: Vec<usize> = report.errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![1, 6]);
+        assert_eq!(report.transactions_posted, 0);
+
+        // Given
+        let ledger_id = Uuid::new_v4();
+        shared_state.ledgers.write().save_ledger(Ledger {
+            ledger_id,
+            name: "lunches".to_string(),
+            created_at: Utc::now(),
+        });
+
+        let import = |journal: &'static str| {
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(format!("/ledgers/{ledger_id}/plaintext/import"))
+                .body(Body::from(journal))
+                .unwrap();
+            app(shared_state.clone().into()).oneshot(request)
+        };
+
+        // When
+        let response = import(
+            "2025-06-03 * Lunch
+    Expenses:Food  12.50 EUR
+    Assets:Wallet
+",
+        )
+        .await
+        .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: PlainTextImportReport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!((report.accounts_created, report.transactions_posted), (3, 2));
+
+        let balance_of = |alias: &str| {
+            shared_state
+                .accounts
+                .fetch_by_alias(&ledger_id, alias)
+                .map(|account| account.balance)
+        };
+        assert_eq!(balance_of("wallet"), Some(0));
+        assert_eq!(balance_of("equity.opening-balances.eur"), Some(-1250));
+
+        let booked: Vec<(String, NaiveDate)> = shared_state
+            .transactions
+            .read()
+            .iter()
+            .filter(|tx| tx.ledger_id == ledger_id)
+            .map(|tx| (tx.description.clone(), tx.created_at.date_naive()))
+            .collect();
+        let june_third = NaiveDate::from_ymd_opt(2025, 6, 3).unwrap();
+        assert_eq!(
+            booked,
+            vec![
+                ("Opening balance".to_string(), june_third),
+                ("Lunch".to_string(), june_third)
+            ]
+        );
+
+        // When
+        let response = import(
+            "2025-06-04 * Yacht
+    Expenses:Boats  100000000000000000.00 EUR
+    Assets:Pocket
+",
+        )
+        .await
+        .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: PlainTextImportReport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert_eq!((report.accounts_created, report.transactions_posted), (0, 0));
+        assert_eq!(balance_of("pocket"), None);
+        assert_eq!(balance_of("boats"), None);
+    }
+
+    #[tokio::test]
+    async fn should_import_bank_statements_as_external_transactions() {
+        // Given
+        let accounts: Vec<Account> = ["ufs.main", "ufs.savings", "ufs.card"]
+            .into_iter()
+            .map(|alias| Account::new(alias, 0))
+            .collect();
+        let account_ids: Vec<Uuid> = accounts.iter().map(|account| account.account_id).collect();
+        let main_account_id = account_ids[0];
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(accounts),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let camt053 = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <BkToCstmrStmt>
+    <GrpHdr><MsgId>MSG-0602</MsgId><CreDtTm>2025-06-04T08:00:00</CreDtTm></GrpHdr>
+    <Stmt>
+      <Id>STMT-0602</Id>
+      <Ntry>
+        <Amt Ccy="EUR">1250.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>2025-06-01</Dt></BookgDt>
+        <ValDt><Dt>2025-06-01</Dt></ValDt>
+        <AcctSvcrRef>BANK-REF-1</AcctSvcrRef>
+        <NtryDtls>
+          <TxDtls>
+            <Refs><EndToEndId>INV-42</EndToEndId></Refs>
+            <RltdPties><Dbtr><Nm>ACME Corp</Nm></Dbtr></RltdPties>
+            <RmtInf><Ustrd>Invoice 42</Ustrd></RmtInf>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">320.50</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><DtTm>2025-06-03T10:00:00</DtTm></BookgDt>
+        <AcctSvcrRef>BANK-REF-2</AcctSvcrRef>
+        <AddtlNtryInf>Rent June</AddtlNtryInf>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">10.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts>PDNG</Sts>
+        <BookgDt><Dt>2025-06-04</Dt></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+        let mt940 = "{1:F01BANKDEFFXXXX0000000000}{2:O940BANKDEFFXXXXN}{4:
+:20:STMT-0602
+:25:DE89370400440532013000
+:28C:00001/001
+:60F:C250531EUR1000,00
+:61:2506010601C1250,00NTRFINV-42//BANK-REF-1
+ACME invoice payment
+:86:166?00GUTSCHRIFT?20Invoice 42?32ACME Corp
+:61:2506030603D320,5NMSCNONREF
+:86:Rent June
+:62F:C250603EUR1929,50
+-}
+";
+
+        let ofx = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<CURDEF>EUR
+<BANKACCTFROM><BANKID>37040044<ACCTID>0532013000<ACCTTYPE>CHECKING</BANKACCTFROM>
+<BANKTRANLIST>
+<DTSTART>20250601
+<DTEND>20250630
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20250601120000.000[+2:CEST]
+<TRNAMT>1250.00
+<FITID>BANK-REF-1
+<NAME>ACME Corp
+<MEMO>Invoice 42
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20250603
+<TRNAMT>-320.50
+<FITID>BANK-REF-2
+<NAME>Landlord
+<MEMO>Rent June
+</STMTTRN>
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>
+";
+
+        let import = |account_id: Uuid, statement: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .header(header::CONTENT_TYPE, "text/plain")
+                .uri(format!("/accounts/{account_id}/external-transactions/import"))
+                .body(Body::from(statement.to_string()))
+                .unwrap()
+        };
+
+        let june = |day| NaiveDate::from_ymd_opt(2025, 6, day).unwrap();
+
+        let statements = [
+            (camt053, BankStatementFormat::Camt053),
+            (mt940, BankStatementFormat::Mt940),
+            (ofx, BankStatementFormat::Ofx),
+        ];
+
+        for ((statement, format), account_id) in statements.into_iter().zip(account_ids) {
+            // When
+            let response = app(shared_state.clone().into())
+                .oneshot(import(account_id, statement))
+                .await
+                .unwrap();
+
+            // Then
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let report: BankStatementImport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+            assert_eq!(report.format, format);
+            assert_eq!((report.imported, report.duplicates), (2, 0));
+
+            let lines: Vec<(NaiveDate, MovementType, u64, &str)> = report
+                .transactions
+                .iter()
+                .map(|line| {
+                    (
+                        line.booked_on,
+                        line.movement_type,
+                        line.amount_in_cents,
+                        line.description.as_str(),
+                    )
+                })
+                .collect();
+
+            assert_eq!(
+                lines,
+                vec![
+                    (june(1), MovementType::Credit, 125000, "Invoice 42"),
+                    (june(3), MovementType::Debit, 32050, "Rent June")
+                ]
+            );
+
+            let payment = &report.transactions[0];
+            assert_eq!(payment.account_id, account_id);
+            assert_eq!(payment.currency, "EUR");
+            assert_eq!(payment.counterparty.as_deref(), Some("ACME Corp"));
+            assert_eq!(payment.bank_reference.as_deref(), Some("BANK-REF-1"));
+        }
+
+        for statement in [camt053, ofx] {
+            // When
+            let response = app(shared_state.clone().into())
+                .oneshot(import(main_account_id, statement))
+                .await
+                .unwrap();
+
+            // Then
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let report: BankStatementImport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+            assert_eq!((report.imported, report.duplicates), (0, 2));
+        }
+
+        // When
+        let endpoint = format!("/accounts/{main_account_id}/external-transactions?from=2025-06-02");
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request(&endpoint))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let listed: Vec<ExternalTransaction> = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        let references: Vec<Option<&str>> = listed.iter().map(|line| line.bank_reference.as_deref()).collect();
+        assert_eq!(references, vec![Some("BANK-REF-2")]);
+
+        // When
+        let rejected = [
+            (
+                import(main_account_id, &camt053.replace("EUR", "USD")),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                import(main_account_id, &mt940.replace(":60F:", ":60X:")),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                import(main_account_id, "Date,Amount\n2025-06-01,12.50"),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/accounts/{}/external-transactions/import", Uuid::new_v4()))
+                    .body(Body::from(ofx))
+                    .unwrap(),
+                StatusCode::NOT_FOUND,
+            ),
+        ];
+
+        for (request, expected_status) in rejected {
+            let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+
+            // Then
+            assert_eq!(response.status(), expected_status);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_read_reversals_year_boundaries_and_ofx_xml_from_bank_statements() {
+        // Given
+        let euro_account = Account::new("ufs.main", 0);
+        let euro_account_id = euro_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![euro_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let mt940 = ":20:STMT-1231
+:60F:C241230EUR1000,00
+:61:2501011231C100,00NTRFREF-1//BANK-REF-1
+:61:2412310101D50,00NTRFREF-2//BANK-REF-2
+:61:250102RC25,00NTRFREF-3//BANK-REF-3
+:61:250102RD10,00NTRFREF-4//BANK-REF-4
+:62F:C250102EUR1035,00
+";
+
+        let ofx = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<?OFX OFXHEADER="200" VERSION="220" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>
+<OFX>
+  <BANKMSGSRSV1>
+    <STMTTRNRS>
+      <STMTRS>
+        <CURDEF>EUR</CURDEF>
+        <BANKTRANLIST>
+          <STMTTRN>
+            <TRNTYPE>DEBIT</TRNTYPE>
+            <DTPOSTED>20250603100000.000[+2:CEST]</DTPOSTED>
+            <DTUSER>20250602</DTUSER>
+            <TRNAMT>-320.50</TRNAMT>
+            <FITID>BANK-REF-5</FITID>
+            <NAME>Landlord</NAME>
+            <MEMO>Rent June</MEMO>
+          </STMTTRN>
+        </BANKTRANLIST>
+      </STMTRS>
+    </STMTTRNRS>
+  </BANKMSGSRSV1>
+</OFX>
+"#;
+
+        let date = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        let import = |statement: &str| {
+            bank_statements::import_statement(&shared_state, DEFAULT_LEDGER_ID, euro_account_id, None, statement)
+        };
+
+        // When
+        let report = import(mt940).unwrap();
+
+        // Then reversals move money back, and entry dates fall in the year closest to value dates
+        assert_eq!(report.format, BankStatementFormat::Mt940);
+
+        let lines: Vec<(NaiveDate, Option<NaiveDate>, MovementType, u64)> = report
+            .transactions
+            .iter()
+            .map(|line| (line.booked_on, line.value_on, line.movement_type, line.amount_in_cents))
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                (date(2024, 12, 31), Some(date(2025, 1, 1)), MovementType::Credit, 10000),
+                (date(2025, 1, 1), Some(date(2024, 12, 31)), MovementType::Debit, 5000),
+                (date(2025, 1, 2), Some(date(2025, 1, 2)), MovementType::Debit, 2500),
+                (date(2025, 1, 2), Some(date(2025, 1, 2)), MovementType::Credit, 1000),
+            ]
+        );
+
+        // When
+        let report = import(ofx).unwrap();
+
+        // Then
+        assert_eq!(report.format, BankStatementFormat::Ofx);
+
+        let rent = &report.transactions[0];
+        assert_eq!(report.transactions.len(), 1);
+        assert_eq!(
+            (rent.booked_on, rent.value_on),
+            (date(2025, 6, 3), Some(date(2025, 6, 2)))
+        );
+        assert_eq!((rent.movement_type, rent.amount_in_cents), (MovementType::Debit, 32050));
+        assert_eq!(
+            (rent.currency.as_str(), rent.description.as_str()),
+            ("EUR", "Rent June")
+        );
+        assert_eq!(rent.counterparty.as_deref(), Some("Landlord"));
+        assert_eq!(rent.bank_reference.as_deref(), Some("BANK-REF-5"));
+
+        // When
+        let error = import(&mt940.replace("EUR", "USD")).unwrap_err();
+
+        // Then
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // When
+        let oversized_ofx = format!(
+            "<OFX><CURDEF>EUR{}</OFX>",
+            (0..=bank_statements::MAX_STATEMENT_LINES)
+                .map(|index| format!("<STMTTRN><DTPOSTED>20250601<TRNAMT>1.00<FITID>BULK-{index}</STMTTRN>"))
+                .collect::<String>()
+        );
+        let error = import(&oversized_ofx).unwrap_err();
+
+        // Then
+        assert_eq!(error.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            shared_state
+                .external_transactions
+                .read()
+                .for_account(&euro_account_id, None, None)
+                .count(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn should_reconcile_bank_statements_with_ledger_transactions() {
+        // Given
+        let main_account = Account::new("ufs.main", 100000);
+        let customers_account = Account::new("customers", 500000);
+        let suppliers_account = Account::new("suppliers", 0);
+
+        let main_account_id = main_account.account_id;
+        let customers_account_id = customers_account.account_id;
+        let suppliers_account_id = suppliers_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![main_account, customers_account, suppliers_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let postings = [
+            (
+                "invoice",
+                customers_account_id,
+                main_account_id,
+                "ACME invoice 42",
+                125000,
+            ),
+            ("rent", main_account_id, suppliers_account_id, "Rent June", 32050),
+            (
+                "groceries",
+                main_account_id,
+                suppliers_account_id,
+                "Card payment groceries",
+                4000,
+            ),
+            ("fuel", main_account_id, suppliers_account_id, "Card payment fuel", 6000),
+            ("salary", customers_account_id, main_account_id, "Salary June", 50000),
+            (
+                "subscription",
+                main_account_id,
+                suppliers_account_id,
+                "Streaming subscription",
+                999,
+            ),
+        ];
+
+        let mut transaction_ids = HashMap::new();
+        for (name, from, to, description, amount) in postings {
+            let payload = CreateNewTransaction::new_debit(from, to, description, amount);
+            let created = transactions::post_transaction(&shared_state, DEFAULT_LEDGER_ID, payload).unwrap();
+            transaction_ids.insert(name, created.transaction_id);
+        }
+
+        let today = Utc::now().format("%Y%m%d");
+        let statement_line = |fitid: &str, amount: &str, memo: &str| {
+            format!("<STMTTRN><DTPOSTED>{today}<TRNAMT>{amount}<FITID>{fitid}<MEMO>{memo}</STMTTRN>")
+        };
+
+        let ofx = format!(
+            "OFXHEADER:100\n<OFX><STMTRS><CURDEF>EUR<BANKTRANLIST>{}</BANKTRANLIST></STMTRS></OFX>",
+            [
+                statement_line("1", "1250.00", "Invoice 42 ACME Corp"),
+                statement_line("2", "-320.50", "RENT JUNE"),
+                statement_line("3", "-100.00", "Card settlement"),
+                statement_line("4", "300.00", "Salary June part 1"),
+                statement_line("5", "200.00", "Salary June part 2"),
+                statement_line("6", "-15.00", "Bank fee"),
+            ]
+            .concat()
+        );
+
+        let import = bank_statements::import_statement(&shared_state, DEFAULT_LEDGER_ID, main_account_id, None, &ofx);
+        let lines = import.unwrap().transactions;
+        let external_id = |fitid: &str| {
+            lines
+                .iter()
+                .find(|line| line.bank_reference.as_deref() == Some(fitid))
+                .map(|line| line.external_id)
+                .unwrap()
+        };
+
+        let endpoint = format!("/accounts/{main_account_id}/reconciliation");
+        let post = |path: &str, body: String| {
+            Request::builder()
+                .method(Method::POST)
+                .header(header::CONTENT_TYPE, "application/json")
+                .uri(format!("{endpoint}{path}"))
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        // When
+        let response = app(shared_state.clone().into())
+            .oneshot(post("/run?date_window_days=1", String::new()))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let matches: Vec<ReconciliationMatch> = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+
+        let mut matched: Vec<(MatchKind, Vec<Uuid>, Vec<Uuid>)> = matches
+            .iter()
+            .map(|matched| {
+                let mut transactions = matched.transaction_ids.clone();
+                transactions.sort();
+                (matched.kind, matched.external_ids.clone(), transactions)
+            })
+            .collect();
+        matched.sort_by_key(|(_, external_ids, _)| external_ids.clone());
+
+        let mut card_payments = vec![transaction_ids["groceries"], transaction_ids["fuel"]];
+        card_payments.sort();
+
+        let mut expected = vec![
+            (
+                MatchKind::OneToOne,
+                vec![external_id("1")],
+                vec![transaction_ids["invoice"]],
+            ),
+            (
+                MatchKind::OneToOne,
+                vec![external_id("2")],
+                vec![transaction_ids["rent"]],
+            ),
+            (MatchKind::OneToMany, vec![external_id("3")], card_payments),
+            (
+                MatchKind::ManyToOne,
+                vec![external_id("4"), external_id("5")],
+                vec![transaction_ids["salary"]],
+            ),
+        ];
+        expected.sort_by_key(|(_, external_ids, _)| external_ids.clone());
+
+        assert_eq!(matched, expected);
+        assert!(matches.iter().all(|matched| matched.method == MatchMethod::Automatic));
+
+        // When
+        let response = app(shared_state.clone().into())
+            .oneshot(get_request(&endpoint))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: ReconciliationReport = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert!(!report.reconciled);
+        assert_eq!(report.matched.len(), 4);
+
+        let unmatched: Vec<Uuid> = report
+            .unmatched_transactions
+            .iter()
+            .map(|tx| tx.transaction_id)
+            .collect();
+        assert_eq!(unmatched, vec![transaction_ids["subscription"]]);
+
+        let unmatched: Vec<Uuid> = report
+            .unmatched_external_transactions
+            .iter()
+            .map(|line| line.external_id)
+            .collect();
+        assert_eq!(unmatched, vec![external_id("6")]);
+
+        // When
+        let response = app(shared_state.clone().into())
+            .oneshot(post(
+                "/run?max_group_size=1000000&from=2000-01-01&until=2000-02-01",
+                String::new(),
+            ))
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let matches_elsewhere: Vec<ReconciliationMatch> = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+        assert!(matches_elsewhere.is_empty());
+
+        // When
+        let rent_match = matches
+            .iter()
+            .find(|matched| matched.transaction_ids == vec![transaction_ids["rent"]])
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::DELETE)
+            .uri(format!("{endpoint}/matches/{}", rent_match.match_id))
+            .body(Body::empty())
+            .unwrap();
+        let response = app(shared_state.clone().into()).oneshot(request).await.unwrap();
+
+        // Then
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let report = reconciliation::build_report(&shared_state, DEFAULT_LEDGER_ID, main_account_id, None, None);
+        assert_eq!(report.unmatched_transactions.len(), 2);
+        assert_eq!(report.unmatched_external_transactions.len(), 2);
+
+        // When
+        let manual_matches = [
+            (vec!["6"], vec!["subscription"], StatusCode::UNPROCESSABLE_ENTITY),
+            (vec!["2"], vec!["rent"], StatusCode::OK),
+            (vec!["2"], vec!["rent"], StatusCode::CONFLICT),
+            (
+                vec!["4", "6"],
+                vec!["rent", "subscription"],
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+        ];
+
+        for (external_ids, transactions, expected_status) in manual_matches {
+            let payload = CreateNewMatch {
+                external_ids: external_ids.into_iter().map(external_id).collect(),
+                transaction_ids: transactions.into_iter().map(|name| transaction_ids[name]).collect(),
+            };
+            let response = app(shared_state.clone().into())
+                .oneshot(post("/matches", json!(payload).to_string()))
+                .await
+                .unwrap();
+
+            // Then
+            assert_eq!(response.status(), expected_status);
+
+            if expected_status == StatusCode::OK {
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let matched: ReconciliationMatch = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
+                assert_eq!(matched.kind, MatchKind::OneToOne);
+                assert_eq!(matched.method, MatchMethod::Manual);
+            }
+        }
+
+        let report = reconciliation::build_report(&shared_state, DEFAULT_LEDGER_ID, main_account_id, None, None);
+        assert_eq!(report.matched.len(), 4);
+        assert_eq!(report.unmatched_transactions.len(), 1);
+        assert_eq!(report.unmatched_external_transactions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_reject_manual_matches_with_other_totals_or_matched_lines() {
+        // Given
+        let main_account = Account::new("ufs.main", 100000);
+        let suppliers_account = Account::new("suppliers", 0);
+
+        let main_account_id = main_account.account_id;
+        let suppliers_account_id = suppliers_account.account_id;
+
+        let repos = Repositories {
+            accounts: AccountsRepository::from(vec![main_account, suppliers_account]),
+            ..Repositories::default()
+        };
+
+        let shared_state = Arc::new(repos);
+
+        let post = |description: &str, amount| {
+            let payload = CreateNewTransaction::new_debit(main_account_id, suppliers_account_id, description, amount);
+            transactions::post_transaction(&shared_state, DEFAULT_LEDGER_ID, payload)
+                .unwrap()
+                .transaction_id
+        };
+
+        let rent_id = post("Rent June", 32050);
+        let other_rent_id = post("Rent July", 32050);
+        let fee_id = post("Bank fee", 1500);
+
+        let today = Utc::now().format("%Y%m%d");
+        let ofx = format!(
+            "<OFX><CURDEF>EUR<STMTTRN><DTPOSTED>{today}<TRNAMT>-320.50<FITID>1</STMTTRN>\
+             <STMTTRN><DTPOSTED>{today}<TRNAMT>-14.00<FITID>2</STMTTRN></OFX>"
+        );
+
+        let lines = bank_statements::import_statement(&shared_state, DEFAULT_LEDGER_ID, main_account_id, None, &ofx)
+            .unwrap()
+            .transactions;
+        let (rent_line_id, fee_line_id) = (lines[0].external_id, lines[1].external_id);
+
+        let new_match = |external_id: Uuid, transaction_id: Uuid| {
+            let payload = CreateNewMatch {
+                external_ids: vec![external_id],
+                transaction_ids: vec![transaction_id],
+            };
+            let request = Request::builder()
+                .method(Method::POST)
+                .header(header::CONTENT_TYPE, "application/json")
+                .uri(format!("/accounts/{main_account_id}/reconciliation/matches"))
+                .body(Body::from(json!(payload).to_string()))
+                .unwrap();
+            app(shared_state.clone().into()).oneshot(request)
+        };
+
+        // When
+        let response = new_match(fee_line_id, fee_id).await.unwrap();
+
+        // Then the 14.00 line can't settle a 15.00 transaction
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // When
+        let response = new_match(rent_line_id, rent_id).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = new_match(rent_line_id, other_rent_id).await.unwrap();
+
+        // Then the line stays matched to the first rent, even though totals agree
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let report = reconciliation::build_report(&shared_state, DEFAULT_LEDGER_ID, main_account_id, None, None);
+        assert_eq!(report.matched.len(), 1);
+        assert_eq!(report.matched[0].matched.transaction_ids, vec![rent_id]);
+
+        let unmatched: Vec<Uuid> = report
+            .unmatched_transactions
+            .iter()
+            .map(|tx| tx.transaction_id)
+            .collect();
+        assert_eq!(unmatched, vec![other_rent_id, fee_id]);
+    }
+}
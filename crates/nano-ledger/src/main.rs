@@ -1,305 +1,255 @@
 // Copyright 2025 Dotanuki Labs
 // SPDX-License-Identifier: MIT
 
-mod accounts;
-mod journal;
-mod transactions;
-
-use crate::accounts::AccountsRepository;
-use crate::journal::JournalRepository;
-use crate::transactions::TransactionsRepository;
-use axum::Router;
-use axum::routing::{get, post};
-use std::sync::{Arc, RwLock};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use nano_ledger::auth::Authenticator;
+use nano_ledger::bank_statements::BankStatementFormat;
+use nano_ledger::client::{self, DEFAULT_SERVER_URL, ExportOptions, ServerOptions};
+use nano_ledger::exports::ExportFormat;
+use nano_ledger::plaintext::PlainTextFormat;
+use nano_ledger::shutdown::ShutdownOutcome;
+use nano_ledger::statements::StatementSigner;
+use nano_ledger::telemetry::LogFormat;
+use nano_ledger::webhooks::WebhookDispatcher;
+use nano_ledger::{AppState, app, checkpoints, integrity, outbox, shutdown, telemetry};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
 use tokio::net::TcpListener;
-use tracing_subscriber::EnvFilter;
-use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::util::SubscriberInitExt;
+use uuid::Uuid;
 
-type SharedState = Arc<RwLock<Repositories>>;
+#[derive(Parser)]
+#[command(about, long_about = None)]
+struct CliParser {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
 
-#[derive(Default)]
-struct Repositories {
-    pub accounts: AccountsRepository,
-    pub transactions: TransactionsRepository,
-    pub journal: JournalRepository,
+#[derive(Args)]
+struct ServerArgs {
+    /// Base URL of a running nano-ledger server
+    #[arg(long, env = "NANO_LEDGER_URL", default_value = DEFAULT_SERVER_URL)]
+    pub server: String,
+    /// API key sent to the server
+    #[arg(long, env = "NANO_LEDGER_API_KEY")]
+    pub api_key: Option<String>,
+    /// Ledger to work with, the default ledger when omitted
+    #[arg(long)]
+    pub ledger: Option<Uuid>,
+}
+
+impl From<ServerArgs> for ServerOptions {
+    fn from(args: ServerArgs) -> Self {
+        ServerOptions {
+            url: args.server,
+            api_key: args.api_key,
+            ledger_id: args.ledger,
+        }
+    }
 }
 
-fn app(state: SharedState) -> Router {
-    Router::new()
-        .route("/accounts/new", post(accounts::new_account))
-        .route("/accounts/{account_id}", get(accounts::account_details))
-        .route("/transactions/new", post(transactions::new_transaction))
-        .route("/transactions/{transaction_id}", get(transactions::transaction_details))
-        .route("/journal/{transaction_id}", get(journal::entries_for_transaction))
-        .with_state(state)
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ExportedRecords {
+    Journal,
+    Transactions,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Serves the ledger over HTTP, also the default command
+    Serve,
+    /// Imports accounts and opening balances from a CSV file into a running server
+    ImportAccounts {
+        /// CSV file with alias, type, currency, parent and opening_balance_in_cents columns
+        file: PathBuf,
+        /// Only validates rows, without importing anything
+        #[arg(long)]
+        dry_run: bool,
+        #[command(flatten)]
+        server: ServerArgs,
+    },
+    /// Exports journal entries or transactions from a running server
+    Export {
+        #[arg(value_enum)]
+        records: ExportedRecords,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Only records involving this account
+        #[arg(long)]
+        account: Option<Uuid>,
+        /// Only records created at or after this RFC 3339 instant
+        #[arg(long)]
+        from: Option<DateTime<Utc>>,
+        /// Only records created before this RFC 3339 instant
+        #[arg(long)]
+        until: Option<DateTime<Utc>>,
+        /// File to write to, the standard output when omitted
+        #[arg(long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        server: ServerArgs,
+    },
+    /// Exports the ledger as a ledger-cli, hledger or beancount journal
+    ExportPlaintext {
+        #[arg(long, value_enum, default_value_t = PlainTextFormat::Ledger)]
+        format: PlainTextFormat,
+        /// File to write to, the standard output when omitted
+        #[arg(long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        server: ServerArgs,
+    },
+    /// Seeds a ledger with accounts and transactions from a ledger-cli, hledger or beancount journal
+    ImportPlaintext {
+        file: PathBuf,
+        #[command(flatten)]
+        server: ServerArgs,
+    },
+    /// Imports a CAMT.053, MT940 or OFX bank statement for reconciling an account
+    ImportBankStatement {
+        file: PathBuf,
+        /// Account the statement belongs to
+        #[arg(long)]
+        account: Uuid,
+        /// Statement format, detected from the file when omitted
+        #[arg(long, value_enum)]
+        format: Option<BankStatementFormat>,
+        #[command(flatten)]
+        server: ServerArgs,
+    },
 }
 
 #[tokio::main]
-async fn main() {
-    let filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| format!("{}=debug", env!("CARGO_CRATE_NAME")).into());
+async fn main() -> anyhow::Result<ExitCode> {
+    let cli = CliParser::parse();
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => {
+            telemetry::init_logging(LogFormat::from_env());
+            serve().await;
+            Ok(ExitCode::SUCCESS)
+        },
+        Command::ImportAccounts { file, dry_run, server } => {
+            let csv = std::fs::read_to_string(&file)?;
+            let report = client::import_accounts(&server.into(), csv, dry_run).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+
+            match report.rejected {
+                0 => Ok(ExitCode::SUCCESS),
+                _ => Ok(ExitCode::FAILURE),
+            }
+        },
+        Command::Export {
+            records,
+            format,
+            account,
+            from,
+            until,
+            output,
+            server,
+        } => {
+            let records = match records {
+                ExportedRecords::Journal => "journal",
+                ExportedRecords::Transactions => "transactions",
+            };
+
+            let options = ExportOptions {
+                format,
+                account_id: account,
+                from,
+                until,
+            };
+
+            let mut output: Box<dyn Write> = match output {
+                Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                None => Box::new(std::io::stdout().lock()),
+            };
+
+            client::export(&server.into(), records, &options, &mut output).await?;
+            Ok(ExitCode::SUCCESS)
+        },
+        Command::ExportPlaintext { format, output, server } => {
+            let journal = client::export_plaintext(&server.into(), format).await?;
+
+            match output {
+                Some(path) => std::fs::write(path, journal)?,
+                None => print!("{journal}"),
+            }
+
+            Ok(ExitCode::SUCCESS)
+        },
+        Command::ImportPlaintext { file, server } => {
+            let journal = std::fs::read_to_string(&file)?;
+            let report = client::import_plaintext(&server.into(), journal).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+
+            match report.errors.len() {
+                0 => Ok(ExitCode::SUCCESS),
+                _ => Ok(ExitCode::FAILURE),
+            }
+        },
+        Command::ImportBankStatement {
+            file,
+            account,
+            format,
+            server,
+        } => {
+            let statement = std::fs::read_to_string(&file)?;
+            let report = client::import_bank_statement(&server.into(), account, format, statement).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(ExitCode::SUCCESS)
+        },
+    }
+}
 
+async fn serve() {
     let binding_address = match std::env::var("DOCKER_CONTAINER_HOST") {
         Ok(_) => "0.0.0.0:3000",
         Err(_) => "127.0.0.1:3000",
     };
 
-    tracing_subscriber::registry()
-        .with(filter)
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
     let listener = TcpListener::bind(binding_address)
         .await
         .expect("cannot bind to local port");
 
-    tracing::debug!("Listening on {}", binding_address);
-
-    let shared_state = SharedState::default();
-
-    axum::serve(listener, app(shared_state))
-        .await
-        .expect("failed to run server");
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::accounts::{Account, AccountsRepository, CreateNewAccount};
-    use crate::journal::JournalEntry;
-    use crate::transactions::{CreateNewTransaction, CreatedTransaction};
-    use crate::{Repositories, SharedState, app};
-    use axum::body::{Body, to_bytes};
-    use http::{Method, Request, StatusCode, header};
-    use serde::Serialize;
-    use serde_json::json;
-    use std::sync::{Arc, RwLock};
-    use tower::ServiceExt;
-    use uuid::Uuid;
-
-    fn post_request(endpoint: &str, payload: impl Serialize) -> Request<Body> {
-        Request::builder()
-            .method(Method::POST)
-            .header(header::CONTENT_TYPE, "application/json")
-            .uri(endpoint)
-            .body(Body::from(json!(payload).to_string()))
-            .unwrap()
-    }
-
-    fn get_request(endpoint: &str) -> Request<Body> {
-        Request::builder()
-            .method(Method::GET)
-            .header(header::CONTENT_TYPE, "application/json")
-            .uri(endpoint)
-            .body(Body::empty())
-            .unwrap()
-    }
-
-    #[tokio::test]
-    async fn should_report_account_not_found() {
-        // Given
-        let shared_state = SharedState::default();
-        let app = app(shared_state);
-
-        // When
-        let get_account = format!("/accounts/{}", Uuid::new_v4());
-        let request = get_request(&get_account);
-        let response = app.oneshot(request).await.unwrap();
-
-        // Then
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
-    }
-
-    #[tokio::test]
-    async fn should_create_new_account_with_success() {
-        // Given
-        let shared_state = SharedState::default();
-        let app = app(shared_state);
-
-        // When
-        let new_account = json!(CreateNewAccount {
-            alias: "ufs.main".to_string(),
-            balance: Some(100000)
-        });
-
-        let request = post_request("/accounts/new", new_account);
-        let response = app.oneshot(request).await.unwrap();
-
-        // Then
-        assert_eq!(response.status(), StatusCode::OK);
-    }
-
-    #[tokio::test]
-    async fn should_not_create_account_with_existing_alias() {
-        // Given
-        let existing_account = Account::new("ufs.savings", 10000);
-
-        let accounts_repository = AccountsRepository {
-            accounts: vec![existing_account.clone()],
-        };
-
-        let repos = Repositories {
-            accounts: accounts_repository,
-            ..Repositories::default()
-        };
-
-        let shared_state = Arc::new(RwLock::new(repos));
-        let app = app(shared_state);
-
-        // When
-        let new_account = json!(CreateNewAccount {
-            alias: "ufs.savings".to_string(),
-            balance: None
-        });
+    tracing::debug!(binding_address, "Listening");
 
-        let request = post_request("/accounts/new", new_account);
-        let response = app.oneshot(request).await.unwrap();
-
-        // Then
-        assert_eq!(response.status(), StatusCode::CONFLICT);
-    }
-
-    #[tokio::test]
-    async fn should_report_transaction_not_found() {
-        // Given
-        let shared_state = SharedState::default();
-        let app = app(shared_state);
-
-        // When
-        let transaction_by_id = format!("/transactions/{}", Uuid::new_v4());
-        let request = get_request(&transaction_by_id);
-        let response = app.oneshot(request).await.unwrap();
-
-        // Then
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
-    }
-
-    #[tokio::test]
-    async fn should_create_new_transaction_with_success() {
-        // Given
-        let savings_account = Account::new("ufs.savings", 100000);
-        let main_account = Account::new("ufs.main", 50000);
-
-        let savings_account_id = savings_account.account_id;
-        let main_account_id = main_account.account_id;
-
-        let accounts_repository = AccountsRepository {
-            accounts: vec![savings_account, main_account],
-        };
-
-        let repos = Repositories {
-            accounts: accounts_repository,
-            ..Repositories::default()
-        };
-
-        let shared_state = Arc::new(RwLock::new(repos));
-        let app = app(shared_state.clone());
-
-        // When
-        let new_transaction = json!(CreateNewTransaction::new_debit(
-            savings_account_id,
-            main_account_id,
-            "emergency",
-            10000
-        ));
-
-        let request = post_request("/transactions/new", new_transaction);
-        let response = app.oneshot(request).await.unwrap();
-
-        // Then
-        assert_eq!(response.status(), StatusCode::OK);
-
-        // Given
-        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-        let tx: CreatedTransaction = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
-
-        // When
-        let entries_by_transaction = format!("/journal/{}", tx.transaction_id);
-        let request = get_request(&entries_by_transaction);
-
-        let app = crate::app(shared_state);
-        let response = app.oneshot(request).await.unwrap();
+    let app_state = AppState {
+        auth: Arc::new(Authenticator::from_env()),
+        signer: StatementSigner::from_env().map(Arc::new),
+        webhooks: Arc::new(WebhookDispatcher::from_env()),
+        ..AppState::default()
+    };
 
-        // Then
-        assert_eq!(response.status(), StatusCode::OK);
+    tokio::spawn(outbox::run_relay(app_state.clone(), outbox::relay_interval()));
 
-        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-        let entries: Vec<JournalEntry> = serde_json::from_slice(bytes.iter().as_slice()).unwrap();
-        assert_eq!(entries.len(), 2);
+    if let Some(every) = integrity::check_interval() {
+        tracing::debug!(interval = ?every, "Scheduling integrity checks");
+        tokio::spawn(integrity::run_periodically(app_state.clone(), every));
     }
 
-    #[tokio::test]
-    async fn should_reject_transaction_over_non_existing_account() {
-        // Given
-        let savings_account = Account::new("ufs.savings", 100000);
-
-        let savings_account_id = savings_account.account_id;
-        let non_existing_account_id = Uuid::new_v4();
-
-        let accounts_repository = AccountsRepository {
-            accounts: vec![savings_account],
-        };
-
-        let repos = Repositories {
-            accounts: accounts_repository,
-            ..Repositories::default()
-        };
-
-        let shared_state = Arc::new(RwLock::new(repos));
-        let app = app(shared_state);
-
-        // When
-        let new_transaction = json!(CreateNewTransaction::new_credit(
-            savings_account_id,
-            non_existing_account_id,
-            "deposit",
-            10000
-        ));
-
-        let request = post_request("/transactions/new", new_transaction);
-        let response = app.oneshot(request).await.unwrap();
-
-        // Then
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    if let Some(every) = checkpoints::checkpoint_interval() {
+        tracing::debug!(interval = ?every, "Scheduling checkpoints");
+        tokio::spawn(checkpoints::run_periodically(app_state.repos.clone(), every));
     }
 
-    #[tokio::test]
-    async fn should_reject_transaction_with_insufficient_balance() {
-        // Given
-        let amount_to_transfer = 100000;
-        let amount_available = 100;
+    app_state.lifecycle.mark_ready();
 
-        let savings_account = Account::new("ufs.savings", amount_available);
-        let main_account = Account::new("ufs.main", 0);
-
-        // Save these IDs for later
-        let savings_account_id = savings_account.account_id;
-        let main_account_id = main_account.account_id;
-
-        let accounts_repository = AccountsRepository {
-            accounts: vec![savings_account, main_account],
-        };
-
-        let repos = Repositories {
-            accounts: accounts_repository,
-            ..Repositories::default()
-        };
-
-        let shared_state = Arc::new(RwLock::new(repos));
-        let app = app(shared_state);
-
-        // When
-        let new_transaction = json!(CreateNewTransaction::new_debit(
-            savings_account_id,
-            main_account_id,
-            "emergency",
-            amount_to_transfer
-        ));
+    let lifecycle = app_state.lifecycle.clone();
+    let signal = async move {
+        shutdown::termination_signal().await;
+        lifecycle.mark_shutting_down();
+    };
 
-        let request = post_request("/transactions/new", new_transaction);
-        let response = app.oneshot(request).await.unwrap();
+    let outcome = shutdown::serve(listener, app(app_state), signal, shutdown::drain_timeout())
+        .await
+        .expect("failed to run server");
 
-        // Then
-        assert_eq!(response.status(), StatusCode::CONFLICT);
+    match outcome {
+        ShutdownOutcome::Drained => tracing::info!("Server stopped after draining in-flight requests"),
+        ShutdownOutcome::TimedOut => tracing::warn!("Server stopped with in-flight requests aborted"),
     }
 }
@@ -0,0 +1,132 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+// Distinct prefixes keep a leaf from being passed off as an inner node
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub type Hash = [u8; 32];
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BalanceLeaf {
+    pub account_id: Uuid,
+    pub balance: i64,
+    // Random per checkpoint, so siblings in a proof don't disclose other balances
+    pub salt: String,
+}
+
+impl BalanceLeaf {
+    pub fn hash(&self) -> Hash {
+        let serialized = serde_json::to_vec(self).expect("Cannot serialize balance leaf");
+        Sha256::new()
+            .chain_update([LEAF_PREFIX])
+            .chain_update(serialized)
+            .finalize()
+            .into()
+    }
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    Sha256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+// A sibling hash, and on which side of the running hash it goes
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ProofStep {
+    pub side: Side,
+    pub hash: String,
+}
+
+pub struct MerkleTree {
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    // An odd node at the end of a level is promoted to the next level as-is
+    pub fn build(leaves: Vec<Hash>) -> Self {
+        let mut levels = vec![leaves];
+
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+
+            levels.push(next);
+        }
+
+        MerkleTree { levels }
+    }
+
+    pub fn root(&self) -> Hash {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn path(&self, leaf_index: usize) -> Vec<ProofStep> {
+        let mut path = Vec::new();
+        let mut index = leaf_index;
+
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = index ^ 1;
+
+            if let Some(hash) = level.get(sibling) {
+                let side = if sibling < index { Side::Left } else { Side::Right };
+                path.push(ProofStep {
+                    side,
+                    hash: hex::encode(hash),
+                });
+            }
+
+            index /= 2;
+        }
+
+        path
+    }
+}
+
+pub fn verify_inclusion(leaf: &BalanceLeaf, path: &[ProofStep], root: &str) -> bool {
+    let mut running = leaf.hash();
+
+    for step in path {
+        let decoded = hex::decode(&step.hash)
+            .ok()
+            .and_then(|bytes| Hash::try_from(bytes).ok());
+
+        let Some(sibling) = decoded else {
+            return false;
+        };
+
+        running = match step.side {
+            Side::Left => node_hash(&sibling, &running),
+            Side::Right => node_hash(&running, &sibling),
+        };
+    }
+
+    hex::encode(running) == root
+}
@@ -0,0 +1,212 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::AppState;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder, exponential_buckets,
+};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+#[derive(Clone, Copy, Debug)]
+pub enum PostingOutcome {
+    Created,
+    InsufficientBalance,
+    AccountNotFound,
+    CrossLedger,
+    CurrencyMismatch,
+    VersionMismatch,
+    BalanceOverflow,
+    AlreadyReversed,
+    RolledBack,
+}
+
+impl PostingOutcome {
+    fn label(&self) -> &'static str {
+        match self {
+            PostingOutcome::Created => "created",
+            PostingOutcome::InsufficientBalance => "insufficient_balance",
+            PostingOutcome::AccountNotFound => "account_not_found",
+            PostingOutcome::CrossLedger => "cross_ledger",
+            PostingOutcome::CurrencyMismatch => "currency_mismatch",
+            PostingOutcome::VersionMismatch => "version_mismatch",
+            PostingOutcome::BalanceOverflow => "balance_overflow",
+            PostingOutcome::AlreadyReversed => "already_reversed",
+            PostingOutcome::RolledBack => "rolled_back",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LockMode {
+    Read,
+    Write,
+}
+
+impl LockMode {
+    fn label(&self) -> &'static str {
+        match self {
+            LockMode::Read => "read",
+            LockMode::Write => "write",
+        }
+    }
+}
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    transaction_postings: IntCounterVec,
+    accounts: IntGauge,
+    journal_entries: IntGauge,
+    lock_wait: HistogramVec,
+    integrity_checks: IntCounterVec,
+    integrity_violations: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("nano_ledger".to_string()), None).expect("invalid metrics registry");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled, per route and status"),
+            &["method", "route", "status"],
+        )
+        .expect("invalid metric");
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency, per route"),
+            &["method", "route"],
+        )
+        .expect("invalid metric");
+
+        let transaction_postings = IntCounterVec::new(
+            Opts::new("transaction_postings_total", "Transaction postings, per outcome"),
+            &["outcome"],
+        )
+        .expect("invalid metric");
+
+        let accounts = IntGauge::new("accounts", "Accounts currently in the ledger").expect("invalid metric");
+
+        let journal_entries =
+            IntGauge::new("journal_entries", "Journal entries currently in the ledger").expect("invalid metric");
+
+        let lock_wait = HistogramVec::new(
+            HistogramOpts::new(
+                "state_lock_wait_seconds",
+                "Time spent waiting for the shared state lock",
+            )
+            .buckets(exponential_buckets(0.000_001, 4.0, 12).expect("invalid buckets")),
+            &["mode"],
+        )
+        .expect("invalid metric");
+
+        let integrity_checks = IntCounterVec::new(
+            Opts::new("integrity_checks_total", "Ledger integrity checks, per outcome"),
+            &["outcome"],
+        )
+        .expect("invalid metric");
+
+        let integrity_violations = IntGauge::new(
+            "integrity_violations",
+            "Violations found by the most recent integrity check",
+        )
+        .expect("invalid metric");
+
+        let collectors: Vec<Box<dyn Collector>> = vec![
+            Box::new(http_requests.clone()),
+            Box::new(http_request_duration.clone()),
+            Box::new(transaction_postings.clone()),
+            Box::new(accounts.clone()),
+            Box::new(journal_entries.clone()),
+            Box::new(lock_wait.clone()),
+            Box::new(integrity_checks.clone()),
+            Box::new(integrity_violations.clone()),
+        ];
+
+        for collector in collectors {
+            registry.register(collector).expect("cannot register metric");
+        }
+
+        Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            transaction_postings,
+            accounts,
+            journal_entries,
+            lock_wait,
+            integrity_checks,
+            integrity_violations,
+        }
+    }
+
+    pub fn record_posting(&self, outcome: PostingOutcome) {
+        self.transaction_postings.with_label_values(&[outcome.label()]).inc();
+    }
+
+    pub fn record_lock_wait(&self, mode: LockMode, waited: Duration) {
+        self.lock_wait
+            .with_label_values(&[mode.label()])
+            .observe(waited.as_secs_f64());
+    }
+
+    pub fn record_integrity_check(&self, violations: usize) {
+        let outcome = if violations == 0 { "healthy" } else { "violated" };
+        self.integrity_checks.with_label_values(&[outcome]).inc();
+        self.integrity_violations.set(violations as i64);
+    }
+
+    fn encode(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        String::from_utf8(buffer).map_err(|error| prometheus::Error::Msg(error.to_string()))
+    }
+}
+
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = started.elapsed();
+
+    let status = response.status().as_u16().to_string();
+    METRICS
+        .http_requests
+        .with_label_values(&[&method, &route, &status])
+        .inc();
+    METRICS
+        .http_request_duration
+        .with_label_values(&[&method, &route])
+        .observe(elapsed.as_secs_f64());
+
+    response
+}
+
+pub async fn export_metrics(State(state): State<AppState>) -> Response {
+    METRICS.accounts.set(state.repos.accounts.count() as i64);
+    METRICS
+        .journal_entries
+        .set(crate::read_lock(&state.repos.journal).count() as i64);
+
+    match METRICS.encode() {
+        Ok(body) => ([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body).into_response(),
+        Err(error) => {
+            tracing::error!(%error, "Cannot encode metrics");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        },
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::auth::Principal;
+use crate::errors::ApiError;
+use crate::events::LedgerEvent;
+use crate::ledgers::LedgerScope;
+use crate::webhooks::PendingDelivery;
+use crate::{AppState, SharedState, pagination};
+use axum::extract::{Query, State};
+use axum::{Extension, Json};
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+const DEFAULT_RELAY_INTERVAL: Duration = Duration::from_millis(100);
+
+// Items a sink picks up at once, the next ones wait for the following pass
+const RELAY_BATCH_SIZE: usize = 500;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OutboxSink {
+    Webhooks,
+    Stdout,
+    File(PathBuf),
+}
+
+impl fmt::Display for OutboxSink {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutboxSink::Webhooks => write!(formatter, "webhooks"),
+            OutboxSink::Stdout => write!(formatter, "stdout"),
+            OutboxSink::File(path) => write!(formatter, "file:{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for OutboxSink {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.trim() {
+            "webhooks" => Ok(OutboxSink::Webhooks),
+            "stdout" => Ok(OutboxSink::Stdout),
+            other => match other.strip_prefix("file:") {
+                Some(path) if !path.is_empty() => Ok(OutboxSink::File(PathBuf::from(path))),
+                _ => Err(format!("Unknown outbox sink {other}")),
+            },
+        }
+    }
+}
+
+impl OutboxSink {
+    async fn publish(&self, state: &AppState, item: &OutboxItem) -> Result<(), String> {
+        let event = &item.event;
+
+        match self {
+            // Subscribers are resolved once, then each delivery stays on the item until it
+            // succeeds or gets dead-lettered. Webhooks are delivered one after the other.
+            OutboxSink::Webhooks => {
+                let deliveries = item.webhook_deliveries.clone().unwrap_or_else(|| {
+                    crate::read_lock(&state.repos.webhooks)
+                        .subscribed_to(event)
+                        .into_iter()
+                        .map(PendingDelivery::new)
+                        .collect()
+                });
+
+                let mut pending = Vec::with_capacity(deliveries.len());
+
+                for delivery in deliveries {
+                    pending.extend(state.webhooks.deliver_due(&state.repos, delivery, event).await);
+                }
+
+                let delivered = pending.is_empty();
+                let last_error = pending.iter().find_map(|delivery| delivery.last_error.clone());
+                crate::write_lock(&state.repos.outbox).track_deliveries(event.sequence, pending);
+
+                match delivered {
+                    true => Ok(()),
+                    false => Err(last_error.unwrap_or_else(|| "Webhook deliveries pending".to_string())),
+                }
+            },
+            OutboxSink::Stdout => {
+                let line = serde_json::to_string(event).expect("Cannot serialize ledger event");
+                println!("{line}");
+                Ok(())
+            },
+            OutboxSink::File(path) => {
+                let mut line = serde_json::to_vec(event).expect("Cannot serialize ledger event");
+                line.push(b'\n');
+
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await
+                    .map_err(|error| format!("Cannot open {} : {error}", path.display()))?;
+
+                file.write_all(&line)
+                    .await
+                    .map_err(|error| format!("Cannot write to {} : {error}", path.display()))
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OutboxItem {
+    pub event: LedgerEvent,
+    pub recorded_at: DateTime<Utc>,
+    pub pending_sinks: BTreeSet<String>,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_deliveries: Option<Vec<PendingDelivery>>,
+}
+
+impl OutboxItem {
+    // Webhook deliveries wait for their backoff before the next attempt
+    fn awaits_retry(&self, sink: &OutboxSink) -> bool {
+        let now = Utc::now();
+
+        *sink == OutboxSink::Webhooks
+            && self.webhook_deliveries.as_ref().is_some_and(|deliveries| {
+                !deliveries.is_empty() && deliveries.iter().all(|delivery| delivery.retry_at > now)
+            })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PendingOutbox {
+    pub sinks: Vec<String>,
+    pub pending: usize,
+    pub items: Vec<OutboxItem>,
+}
+
+// Items stay until every sink has published them, and are keyed by event sequence. Each sink
+// keeps the sequences it still has to publish, so it never walks items it's done with
+pub struct OutboxRepository {
+    sinks: Vec<OutboxSink>,
+    items: BTreeMap<u64, OutboxItem>,
+    queues: BTreeMap<OutboxSink, BTreeSet<u64>>,
+}
+
+impl Default for OutboxRepository {
+    fn default() -> Self {
+        let sinks = std::env::var("OUTBOX_SINKS").unwrap_or_else(|_| "webhooks".to_string());
+
+        let sinks = sinks
+            .split(',')
+            .filter(|sink| !sink.trim().is_empty())
+            .map(|sink| sink.parse().expect("invalid OUTBOX_SINKS"))
+            .collect();
+
+        OutboxRepository::with_sinks(sinks)
+    }
+}
+
+impl OutboxRepository {
+    pub fn with_sinks(sinks: Vec<OutboxSink>) -> Self {
+        OutboxRepository {
+            queues: sinks.iter().map(|sink| (sink.clone(), BTreeSet::new())).collect(),
+            sinks,
+            items: BTreeMap::new(),
+        }
+    }
+
+    pub fn sinks(&self) -> &[OutboxSink] {
+        &self.sinks
+    }
+
+    pub fn append(&mut self, events: Vec<LedgerEvent>) {
+        if self.sinks.is_empty() {
+            return;
+        }
+
+        let pending_sinks: BTreeSet<String> = self.sinks.iter().map(ToString::to_string).collect();
+
+        for event in events {
+            let item = OutboxItem {
+                event,
+                recorded_at: Utc::now(),
+                pending_sinks: pending_sinks.clone(),
+                attempts: 0,
+                last_error: None,
+                webhook_deliveries: None,
+            };
+
+            for queue in self.queues.values_mut() {
+                queue.insert(item.event.sequence);
+            }
+
+            self.items.insert(item.event.sequence, item);
+        }
+    }
+
+    // Up to `limit` items the sink has yet to publish, oldest first
+    pub fn pending_for(&self, sink: &OutboxSink, limit: usize) -> Vec<OutboxItem> {
+        self.queues
+            .get(sink)
+            .into_iter()
+            .flatten()
+            .take(limit)
+            .filter_map(|sequence| self.items.get(sequence))
+            .cloned()
+            .collect()
+    }
+
+    // Webhook deliveries left to retry, the item is published once there are none
+    pub fn track_deliveries(&mut self, sequence: u64, deliveries: Vec<PendingDelivery>) {
+        if let Some(item) = self.items.get_mut(&sequence) {
+            item.webhook_deliveries = Some(deliveries);
+        }
+    }
+
+    pub fn mark_published(&mut self, sequence: u64, sink: &OutboxSink) {
+        let Some(item) = self.items.get_mut(&sequence) else {
+            return;
+        };
+
+        item.pending_sinks.remove(&sink.to_string());
+
+        if let Some(queue) = self.queues.get_mut(sink) {
+            queue.remove(&sequence);
+        }
+
+        // Items every sink has published are dropped
+        if item.pending_sinks.is_empty() {
+            self.items.remove(&sequence);
+        }
+    }
+
+    pub fn mark_failed(&mut self, sequence: u64, sink: &OutboxSink, error: String) {
+        if let Some(item) = self.items.get_mut(&sequence) {
+            item.attempts += 1;
+            item.last_error = Some(format!("{sink} : {error}"));
+        }
+    }
+
+    pub fn pending(&self, ledger_id: &Uuid) -> impl Iterator<Item = &OutboxItem> {
+        self.items
+            .values()
+            .filter(move |item| item.event.ledger_id == *ledger_id)
+    }
+}
+
+// Publishes pending items to every sink at once, so a slow sink doesn't hold the others back
+pub async fn relay_pending(state: &AppState) {
+    let sinks = crate::read_lock(&state.repos.outbox).sinks().to_vec();
+    join_all(sinks.iter().map(|sink| relay_to(state, sink))).await;
+}
+
+// Items sharing an account are chained and published one after the other, oldest first. When an
+// item fails, later items of its accounts wait for the next pass, so each account sees its events
+// in order. Webhook chains are delivered concurrently, so one unreachable endpoint only holds back
+// the accounts it's subscribed to.
+async fn relay_to(state: &AppState, sink: &OutboxSink) {
+    let pending = crate::read_lock(&state.repos.outbox).pending_for(sink, RELAY_BATCH_SIZE);
+    let mut chains: Vec<Vec<OutboxItem>> = Vec::new();
+    let mut chain_of: HashMap<Uuid, usize> = HashMap::new();
+    let mut held_accounts: HashSet<Uuid> = HashSet::new();
+
+    for item in pending {
+        let account_ids = item.event.payload.account_ids();
+
+        if item.awaits_retry(sink) || account_ids.iter().any(|account_id| held_accounts.contains(account_id)) {
+            held_accounts.extend(account_ids);
+            continue;
+        }
+
+        let joined: BTreeSet<usize> = account_ids
+            .iter()
+            .filter_map(|account_id| chain_of.get(account_id).copied())
+            .collect();
+
+        // Items linking two chains wait until both are published
+        let chain = match joined.first() {
+            None => {
+                chains.push(Vec::new());
+                chains.len() - 1
+            },
+            Some(chain) if joined.len() == 1 => *chain,
+            Some(_) => {
+                held_accounts.extend(account_ids);
+                continue;
+            },
+        };
+
+        for account_id in account_ids {
+            chain_of.insert(account_id, chain);
+        }
+
+        chains[chain].push(item);
+    }
+
+    if *sink == OutboxSink::Webhooks {
+        join_all(chains.into_iter().map(|chain| publish_chain(state, sink, chain))).await;
+    } else {
+        for chain in chains {
+            publish_chain(state, sink, chain).await;
+        }
+    }
+}
+
+async fn publish_chain(state: &AppState, sink: &OutboxSink, chain: Vec<OutboxItem>) {
+    for item in chain {
+        let sequence = item.event.sequence;
+
+        match sink.publish(state, &item).await {
+            Ok(()) => crate::write_lock(&state.repos.outbox).mark_published(sequence, sink),
+            Err(error) => {
+                tracing::warn!(%sink, sequence, %error, "Outbox item not published");
+                crate::write_lock(&state.repos.outbox).mark_failed(sequence, sink, error);
+                return;
+            },
+        }
+    }
+}
+
+pub fn relay_interval() -> Duration {
+    std::env::var("OUTBOX_RELAY_INTERVAL_MS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|millis| *millis > 0)
+        .map_or(DEFAULT_RELAY_INTERVAL, Duration::from_millis)
+}
+
+// Each sink is relayed by its own task
+pub async fn run_relay(state: AppState, every: Duration) {
+    let sinks = crate::read_lock(&state.repos.outbox).sinks().to_vec();
+
+    let relays = sinks.into_iter().map(|sink| {
+        let state = state.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+
+            loop {
+                interval.tick().await;
+                relay_to(&state, &sink).await;
+            }
+        })
+    });
+
+    join_all(relays).await;
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OutboxQuery {
+    limit: Option<usize>,
+}
+
+// End users only see items about their accounts
+pub async fn pending_outbox(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Query(OutboxQuery { limit }): Query<OutboxQuery>,
+) -> Result<Json<PendingOutbox>, ApiError> {
+    let limit = pagination::page_size(limit)?;
+
+    let outbox = crate::read_lock(&state.outbox);
+    let visible = || {
+        outbox.pending(&ledger_id).filter(|item| {
+            let account_ids = item.event.payload.account_ids();
+            account_ids.iter().any(|account_id| principal.owns(account_id))
+        })
+    };
+
+    Ok(Json(PendingOutbox {
+        sinks: outbox.sinks().iter().map(ToString::to_string).collect(),
+        pending: visible().count(),
+        items: visible().take(limit).cloned().collect(),
+    }))
+}
@@ -0,0 +1,77 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::errors::ApiError;
+use axum::http::StatusCode;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+const CURSOR_PREFIX: &str = "position:";
+
+// Opaque to clients, it only tells where the next page starts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cursor(pub usize);
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{CURSOR_PREFIX}{}", self.0))
+    }
+
+    pub fn decode(raw: &str) -> Option<Self> {
+        let decoded = URL_SAFE_NO_PAD.decode(raw).ok()?;
+        let position = String::from_utf8(decoded).ok()?;
+        let position = position.strip_prefix(CURSOR_PREFIX)?.parse().ok()?;
+        Some(Cursor(position))
+    }
+}
+
+const KEYSET_PREFIX: &str = "after:";
+
+// Sort key and position of the last item of a page, so items added in between can't shift
+// the next pages. The key is formatted by whoever sorts the items.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeysetCursor {
+    pub key: String,
+    pub position: usize,
+}
+
+impl KeysetCursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{KEYSET_PREFIX}{}/{}", self.key, self.position))
+    }
+
+    pub fn decode(raw: &str) -> Option<Self> {
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(raw).ok()?).ok()?;
+        let (key, position) = decoded.strip_prefix(KEYSET_PREFIX)?.rsplit_once('/')?;
+
+        Some(KeysetCursor {
+            key: key.to_string(),
+            position: position.parse().ok()?,
+        })
+    }
+}
+
+pub fn parse_keyset_cursor(raw: Option<&str>) -> Result<Option<KeysetCursor>, ApiError> {
+    raw.map(|raw| KeysetCursor::decode(raw).ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "Invalid cursor")))
+        .transpose()
+}
+
+pub fn parse_cursor(raw: Option<&str>) -> Result<Cursor, ApiError> {
+    match raw {
+        None => Ok(Cursor::default()),
+        Some(raw) => Cursor::decode(raw).ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "Invalid cursor")),
+    }
+}
+
+pub fn page_size(limit: Option<usize>) -> Result<usize, ApiError> {
+    match limit.unwrap_or(DEFAULT_PAGE_SIZE) {
+        limit @ 1..=MAX_PAGE_SIZE => Ok(limit),
+        _ => Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Limit must be between 1 and {MAX_PAGE_SIZE}"),
+        )),
+    }
+}
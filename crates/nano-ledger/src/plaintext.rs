@@ -0,0 +1,948 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::accounts::{self, Account, AccountType};
+use crate::auth::Principal;
+use crate::errors::ApiError;
+use crate::imports;
+use crate::ledgers::LedgerScope;
+use crate::transactions::{self, CreateNewTransaction, MovementType, Transaction};
+use crate::{Repositories, SharedState};
+use axum::extract::{Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::{NaiveDate, NaiveTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use uuid::Uuid;
+
+// Balances accounts were created with, which have no journal entries of their own, are booked
+// against one such account per commodity
+pub const INITIAL_BALANCES_ACCOUNT: &str = "Equity:Initial-Balances";
+
+fn initial_balances_account(currency: &str) -> String {
+    format!("{INITIAL_BALANCES_ACCOUNT}:{currency}")
+}
+
+pub const INITIAL_BALANCES_DESCRIPTION: &str = "Initial balances";
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum PlainTextFormat {
+    // Understood by both ledger-cli and hledger
+    #[default]
+    Ledger,
+    Beancount,
+}
+
+impl PlainTextFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            PlainTextFormat::Ledger => "journal",
+            PlainTextFormat::Beancount => "beancount",
+        }
+    }
+}
+
+fn root_name(account_type: AccountType) -> &'static str {
+    match account_type {
+        AccountType::Asset => "Assets",
+        AccountType::Liability => "Liabilities",
+        AccountType::Equity => "Equity",
+        AccountType::Revenue => "Income",
+        AccountType::Expense => "Expenses",
+    }
+}
+
+fn root_type(name: &str) -> Option<AccountType> {
+    match name.to_lowercase().as_str() {
+        "assets" | "asset" => Some(AccountType::Asset),
+        "liabilities" | "liability" => Some(AccountType::Liability),
+        "equity" => Some(AccountType::Equity),
+        "income" | "revenue" | "revenues" => Some(AccountType::Revenue),
+        "expenses" | "expense" => Some(AccountType::Expense),
+        _ => None,
+    }
+}
+
+// Components start with an uppercase letter or digit and only hold letters, digits and dashes,
+// as beancount requires, so the same names work for every format
+fn component(segment: &str) -> String {
+    let sanitized: String = segment
+        .chars()
+        .map(|character| match character {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' => character,
+            _ => '-',
+        })
+        .collect();
+
+    let mut characters = sanitized.chars();
+    match characters.next() {
+        Some(first) if first.is_ascii_alphanumeric() => first.to_ascii_uppercase().to_string() + characters.as_str(),
+        _ => format!("X{sanitized}"),
+    }
+}
+
+// `ufs.savings` of an asset account becomes `Assets:Ufs:Savings`. A leading segment naming
+// the account type, as in `equity.opening-balances.eur`, is not repeated.
+pub fn account_name(account: &Account) -> String {
+    let root = root_name(account.account_type);
+    let mut segments: Vec<&str> = account.alias.split('.').filter(|segment| !segment.is_empty()).collect();
+
+    if segments.len() > 1 && root_type(segments[0]) == Some(account.account_type) {
+        segments.remove(0);
+    }
+
+    let components: Vec<String> = segments.into_iter().map(component).collect();
+    format!("{root}:{}", components.join(":"))
+}
+
+// Names for every account, disambiguated with the account id when aliases sanitize alike
+fn account_names(accounts: &[Account]) -> HashMap<Uuid, String> {
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+
+    for account in accounts {
+        *occurrences.entry(account_name(account).to_lowercase()).or_default() += 1;
+    }
+
+    accounts
+        .iter()
+        .map(|account| {
+            let name = account_name(account);
+            let name = match occurrences[&name.to_lowercase()] {
+                1 if !name
+                    .to_lowercase()
+                    .starts_with(&INITIAL_BALANCES_ACCOUNT.to_lowercase()) =>
+                {
+                    name
+                },
+                _ => format!("{name}-{}", &account.account_id.simple().to_string()[..8]),
+            };
+            (account.account_id, name)
+        })
+        .collect()
+}
+
+fn format_amount(amount_in_cents: i128, currency: &str) -> String {
+    let sign = if amount_in_cents < 0 { "-" } else { "" };
+    let cents = amount_in_cents.unsigned_abs();
+    format!("{sign}{}.{:02} {currency}", cents / 100, cents % 100)
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+struct Posting<'a> {
+    account: &'a str,
+    amount_in_cents: i128,
+    currency: &'a str,
+}
+
+fn write_transaction(
+    output: &mut String,
+    format: PlainTextFormat,
+    date: NaiveDate,
+    description: &str,
+    transaction_id: Option<Uuid>,
+    postings: &[Posting<'_>],
+) {
+    let description = single_line(description);
+
+    match format {
+        PlainTextFormat::Ledger => {
+            let _ = writeln!(output, "{date} * {description}");
+            if let Some(transaction_id) = transaction_id {
+                let _ = writeln!(output, "    ; transaction_id: {transaction_id}");
+            }
+        },
+        PlainTextFormat::Beancount => {
+            let escaped = description.replace('\\', "\\\\").replace('"', "\\\"");
+            let _ = writeln!(output, "{date} * \"{escaped}\"");
+            if let Some(transaction_id) = transaction_id {
+                let _ = writeln!(output, "  transaction_id: \"{transaction_id}\"");
+            }
+        },
+    }
+
+    for posting in postings {
+        let amount = format_amount(posting.amount_in_cents, posting.currency);
+        let _ = writeln!(output, "    {:<50}  {amount:>20}", posting.account);
+    }
+
+    output.push('\n');
+}
+
+// Credited accounts receive the amount and debited ones give it away, so running `hledger balance`
+// on the output lists the balance of every account, with initial balances booked against equity
+pub fn export(ledger_id: Uuid, accounts: &[Account], transactions: &[Transaction], format: PlainTextFormat) -> String {
+    let names = account_names(accounts);
+    let by_id: HashMap<Uuid, &Account> = accounts.iter().map(|account| (account.account_id, account)).collect();
+
+    // Whatever the journal doesn't explain was there when the account was created
+    let mut initial_balances: HashMap<Uuid, i128> = accounts
+        .iter()
+        .map(|account| (account.account_id, i128::from(account.balance)))
+        .collect();
+
+    for tx in transactions {
+        let (source, target) = match tx.movement_type {
+            MovementType::Debit => (tx.lhs_account_id, tx.rhs_account_id),
+            MovementType::Credit => (tx.rhs_account_id, tx.lhs_account_id),
+        };
+
+        let amount = i128::from(tx.amount_in_cents);
+        *initial_balances.entry(source).or_default() += amount;
+        *initial_balances.entry(target).or_default() -= amount;
+    }
+
+    let opened_at = transactions
+        .iter()
+        .map(|tx| tx.created_at.date_naive())
+        .min()
+        .unwrap_or_else(|| Utc::now().date_naive());
+
+    let mut output = String::new();
+    let comment = match format {
+        PlainTextFormat::Ledger => ";",
+        PlainTextFormat::Beancount => ";;",
+    };
+
+    let _ = writeln!(
+        output,
+        "{comment} Exported from nano-ledger, ledger {ledger_id}, at {}",
+        Utc::now().to_rfc3339()
+    );
+    output.push('\n');
+
+    let mut sorted: Vec<&Account> = accounts.iter().collect();
+    sorted.sort_by(|lhs, rhs| names[&lhs.account_id].cmp(&names[&rhs.account_id]));
+
+    let mut currencies: Vec<&str> = accounts.iter().map(|account| account.currency.as_str()).collect();
+    currencies.sort_unstable();
+    currencies.dedup();
+
+    match format {
+        PlainTextFormat::Ledger => {
+            for currency in &currencies {
+                let _ = writeln!(output, "commodity {currency}");
+            }
+            output.push('\n');
+
+            for currency in &currencies {
+                let _ = writeln!(output, "account {}", initial_balances_account(currency));
+            }
+            for account in &sorted {
+                let _ = writeln!(output, "account {}", names[&account.account_id]);
+            }
+        },
+        PlainTextFormat::Beancount => {
+            for currency in &currencies {
+                let _ = writeln!(output, "{opened_at} commodity {currency}");
+            }
+            output.push('\n');
+
+            for currency in &currencies {
+                let _ = writeln!(
+                    output,
+                    "{opened_at} open {} {currency}",
+                    initial_balances_account(currency)
+                );
+            }
+            for account in &sorted {
+                let _ = writeln!(
+                    output,
+                    "{opened_at} open {} {}",
+                    names[&account.account_id], account.currency
+                );
+            }
+        },
+    }
+
+    output.push('\n');
+
+    // One transfer per account, so every transaction has exactly two postings, as imports expect
+    for account in sorted
+        .iter()
+        .filter(|account| initial_balances[&account.account_id] != 0)
+    {
+        let amount = initial_balances[&account.account_id];
+        let equity = initial_balances_account(&account.currency);
+        let postings = [
+            Posting {
+                account: &names[&account.account_id],
+                amount_in_cents: amount,
+                currency: &account.currency,
+            },
+            Posting {
+                account: &equity,
+                amount_in_cents: -amount,
+                currency: &account.currency,
+            },
+        ];
+
+        write_transaction(
+            &mut output,
+            format,
+            opened_at,
+            INITIAL_BALANCES_DESCRIPTION,
+            None,
+            &postings,
+        );
+    }
+
+    for tx in transactions {
+        let (source, target) = match tx.movement_type {
+            MovementType::Debit => (tx.lhs_account_id, tx.rhs_account_id),
+            MovementType::Credit => (tx.rhs_account_id, tx.lhs_account_id),
+        };
+
+        let (Some(source_account), Some(target_account)) = (by_id.get(&source), by_id.get(&target)) else {
+            continue;
+        };
+
+        let amount = i128::from(tx.amount_in_cents);
+        let postings = [
+            Posting {
+                account: &names[&target_account.account_id],
+                amount_in_cents: amount,
+                currency: &target_account.currency,
+            },
+            Posting {
+                account: &names[&source_account.account_id],
+                amount_in_cents: -amount,
+                currency: &source_account.currency,
+            },
+        ];
+
+        write_transaction(
+            &mut output,
+            format,
+            tx.created_at.date_naive(),
+            &tx.description,
+            Some(tx.transaction_id),
+            &postings,
+        );
+    }
+
+    output
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ParsedPosting {
+    account: String,
+    amount: Option<(i128, String)>,
+}
+
+#[derive(Clone, Debug)]
+struct ParsedTransaction {
+    line: usize,
+    date: NaiveDate,
+    description: String,
+    postings: Vec<ParsedPosting>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LineError {
+    pub line: usize,
+    pub error: String,
+}
+
+#[derive(Default)]
+struct ParsedBook {
+    // Account names, with the currency they were opened for, if any
+    declared: Vec<(usize, String, Option<String>)>,
+    transactions: Vec<ParsedTransaction>,
+}
+
+fn parse_date(token: &str) -> Option<NaiveDate> {
+    let normalized = token.replace(['/', '.'], "-");
+    NaiveDate::parse_from_str(&normalized, "%Y-%m-%d").ok()
+}
+
+// Cents from amounts like `1,234.5`, `-12` or `0.99`
+fn parse_cents(number: &str) -> Option<i128> {
+    let number = number.replace(',', "");
+    let (negative, digits) = match number.strip_prefix('-') {
+        Some(digits) => (true, digits.to_string()),
+        None => (false, number.strip_prefix('+').unwrap_or(&number).to_string()),
+    };
+
+    let (units, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+
+    if units.is_empty() && fraction.is_empty() || fraction.len() > 2 {
+        return None;
+    }
+
+    let all_digits = |text: &str| text.chars().all(|character| character.is_ascii_digit());
+    if !all_digits(units) || !all_digits(fraction) {
+        return None;
+    }
+
+    let units: i128 = if units.is_empty() { 0 } else { units.parse().ok()? };
+    let fraction: i128 = format!("{fraction:0<2}").parse().ok()?;
+    let cents = units.checked_mul(100)?.checked_add(fraction)?;
+    Some(if negative { -cents } else { cents })
+}
+
+// Amounts hold a number and a commodity, in either order
+fn parse_amount(text: &str) -> Result<(i128, String), String> {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+
+    let (number, commodity) = match tokens.as_slice() {
+        [first, second] if parse_cents(first).is_some() => (*first, *second),
+        [first, second] => (*second, *first),
+        _ => return Err(format!("Invalid amount {text}, expected a number and a commodity")),
+    };
+
+    let cents = parse_cents(number).ok_or_else(|| format!("Invalid amount {number}"))?;
+    let currency = accounts::parse_currency(commodity).ok_or_else(|| format!("Invalid commodity {commodity}"))?;
+    Ok((cents, currency))
+}
+
+fn parse_posting(text: &str) -> Result<ParsedPosting, String> {
+    // Comments end postings, costs and prices are not supported
+    let text = text.split(';').next().unwrap_or_default().trim_end();
+
+    if text.contains('@') || text.contains('{') {
+        return Err("Costs and prices are not supported".to_string());
+    }
+
+    // Ledger account names may hold single spaces, and end at two spaces or a tab
+    let separator = text
+        .find("  ")
+        .or_else(|| text.find('\t'))
+        .or_else(|| text.find(' '))
+        .unwrap_or(text.len());
+
+    let (account, amount) = text.split_at(separator);
+    let amount = amount.trim();
+
+    Ok(ParsedPosting {
+        account: account.trim().to_string(),
+        amount: if amount.is_empty() {
+            None
+        } else {
+            Some(parse_amount(amount)?)
+        },
+    })
+}
+
+// Parses the subset of ledger-cli, hledger and beancount journals made of account declarations
+// and transactions, ignoring other directives, comments and metadata
+fn parse(text: &str) -> (ParsedBook, Vec<LineError>) {
+    let mut book = ParsedBook::default();
+    let mut errors = Vec::new();
+    let mut current: Option<ParsedTransaction> = None;
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = index + 1;
+        let indented = raw_line.starts_with([' ', '\t']);
+        let content = raw_line.trim();
+
+        if content.is_empty() {
+            book.transactions.extend(current.take());
+            continue;
+        }
+
+        if content.starts_with([';', '#', '*', '%', '|']) {
+            continue;
+        }
+
+        if indented {
+            let Some(transaction) = current.as_mut() else {
+                continue;
+            };
+
+            // Beancount metadata, like `transaction_id: "..."`
+            let is_metadata = content
+                .split_whitespace()
+                .next()
+                .is_some_and(|key| key.ends_with(':') && key.starts_with(|first: char| first.is_ascii_lowercase()));
+
+            if is_metadata {
+                continue;
+            }
+
+            match parse_posting(content) {
+                Ok(posting) => transaction.postings.push(posting),
+                Err(error) => errors.push(LineError { line, error }),
+            }
+
+            continue;
+        }
+
+        book.transactions.extend(current.take());
+        let mut tokens = content.split_whitespace();
+        let first = tokens.next().unwrap_or_default();
+
+        if first == "account" {
+            let name = content["account".len()..].split(';').next().unwrap_or_default().trim();
+            book.declared.push((line, name.to_string(), None));
+            continue;
+        }
+
+        let Some(date) = parse_date(first) else {
+            continue;
+        };
+
+        let rest = content[first.len()..].trim_start();
+        let keyword = tokens.next().unwrap_or_default();
+
+        match keyword {
+            "open" => {
+                let mut arguments = rest["open".len()..].split_whitespace();
+                let name = arguments.next().unwrap_or_default().to_string();
+                let currency = arguments
+                    .next()
+                    .map(|currency| currency.split(',').next().unwrap_or_default().to_string());
+                book.declared.push((line, name, currency));
+            },
+            "*" | "!" | "txn" => {
+                let description = rest[keyword.len()..].trim();
+                current = Some(ParsedTransaction {
+                    line,
+                    date,
+                    description: parse_description(description),
+                    postings: Vec::new(),
+                });
+            },
+            "close" | "balance" | "pad" | "note" | "document" | "price" | "event" | "query" | "custom"
+            | "commodity" => {},
+            _ => {
+                current = Some(ParsedTransaction {
+                    line,
+                    date,
+                    description: parse_description(rest),
+                    postings: Vec::new(),
+                });
+            },
+        }
+    }
+
+    book.transactions.extend(current.take());
+    (book, errors)
+}
+
+// Beancount quotes payees and narrations, ledger takes the rest of the line, after an optional code
+fn parse_description(text: &str) -> String {
+    let text = text.split(" ;").next().unwrap_or_default().trim();
+
+    if text.starts_with('"') {
+        return quoted_strings(text).join(" ").trim().to_string();
+    }
+
+    match text.strip_prefix('(').and_then(|rest| rest.split_once(')')) {
+        Some((_, description)) => description.trim().to_string(),
+        None => text.to_string(),
+    }
+}
+
+// Contents of every double-quoted string, unescaping backslashes
+fn quoted_strings(text: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut current: Option<String> = None;
+    let mut characters = text.chars();
+
+    while let Some(character) = characters.next() {
+        match (&mut current, character) {
+            (None, '"') => current = Some(String::new()),
+            (None, _) => {},
+            (Some(_), '"') => strings.extend(current.take()),
+            (Some(string), '\\') => string.extend(characters.next()),
+            (Some(string), _) => string.push(character),
+        }
+    }
+
+    strings
+}
+
+// Reverses `account_name`, keeping every component but the root
+fn alias_of(name: &str) -> Option<(String, AccountType)> {
+    let mut components = name.split(':');
+    let account_type = root_type(components.next()?)?;
+    let alias: Vec<String> = components.map(str::to_lowercase).collect();
+
+    if alias.is_empty() || alias.iter().any(String::is_empty) {
+        return None;
+    }
+
+    Some((alias.join("."), account_type))
+}
+
+// A transfer between two accounts, as plain-text transactions with two balanced postings are
+struct Transfer {
+    line: usize,
+    date: NaiveDate,
+    description: String,
+    source: String,
+    target: String,
+    amount_in_cents: u64,
+    currency: String,
+}
+
+fn into_transfer(transaction: ParsedTransaction) -> Result<Transfer, String> {
+    let [first, second] = <[ParsedPosting; 2]>::try_from(transaction.postings)
+        .map_err(|postings| format!("Expected two postings, found {}", postings.len()))?;
+
+    let (first_amount, second_amount) = match (first.amount.clone(), second.amount.clone()) {
+        (Some(first_amount), Some(second_amount)) => (first_amount, second_amount),
+        (Some((cents, currency)), None) => ((cents, currency.clone()), (-cents, currency)),
+        (None, Some((cents, currency))) => ((-cents, currency.clone()), (cents, currency)),
+        (None, None) => return Err("Postings without amounts".to_string()),
+    };
+
+    if first_amount.1 != second_amount.1 {
+        return Err("Postings in different commodities".to_string());
+    }
+
+    if first_amount.0 + second_amount.0 != 0 {
+        return Err("Unbalanced transaction".to_string());
+    }
+
+    let (source, target, cents) = match first_amount.0 {
+        0 => return Err("Zero amount transaction".to_string()),
+        cents if cents > 0 => (second.account, first.account, cents),
+        cents => (first.account, second.account, -cents),
+    };
+
+    Ok(Transfer {
+        line: transaction.line,
+        date: transaction.date,
+        description: transaction.description,
+        source,
+        target,
+        amount_in_cents: u64::try_from(cents).map_err(|_| "Amount too large".to_string())?,
+        currency: first_amount.1,
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlainTextImportReport {
+    pub accounts_created: usize,
+    pub transactions_posted: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<LineError>,
+}
+
+struct ImportedAccount {
+    alias: String,
+    account_type: AccountType,
+    currency: Option<String>,
+}
+
+// Accounts are created as they first show up, and those that would go below zero while
+// transactions replay get just enough as an opening balance, drawn from equity
+pub fn import(repos: &Repositories, ledger_id: Uuid, text: &str) -> Result<PlainTextImportReport, ApiError> {
+    let (book, mut errors) = parse(text);
+    let mut imported: BTreeMap<String, ImportedAccount> = BTreeMap::new();
+    let mut declare = |line: usize, name: &str, currency: Option<String>, errors: &mut Vec<LineError>| {
+        let Some((alias, account_type)) = alias_of(name) else {
+            errors.push(LineError {
+                line,
+                error: format!("Invalid account name {name}, expected a root like Assets or Expenses"),
+            });
+            return;
+        };
+
+        let account = imported.entry(name.to_string()).or_insert(ImportedAccount {
+            alias,
+            account_type,
+            currency: None,
+        });
+
+        match (&account.currency, currency) {
+            (Some(known), Some(currency)) if *known != currency => errors.push(LineError {
+                line,
+                error: format!("Account {name} holds {known}, not {currency}"),
+            }),
+            (None, Some(currency)) => account.currency = Some(currency),
+            _ => {},
+        }
+    };
+
+    for (line, name, currency) in &book.declared {
+        let currency = match currency.as_deref().map(accounts::parse_currency) {
+            Some(None) => {
+                errors.push(LineError {
+                    line: *line,
+                    error: format!("Invalid commodity for account {name}"),
+                });
+                None
+            },
+            parsed => parsed.flatten(),
+        };
+
+        declare(*line, name, currency, &mut errors);
+    }
+
+    let mut transfers = Vec::new();
+
+    for transaction in book.transactions {
+        let line = transaction.line;
+        match into_transfer(transaction) {
+            Ok(transfer) => {
+                declare(line, &transfer.source, Some(transfer.currency.clone()), &mut errors);
+                declare(line, &transfer.target, Some(transfer.currency.clone()), &mut errors);
+                transfers.push(transfer);
+            },
+            Err(error) => errors.push(LineError { line, error }),
+        }
+    }
+
+    // Accounts already in the ledger are reused when they hold the same currency
+    let mut existing: HashMap<String, Account> = HashMap::new();
+    let mut aliases: HashSet<String> = HashSet::new();
+
+    for (name, account) in &imported {
+        if !aliases.insert(account.alias.clone()) {
+            errors.push(LineError {
+                line: 0,
+                error: format!(
+                    "Account {name} maps to alias {}, as another account does",
+                    account.alias
+                ),
+            });
+        }
+
+        if let Some(found) = repos.accounts.fetch_by_alias(&ledger_id, &account.alias) {
+            let currency = account.currency.as_deref().unwrap_or(accounts::DEFAULT_CURRENCY);
+            if found.currency != currency {
+                errors.push(LineError {
+                    line: 0,
+                    error: format!(
+                        "Account {} already holds {}, not {currency}",
+                        found.alias, found.currency
+                    ),
+                });
+            }
+            existing.insert(name.clone(), found);
+        }
+    }
+
+    // Equity accounts may go below zero, as that's where the money of a journal comes from
+    let is_equity = |name: &str| {
+        existing
+            .get(name)
+            .map_or(imported[name].account_type, |account| account.account_type)
+            == AccountType::Equity
+    };
+
+    // Lowest balance each account reaches while replaying, new accounts start from zero
+    let mut running: HashMap<&str, i128> = existing
+        .iter()
+        .map(|(name, account)| (name.as_str(), i128::from(account.balance)))
+        .collect();
+    let mut lowest: HashMap<&str, i128> = HashMap::new();
+
+    for transfer in &transfers {
+        let amount = i128::from(transfer.amount_in_cents);
+        let source = running.entry(transfer.source.as_str()).or_default();
+        *source -= amount;
+        let reached = *source;
+        let lowest_source = lowest.entry(transfer.source.as_str()).or_default();
+        *lowest_source = (*lowest_source).min(reached);
+        *running.entry(transfer.target.as_str()).or_default() += amount;
+    }
+
+    for (name, account) in &existing {
+        if !is_equity(name) && lowest.get(name.as_str()).is_some_and(|reached| *reached < 0) {
+            errors.push(LineError {
+                line: 0,
+                error: format!("Insufficient balance on account {}", account.alias),
+            });
+        }
+    }
+
+    // New accounts going below zero get just that much as an opening balance
+    let mut fundings: Vec<(&str, u64)> = Vec::new();
+
+    for (name, account) in &imported {
+        let reached = lowest.get(name.as_str()).copied().unwrap_or_default();
+
+        if existing.contains_key(name) || is_equity(name) || reached >= 0 {
+            continue;
+        }
+
+        match u64::try_from(reached.unsigned_abs()) {
+            Ok(funding) => fundings.push((name.as_str(), funding)),
+            Err(_) => errors.push(LineError {
+                line: 0,
+                error: format!("Opening balance of account {} out of range", account.alias),
+            }),
+        }
+    }
+
+    if !errors.is_empty() {
+        errors.sort_by_key(|error| error.line);
+        return Ok(PlainTextImportReport {
+            accounts_created: 0,
+            transactions_posted: 0,
+            errors,
+        });
+    }
+
+    let mut ids: HashMap<&str, Uuid> = existing
+        .iter()
+        .map(|(name, account)| (name.as_str(), account.account_id))
+        .collect();
+    let mut new_accounts = Vec::new();
+
+    for (name, account) in imported.iter().filter(|(name, _)| !existing.contains_key(*name)) {
+        let new_account = Account {
+            account_type: account.account_type,
+            currency: account
+                .currency
+                .clone()
+                .unwrap_or_else(|| accounts::DEFAULT_CURRENCY.to_string()),
+            ..Account::new(&account.alias, 0).within(ledger_id)
+        };
+
+        ids.insert(name.as_str(), new_account.account_id);
+        new_accounts.push(new_account);
+    }
+
+    // Transactions keep the day they were booked on, opening balances come on the first one
+    let booked_at = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc();
+    let opened_at = transfers
+        .iter()
+        .map(|transfer| transfer.date)
+        .min()
+        .unwrap_or_else(|| Utc::now().date_naive());
+
+    let mut equity_accounts: HashMap<&str, Uuid> = HashMap::new();
+    let mut postings = Vec::new();
+
+    for (name, funding) in &fundings {
+        let currency = imported[*name]
+            .currency
+            .as_deref()
+            .unwrap_or(accounts::DEFAULT_CURRENCY);
+
+        let equity_account_id = *equity_accounts.entry(currency).or_insert_with(|| {
+            let (account_id, created) = imports::opening_balances_account(repos, ledger_id, &new_accounts, currency);
+            new_accounts.extend(created);
+            account_id
+        });
+
+        postings.push(
+            CreateNewTransaction::opening_balance(
+                equity_account_id,
+                ids[*name],
+                imports::OPENING_BALANCE_DESCRIPTION,
+                *funding,
+            )
+            .booked_at(booked_at(opened_at)),
+        );
+    }
+
+    postings.extend(transfers.iter().map(|transfer| {
+        let (source, target) = (ids[transfer.source.as_str()], ids[transfer.target.as_str()]);
+        let payload = if is_equity(&transfer.source) {
+            CreateNewTransaction::opening_balance(source, target, &transfer.description, transfer.amount_in_cents)
+        } else {
+            CreateNewTransaction::new_debit(source, target, &transfer.description, transfer.amount_in_cents)
+        };
+
+        payload.booked_at(booked_at(transfer.date))
+    }));
+
+    // Accounts and transactions are applied together, or not at all
+    let accounts_created = new_accounts.len();
+    let report = transactions::post_batch_with_accounts(repos, ledger_id, new_accounts, postings)?;
+
+    let errors: Vec<LineError> = report
+        .results
+        .iter()
+        .filter_map(|result| {
+            let error = result
+                .error
+                .as_ref()
+                .filter(|_| result.status != StatusCode::FAILED_DEPENDENCY.as_u16())?;
+            let line = result
+                .index
+                .checked_sub(fundings.len())
+                .map_or(0, |index| transfers[index].line);
+            Some(LineError {
+                line,
+                error: error.clone(),
+            })
+        })
+        .collect();
+
+    Ok(PlainTextImportReport {
+        accounts_created: if report.failed > 0 { 0 } else { accounts_created },
+        transactions_posted: report.created,
+        errors,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlainTextQuery {
+    #[serde(default)]
+    format: PlainTextFormat,
+}
+
+// Accounts stay locked while transactions are read, so balances and postings agree. End users
+// only get their accounts and the transfers between them, others show up as initial balances
+pub async fn export_plaintext(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Query(query): Query<PlainTextQuery>,
+) -> impl IntoResponse {
+    let exported = state.accounts.snapshot_with(&ledger_id, |accounts| {
+        let accounts: Vec<Account> = accounts
+            .into_iter()
+            .filter(|account| principal.owns(&account.account_id))
+            .collect();
+
+        let transactions: Vec<Transaction> = crate::read_lock(&state.transactions)
+            .iter()
+            .filter(|tx| {
+                tx.ledger_id == ledger_id && principal.owns(&tx.lhs_account_id) && principal.owns(&tx.rhs_account_id)
+            })
+            .cloned()
+            .collect();
+
+        export(ledger_id, &accounts, &transactions, query.format)
+    });
+
+    tracing::debug!(%ledger_id, format = ?query.format, "Ledger exported as plain text");
+
+    let disposition = format!("attachment; filename=\"ledger.{}\"", query.format.extension());
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        exported,
+    )
+}
+
+pub async fn import_plaintext(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    body: String,
+) -> Result<(StatusCode, Json<PlainTextImportReport>), ApiError> {
+    let report = import(&state, ledger_id, &body)?;
+
+    tracing::debug!(
+        accounts_created = report.accounts_created,
+        transactions_posted = report.transactions_posted,
+        errors = report.errors.len(),
+        "Plain-text journal imported"
+    );
+
+    let status = if report.errors.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+
+    Ok((status, Json(report)))
+}
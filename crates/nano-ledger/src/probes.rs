@@ -0,0 +1,44 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug, Default)]
+pub struct Lifecycle {
+    ready: AtomicBool,
+    shutting_down: AtomicBool,
+}
+
+impl Lifecycle {
+    pub fn mark_ready(&self) {
+        self.ready.store(true, Ordering::SeqCst);
+    }
+
+    pub fn mark_shutting_down(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst) && !self.is_shutting_down()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+}
+
+pub async fn liveness() -> StatusCode {
+    StatusCode::OK
+}
+
+pub async fn readiness(State(lifecycle): State<Arc<Lifecycle>>) -> StatusCode {
+    if lifecycle.is_ready() {
+        return StatusCode::OK;
+    }
+
+    tracing::debug!(shutting_down = lifecycle.is_shutting_down(), "Not ready");
+    StatusCode::SERVICE_UNAVAILABLE
+}
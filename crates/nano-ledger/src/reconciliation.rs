@@ -0,0 +1,788 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::accounts::AccountPath;
+use crate::auth::Principal;
+use crate::bank_statements::ExternalTransaction;
+use crate::errors::ApiError;
+use crate::ledgers::LedgerScope;
+use crate::transactions::{self, MovementType, Transaction};
+use crate::{Repositories, SharedState};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+pub const DEFAULT_DATE_WINDOW_DAYS: u64 = 3;
+
+pub const DEFAULT_MAX_GROUP_SIZE: usize = 4;
+
+// Larger groups are searched as groups of this size, which keeps subset sums cheap
+pub const MAX_GROUP_SIZE: usize = 6;
+
+// Candidates searched when grouping items, the most likely ones first
+const MAX_GROUP_CANDIDATES: usize = 16;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    // One statement line for one transaction
+    OneToOne,
+    // One statement line for several transactions, like a card settlement
+    OneToMany,
+    // Several statement lines for one transaction, like a payment received in parts
+    ManyToOne,
+}
+
+impl MatchKind {
+    fn of(external_transactions: usize, transactions: usize) -> Option<MatchKind> {
+        match (external_transactions, transactions) {
+            (1, 1) => Some(MatchKind::OneToOne),
+            (1, 2..) => Some(MatchKind::OneToMany),
+            (2.., 1) => Some(MatchKind::ManyToOne),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMethod {
+    Automatic,
+    Manual,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReconciliationMatch {
+    pub match_id: Uuid,
+    pub matched_at: DateTime<Utc>,
+    pub ledger_id: Uuid,
+    pub account_id: Uuid,
+    pub kind: MatchKind,
+    pub method: MatchMethod,
+    // How much descriptions look alike for automatic matches, from 0 to 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<f64>,
+    pub external_ids: Vec<Uuid>,
+    pub transaction_ids: Vec<Uuid>,
+}
+
+#[derive(Default)]
+pub struct ReconciliationRepository {
+    matches: HashMap<Uuid, ReconciliationMatch>,
+    by_external: HashMap<Uuid, Uuid>,
+    // Transactions between two reconciled accounts are matched once for each of them
+    by_transaction: HashMap<(Uuid, Uuid), Uuid>,
+}
+
+impl ReconciliationRepository {
+    fn save_match(&mut self, matched: ReconciliationMatch) {
+        for external_id in &matched.external_ids {
+            self.by_external.insert(*external_id, matched.match_id);
+        }
+
+        for transaction_id in &matched.transaction_ids {
+            self.by_transaction
+                .insert((matched.account_id, *transaction_id), matched.match_id);
+        }
+
+        self.matches.insert(matched.match_id, matched);
+    }
+
+    fn remove_match(&mut self, account_id: &Uuid, match_id: &Uuid) -> Option<ReconciliationMatch> {
+        self.matches
+            .get(match_id)
+            .filter(|matched| matched.account_id == *account_id)?;
+
+        let removed = self.matches.remove(match_id)?;
+
+        for external_id in &removed.external_ids {
+            self.by_external.remove(external_id);
+        }
+
+        for transaction_id in &removed.transaction_ids {
+            self.by_transaction.remove(&(removed.account_id, *transaction_id));
+        }
+
+        Some(removed)
+    }
+
+    pub fn fetch_match(&self, match_id: &Uuid) -> Option<&ReconciliationMatch> {
+        self.matches.get(match_id)
+    }
+
+    pub fn external_match(&self, external_id: &Uuid) -> Option<&ReconciliationMatch> {
+        self.by_external
+            .get(external_id)
+            .and_then(|match_id| self.matches.get(match_id))
+    }
+
+    pub fn transaction_match(&self, account_id: &Uuid, transaction_id: &Uuid) -> Option<&ReconciliationMatch> {
+        self.by_transaction
+            .get(&(*account_id, *transaction_id))
+            .and_then(|match_id| self.matches.get(match_id))
+    }
+}
+
+// A transaction as seen from the reconciled account, money coming in being a credit
+struct LedgerItem<'a> {
+    transaction: &'a Transaction,
+    movement_type: MovementType,
+    booked_on: NaiveDate,
+}
+
+fn ledger_item<'a>(transaction: &'a Transaction, account_id: &Uuid) -> Option<LedgerItem<'a>> {
+    // Transfers to the same account move nothing on the bank side
+    let movement_type = if transaction.lhs_account_id == transaction.rhs_account_id {
+        return None;
+    } else if transaction.lhs_account_id == *account_id {
+        transaction.movement_type
+    } else if transaction.rhs_account_id == *account_id {
+        transaction.movement_type.opposite()
+    } else {
+        return None;
+    };
+
+    Some(LedgerItem {
+        transaction,
+        movement_type,
+        booked_on: transaction.created_at.date_naive(),
+    })
+}
+
+fn signed_amount(movement_type: MovementType, amount_in_cents: u64) -> i128 {
+    match movement_type {
+        MovementType::Credit => i128::from(amount_in_cents),
+        MovementType::Debit => -i128::from(amount_in_cents),
+    }
+}
+
+// Banks may book a line some days before or after the ledger, on its booking or value date
+fn days_apart(line: &ExternalTransaction, date: NaiveDate) -> u64 {
+    [Some(line.booked_on), line.value_on]
+        .into_iter()
+        .flatten()
+        .map(|booked_on| (booked_on - date).num_days().unsigned_abs())
+        .min()
+        .unwrap_or(u64::MAX)
+}
+
+// Share of the shortest text's words found in the other one. A reference quoted
+// in the ledger description is as good as a perfect match.
+fn similarity(line: &ExternalTransaction, description: &str) -> f64 {
+    let description_lowercase = description.to_lowercase();
+
+    let quoted = [&line.reference, &line.bank_reference]
+        .into_iter()
+        .flatten()
+        .filter(|reference| reference.len() >= 3)
+        .any(|reference| description_lowercase.contains(&reference.to_lowercase()));
+
+    if quoted {
+        return 1.0;
+    }
+
+    let line_text = [
+        Some(&line.description),
+        line.reference.as_ref(),
+        line.counterparty.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(String::as_str)
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    let line_words = transactions::words(&line_text);
+    let ledger_words = transactions::words(description);
+    let shortest = line_words.len().min(ledger_words.len());
+
+    if shortest == 0 {
+        return 0.0;
+    }
+
+    line_words.intersection(&ledger_words).count() as f64 / shortest as f64
+}
+
+fn search_combination(target: u64, amounts: &[u64], start: usize, max_size: usize, chosen: &mut Vec<usize>) -> bool {
+    if target == 0 {
+        return chosen.len() >= 2;
+    }
+
+    if chosen.len() == max_size {
+        return false;
+    }
+
+    for position in start..amounts.len() {
+        if amounts[position] > 0 && amounts[position] <= target {
+            chosen.push(position);
+            if search_combination(target - amounts[position], amounts, position + 1, max_size, chosen) {
+                return true;
+            }
+            chosen.pop();
+        }
+    }
+
+    false
+}
+
+// Positions of 2 to `max_size` amounts adding up to `target`, earlier amounts preferred
+fn combination_summing_to(target: u64, amounts: &[u64], max_size: usize) -> Option<Vec<usize>> {
+    let mut chosen = Vec::new();
+    search_combination(target, amounts, 0, max_size, &mut chosen).then_some(chosen)
+}
+
+fn automatic_match(
+    ledger_id: Uuid,
+    account_id: Uuid,
+    lines: Vec<&ExternalTransaction>,
+    items: Vec<&LedgerItem<'_>>,
+) -> ReconciliationMatch {
+    let similarities: Vec<f64> = lines
+        .iter()
+        .flat_map(|line| items.iter().map(|item| similarity(line, &item.transaction.description)))
+        .collect();
+
+    // Groups are as similar as their most alike pair
+    let similarity = similarities.into_iter().fold(0.0, f64::max);
+
+    ReconciliationMatch {
+        match_id: Uuid::new_v4(),
+        matched_at: Utc::now(),
+        ledger_id,
+        account_id,
+        kind: MatchKind::of(lines.len(), items.len()).expect("Automatic matches are never many to many"),
+        method: MatchMethod::Automatic,
+        similarity: Some(similarity),
+        external_ids: lines.iter().map(|line| line.external_id).collect(),
+        transaction_ids: items.iter().map(|item| item.transaction.transaction_id).collect(),
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReconcileOptions {
+    // Days a statement line may be booked before or after its transactions
+    #[serde(default = "default_date_window")]
+    pub date_window_days: u64,
+    // Most items grouped into one-to-many and many-to-one matches
+    #[serde(default = "default_max_group_size")]
+    pub max_group_size: usize,
+    pub from: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+fn default_date_window() -> u64 {
+    DEFAULT_DATE_WINDOW_DAYS
+}
+
+fn default_max_group_size() -> usize {
+    DEFAULT_MAX_GROUP_SIZE
+}
+
+impl Default for ReconcileOptions {
+    fn default() -> Self {
+        ReconcileOptions {
+            date_window_days: DEFAULT_DATE_WINDOW_DAYS,
+            max_group_size: DEFAULT_MAX_GROUP_SIZE,
+            from: None,
+            until: None,
+        }
+    }
+}
+
+fn start_of(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+// Transactions of the account booked within the period, copied out of the transactions lock
+fn account_transactions(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    account_id: Uuid,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Vec<Transaction> {
+    crate::read_lock(&repos.transactions)
+        .for_account(&account_id, from.map(start_of), until.map(start_of))
+        .filter(|transaction| transaction.ledger_id == ledger_id)
+        .cloned()
+        .collect()
+}
+
+fn statement_lines(
+    repos: &Repositories,
+    account_id: Uuid,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Vec<ExternalTransaction> {
+    crate::read_lock(&repos.external_transactions)
+        .for_account(&account_id, from, until)
+        .cloned()
+        .collect()
+}
+
+// Matches unmatched statement lines of the period to unmatched transactions of the account,
+// with the same amount first, then by groups adding up to the same amount
+pub fn reconcile(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    account_id: Uuid,
+    options: &ReconcileOptions,
+) -> Vec<ReconciliationMatch> {
+    let window = options.date_window_days;
+    let max_group_size = options.max_group_size.min(MAX_GROUP_SIZE);
+
+    // Transactions just outside of the period may still match lines within it
+    let earliest = options.from.and_then(|from| from.checked_sub_days(Days::new(window)));
+    let latest = options
+        .until
+        .and_then(|until| until.checked_add_days(Days::new(window)));
+
+    // Both sides are copied out first, only matches stay locked while matching
+    let transactions = account_transactions(repos, ledger_id, account_id, earliest, latest);
+    let external_transactions = statement_lines(repos, account_id, options.from, options.until);
+    let mut reconciliations = crate::write_lock(&repos.reconciliations);
+
+    let lines: Vec<&ExternalTransaction> = external_transactions
+        .iter()
+        .filter(|line| reconciliations.external_match(&line.external_id).is_none())
+        .collect();
+
+    let items: Vec<LedgerItem<'_>> = transactions
+        .iter()
+        .filter_map(|transaction| ledger_item(transaction, &account_id))
+        .filter(|item| {
+            reconciliations
+                .transaction_match(&account_id, &item.transaction.transaction_id)
+                .is_none()
+        })
+        .collect();
+
+    let mut line_matched = vec![false; lines.len()];
+    let mut item_matched = vec![false; items.len()];
+    let mut matches = Vec::new();
+
+    // One to one, same amount and direction within the date window, most similar first
+    let mut items_by_amount: HashMap<i128, Vec<usize>> = HashMap::new();
+    for (position, item) in items.iter().enumerate() {
+        let amount = signed_amount(item.movement_type, item.transaction.amount_in_cents);
+        items_by_amount.entry(amount).or_default().push(position);
+    }
+
+    let mut pairs = Vec::new();
+    for (line_position, line) in lines.iter().enumerate() {
+        let amount = signed_amount(line.movement_type, line.amount_in_cents);
+
+        for item_position in items_by_amount.get(&amount).into_iter().flatten() {
+            let item = &items[*item_position];
+            let days = days_apart(line, item.booked_on);
+
+            if days <= window {
+                let similarity = similarity(line, &item.transaction.description);
+                pairs.push((similarity, days, line_position, *item_position));
+            }
+        }
+    }
+
+    pairs.sort_by(|lhs, rhs| {
+        rhs.0
+            .total_cmp(&lhs.0)
+            .then(lhs.1.cmp(&rhs.1))
+            .then(lhs.2.cmp(&rhs.2))
+            .then(lhs.3.cmp(&rhs.3))
+    });
+
+    for (_, _, line_position, item_position) in pairs {
+        if !line_matched[line_position] && !item_matched[item_position] {
+            line_matched[line_position] = true;
+            item_matched[item_position] = true;
+            matches.push(automatic_match(
+                ledger_id,
+                account_id,
+                vec![lines[line_position]],
+                vec![&items[item_position]],
+            ));
+        }
+    }
+
+    // One to many, a statement line for transactions adding up to its amount
+    for line_position in 0..lines.len() {
+        if line_matched[line_position] {
+            continue;
+        }
+
+        let line = lines[line_position];
+        let mut candidates: Vec<(f64, u64, usize)> = (0..items.len())
+            .filter(|position| !item_matched[*position])
+            .map(|position| (position, &items[position]))
+            .filter(|(_, item)| item.movement_type == line.movement_type)
+            .filter(|(_, item)| item.transaction.amount_in_cents < line.amount_in_cents)
+            .map(|(position, item)| {
+                let similarity = similarity(line, &item.transaction.description);
+                (similarity, days_apart(line, item.booked_on), position)
+            })
+            .filter(|(_, days, _)| *days <= window)
+            .collect();
+
+        candidates.sort_by(|lhs, rhs| rhs.0.total_cmp(&lhs.0).then(lhs.1.cmp(&rhs.1)).then(lhs.2.cmp(&rhs.2)));
+        candidates.truncate(MAX_GROUP_CANDIDATES);
+
+        let amounts: Vec<u64> = candidates
+            .iter()
+            .map(|(_, _, position)| items[*position].transaction.amount_in_cents)
+            .collect();
+
+        if let Some(chosen) = combination_summing_to(line.amount_in_cents, &amounts, max_group_size) {
+            let grouped: Vec<usize> = chosen.iter().map(|choice| candidates[*choice].2).collect();
+            line_matched[line_position] = true;
+            for position in &grouped {
+                item_matched[*position] = true;
+            }
+
+            let grouped_items = grouped.iter().map(|position| &items[*position]).collect();
+            matches.push(automatic_match(ledger_id, account_id, vec![line], grouped_items));
+        }
+    }
+
+    // Many to one, statement lines adding up to the amount of a transaction
+    for item_position in 0..items.len() {
+        if item_matched[item_position] {
+            continue;
+        }
+
+        let item = &items[item_position];
+        let mut candidates: Vec<(f64, u64, usize)> = (0..lines.len())
+            .filter(|position| !line_matched[*position])
+            .map(|position| (position, lines[position]))
+            .filter(|(_, line)| line.movement_type == item.movement_type)
+            .filter(|(_, line)| line.amount_in_cents < item.transaction.amount_in_cents)
+            .map(|(position, line)| {
+                let similarity = similarity(line, &item.transaction.description);
+                (similarity, days_apart(line, item.booked_on), position)
+            })
+            .filter(|(_, days, _)| *days <= window)
+            .collect();
+
+        candidates.sort_by(|lhs, rhs| rhs.0.total_cmp(&lhs.0).then(lhs.1.cmp(&rhs.1)).then(lhs.2.cmp(&rhs.2)));
+        candidates.truncate(MAX_GROUP_CANDIDATES);
+
+        let amounts: Vec<u64> = candidates
+            .iter()
+            .map(|(_, _, position)| lines[*position].amount_in_cents)
+            .collect();
+
+        if let Some(chosen) = combination_summing_to(item.transaction.amount_in_cents, &amounts, max_group_size) {
+            let grouped: Vec<usize> = chosen.iter().map(|choice| candidates[*choice].2).collect();
+            item_matched[item_position] = true;
+            for position in &grouped {
+                line_matched[*position] = true;
+            }
+
+            let grouped_lines = grouped.iter().map(|position| lines[*position]).collect();
+            matches.push(automatic_match(ledger_id, account_id, grouped_lines, vec![item]));
+        }
+    }
+
+    for matched in &matches {
+        reconciliations.save_match(matched.clone());
+    }
+
+    matches
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct CreateNewMatch {
+    pub external_ids: Vec<Uuid>,
+    pub transaction_ids: Vec<Uuid>,
+}
+
+fn unprocessable(message: String) -> ApiError {
+    ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, message)
+}
+
+// Manual matches follow the same shapes as automatic ones, without date or similarity checks,
+// but statement lines must add up to the same amount as transactions
+pub fn match_manually(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    account_id: Uuid,
+    payload: CreateNewMatch,
+) -> Result<ReconciliationMatch, ApiError> {
+    let Some(kind) = MatchKind::of(payload.external_ids.len(), payload.transaction_ids.len()) else {
+        return Err(unprocessable(
+            "Matches pair one statement line with transactions, or statement lines with one transaction".to_string(),
+        ));
+    };
+
+    let distinct_external: HashSet<&Uuid> = payload.external_ids.iter().collect();
+    let distinct_transactions: HashSet<&Uuid> = payload.transaction_ids.iter().collect();
+    if distinct_external.len() != payload.external_ids.len()
+        || distinct_transactions.len() != payload.transaction_ids.len()
+    {
+        return Err(unprocessable(
+            "Statement lines and transactions can't be repeated".to_string(),
+        ));
+    }
+
+    let transactions = crate::read_lock(&repos.transactions);
+    let external_transactions = crate::read_lock(&repos.external_transactions);
+    let mut reconciliations = crate::write_lock(&repos.reconciliations);
+
+    let mut external_total = 0;
+    for external_id in &payload.external_ids {
+        let Some(line) = external_transactions
+            .fetch_transaction(external_id)
+            .filter(|line| line.account_id == account_id)
+        else {
+            return Err(unprocessable(format!(
+                "Statement line {external_id} not found for account {account_id}"
+            )));
+        };
+
+        if reconciliations.external_match(external_id).is_some() {
+            return Err(ApiError::conflict(format!(
+                "Statement line {external_id} is already matched"
+            )));
+        }
+
+        external_total += signed_amount(line.movement_type, line.amount_in_cents);
+    }
+
+    let mut ledger_total = 0;
+    for transaction_id in &payload.transaction_ids {
+        let Some(item) = transactions
+            .fetch_transaction(transaction_id)
+            .filter(|transaction| transaction.ledger_id == ledger_id)
+            .and_then(|transaction| ledger_item(transaction, &account_id))
+        else {
+            return Err(unprocessable(format!(
+                "Transaction {transaction_id} does not move money on account {account_id}"
+            )));
+        };
+
+        if reconciliations.transaction_match(&account_id, transaction_id).is_some() {
+            return Err(ApiError::conflict(format!(
+                "Transaction {transaction_id} is already matched"
+            )));
+        }
+
+        ledger_total += signed_amount(item.movement_type, item.transaction.amount_in_cents);
+    }
+
+    if external_total != ledger_total {
+        return Err(unprocessable(format!(
+            "Statement lines add up to {external_total} cents, but transactions to {ledger_total} cents"
+        )));
+    }
+
+    let matched = ReconciliationMatch {
+        match_id: Uuid::new_v4(),
+        matched_at: Utc::now(),
+        ledger_id,
+        account_id,
+        kind,
+        method: MatchMethod::Manual,
+        similarity: None,
+        external_ids: payload.external_ids,
+        transaction_ids: payload.transaction_ids,
+    };
+
+    reconciliations.save_match(matched.clone());
+    Ok(matched)
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MatchedItems {
+    #[serde(rename = "match")]
+    pub matched: ReconciliationMatch,
+    pub external_transactions: Vec<ExternalTransaction>,
+    pub transactions: Vec<Transaction>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReconciliationReport {
+    pub ledger_id: Uuid,
+    pub account_id: Uuid,
+    pub from: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    // Nothing is left unmatched on either side over the period
+    pub reconciled: bool,
+    pub matched: Vec<MatchedItems>,
+    pub unmatched_transactions: Vec<Transaction>,
+    pub unmatched_external_transactions: Vec<ExternalTransaction>,
+}
+
+// Matches are reported when any of their items falls within the period
+pub fn build_report(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    account_id: Uuid,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> ReconciliationReport {
+    let transactions = account_transactions(repos, ledger_id, account_id, from, until);
+    let lines = statement_lines(repos, account_id, from, until);
+
+    let mut matches = Vec::new();
+    let mut unmatched_external_transactions = Vec::new();
+    let mut unmatched_transactions = Vec::new();
+
+    {
+        let reconciliations = crate::read_lock(&repos.reconciliations);
+        let mut seen = HashSet::new();
+        let mut track = |matched: &ReconciliationMatch| {
+            if seen.insert(matched.match_id) {
+                matches.push(matched.clone());
+            }
+        };
+
+        for line in lines {
+            match reconciliations.external_match(&line.external_id) {
+                Some(matched) => track(matched),
+                None => unmatched_external_transactions.push(line),
+            }
+        }
+
+        for transaction in transactions {
+            if ledger_item(&transaction, &account_id).is_none() {
+                continue;
+            }
+
+            match reconciliations.transaction_match(&account_id, &transaction.transaction_id) {
+                Some(matched) => track(matched),
+                None => unmatched_transactions.push(transaction),
+            }
+        }
+    }
+
+    // Matched items may lie outside of the period, they are fetched one by one
+    let mut matched: Vec<MatchedItems> = {
+        let transactions = crate::read_lock(&repos.transactions);
+        let external_transactions = crate::read_lock(&repos.external_transactions);
+
+        matches
+            .into_iter()
+            .map(|matched| MatchedItems {
+                external_transactions: matched
+                    .external_ids
+                    .iter()
+                    .filter_map(|external_id| external_transactions.fetch_transaction(external_id).cloned())
+                    .collect(),
+                transactions: matched
+                    .transaction_ids
+                    .iter()
+                    .filter_map(|transaction_id| transactions.fetch_transaction(transaction_id).cloned())
+                    .collect(),
+                matched,
+            })
+            .collect()
+    };
+
+    matched.sort_by_key(|items| {
+        let booked_on = items.external_transactions.iter().map(|line| line.booked_on).min();
+        (booked_on, items.matched.matched_at)
+    });
+
+    ReconciliationReport {
+        ledger_id,
+        account_id,
+        from,
+        until,
+        reconciled: unmatched_transactions.is_empty() && unmatched_external_transactions.is_empty(),
+        matched,
+        unmatched_transactions,
+        unmatched_external_transactions,
+    }
+}
+
+fn check_account(
+    repos: &Repositories,
+    principal: &Principal,
+    ledger_id: Uuid,
+    account_id: Uuid,
+) -> Result<(), ApiError> {
+    let found = repos
+        .accounts
+        .ledger_of(&account_id)
+        .filter(|owner| *owner == ledger_id && principal.owns(&account_id));
+
+    if found.is_none() {
+        tracing::debug!(%account_id, "Account not found");
+        return Err(ApiError::not_found(format!("Account {account_id} not found")));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MatchPath {
+    account_id: Uuid,
+    match_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportQuery {
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+}
+
+pub async fn run_reconciliation(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(AccountPath { account_id }): Path<AccountPath>,
+    Query(options): Query<ReconcileOptions>,
+) -> Result<Json<Vec<ReconciliationMatch>>, ApiError> {
+    check_account(&state, &principal, ledger_id, account_id)?;
+
+    let matches = reconcile(&state, ledger_id, account_id, &options);
+    tracing::debug!(%account_id, matched = matches.len(), "Account reconciled");
+    Ok(Json(matches))
+}
+
+pub async fn new_match(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(AccountPath { account_id }): Path<AccountPath>,
+    Json(payload): Json<CreateNewMatch>,
+) -> Result<Json<ReconciliationMatch>, ApiError> {
+    check_account(&state, &principal, ledger_id, account_id)?;
+
+    let matched = match_manually(&state, ledger_id, account_id, payload)?;
+    tracing::info!(%account_id, match_id = %matched.match_id, kind = ?matched.kind, "Items matched manually");
+    Ok(Json(matched))
+}
+
+pub async fn delete_match(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(MatchPath { account_id, match_id }): Path<MatchPath>,
+) -> Result<StatusCode, ApiError> {
+    check_account(&state, &principal, ledger_id, account_id)?;
+
+    if crate::write_lock(&state.reconciliations)
+        .remove_match(&account_id, &match_id)
+        .is_none()
+    {
+        return Err(ApiError::not_found(format!("Match {match_id} not found")));
+    }
+
+    tracing::info!(%account_id, %match_id, "Items unmatched");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn reconciliation_report(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(AccountPath { account_id }): Path<AccountPath>,
+    Query(ReportQuery { from, until }): Query<ReportQuery>,
+) -> Result<Json<ReconciliationReport>, ApiError> {
+    check_account(&state, &principal, ledger_id, account_id)?;
+    Ok(Json(build_report(&state, ledger_id, account_id, from, until)))
+}
@@ -0,0 +1,79 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use axum::Router;
+use std::future::{Future, IntoFuture};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    Drained,
+    TimedOut,
+}
+
+pub fn drain_timeout() -> Duration {
+    let seconds = std::env::var("SHUTDOWN_DRAIN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_DRAIN_TIMEOUT_SECS);
+
+    Duration::from_secs(seconds)
+}
+
+pub async fn termination_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c().await.expect("cannot install SIGINT handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("cannot install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => tracing::info!("Received SIGINT"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+pub async fn serve<F>(
+    listener: TcpListener,
+    router: Router,
+    signal: F,
+    drain_timeout: Duration,
+) -> std::io::Result<ShutdownOutcome>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let draining = Arc::new(Notify::new());
+    let notifier = draining.clone();
+
+    let server = axum::serve(listener, router)
+        .with_graceful_shutdown(async move {
+            signal.await;
+            tracing::info!(?drain_timeout, "Stopped accepting connections");
+            notifier.notify_one();
+        })
+        .into_future();
+
+    let deadline = async {
+        draining.notified().await;
+        tokio::time::sleep(drain_timeout).await;
+    };
+
+    tokio::select! {
+        served = server => served.map(|_| ShutdownOutcome::Drained),
+        _ = deadline => Ok(ShutdownOutcome::TimedOut),
+    }
+}
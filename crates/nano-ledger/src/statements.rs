@@ -0,0 +1,253 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::accounts::{AccountPath, AccountType};
+use crate::auth::Principal;
+use crate::errors::ApiError;
+use crate::journal::JournalEntry;
+use crate::ledgers::LedgerScope;
+use crate::transactions::MovementType;
+use crate::{AppState, Repositories};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::pkcs8::DecodePrivateKey;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+pub const SIGNATURE_ALGORITHM: &str = "Ed25519";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Statement {
+    pub statement_id: Uuid,
+    pub issued_at: DateTime<Utc>,
+    pub ledger_id: Uuid,
+    pub account_id: Uuid,
+    pub alias: String,
+    pub account_version: u64,
+    pub period_start: Option<DateTime<Utc>>,
+    pub period_end: DateTime<Utc>,
+    pub opening_balance: i64,
+    pub closing_balance: i64,
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Statement {
+    fn signing_payload(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Cannot serialize statement")
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SignedStatement {
+    pub statement: Statement,
+    pub algorithm: String,
+    pub key_id: String,
+    pub signature: String,
+}
+
+// Published as a JSON Web Key, so standard tooling can consume it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicKey {
+    pub kty: String,
+    pub crv: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    pub kid: String,
+    pub x: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct PublicKeys {
+    pub keys: Vec<PublicKey>,
+}
+
+pub struct StatementSigner {
+    key_id: String,
+    signing_key: SigningKey,
+}
+
+impl StatementSigner {
+    pub fn with_key(signing_key: SigningKey) -> Self {
+        let public_key = signing_key.verifying_key().to_bytes();
+        let key_id = hex::encode(&Sha256::digest(public_key)[..8]);
+
+        StatementSigner { key_id, signing_key }
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let key_file = std::env::var("STATEMENT_SIGNING_KEY_FILE").ok()?;
+        let signer = StatementSigner::from_key_file(&key_file).expect("cannot load statement signing key");
+        tracing::info!(key_id = %signer.key_id, "Statement signing key loaded");
+        Some(signer)
+    }
+
+    // Ed25519 private key in PKCS#8 PEM, as generated by `openssl genpkey -algorithm ed25519`
+    pub fn from_key_file(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|error| format!("Cannot read {path} : {error}"))?;
+        let signing_key = SigningKey::from_pkcs8_pem(&contents)
+            .map_err(|error| format!("Invalid Ed25519 key at {path} : {error}"))?;
+        Ok(StatementSigner::with_key(signing_key))
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            kty: "OKP".to_string(),
+            crv: SIGNATURE_ALGORITHM.to_string(),
+            alg: "EdDSA".to_string(),
+            key_use: "sig".to_string(),
+            kid: self.key_id.clone(),
+            x: URL_SAFE_NO_PAD.encode(self.signing_key.verifying_key().as_bytes()),
+        }
+    }
+
+    pub fn sign(&self, statement: Statement) -> SignedStatement {
+        let signature = self.signing_key.sign(&statement.signing_payload());
+
+        SignedStatement {
+            statement,
+            algorithm: SIGNATURE_ALGORITHM.to_string(),
+            key_id: self.key_id.clone(),
+            signature: URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+        }
+    }
+}
+
+// Checks the signature against the published key matching the statement key id
+pub fn verify_statement(signed: &SignedStatement, published: &PublicKeys) -> bool {
+    let Some(public_key) = published.keys.iter().find(|key| key.kid == signed.key_id) else {
+        return false;
+    };
+
+    let verifying_key = URL_SAFE_NO_PAD
+        .decode(&public_key.x)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok());
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(&signed.signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok());
+
+    match (verifying_key, signature) {
+        (Some(verifying_key), Some(signature)) => verifying_key
+            .verify_strict(&signed.statement.signing_payload(), &signature)
+            .is_ok(),
+        _ => false,
+    }
+}
+
+// Balance change caused by an entry, credits increase the balance and debits decrease it
+fn balance_change(entry: &JournalEntry) -> i128 {
+    match entry.movement_type {
+        MovementType::Credit => i128::from(entry.amount_in_cents),
+        MovementType::Debit => -i128::from(entry.amount_in_cents),
+    }
+}
+
+pub fn build_statement(
+    repos: &Repositories,
+    account_id: &Uuid,
+    period_start: Option<DateTime<Utc>>,
+    period_end: Option<DateTime<Utc>>,
+) -> Option<Result<Statement, ApiError>> {
+    // Same lock order as postings, the account first and then the journal
+    repos.accounts.read_with(account_id, |account| {
+        let issued_at = Utc::now();
+        let period_end = period_end.unwrap_or(issued_at);
+        let journal = crate::read_lock(&repos.journal);
+
+        let mut changes_after_end = 0;
+        let mut changes_within = 0;
+        let mut entries = Vec::new();
+
+        for entry in journal.fetch_by_account(account_id) {
+            if entry.created_at >= period_end {
+                changes_after_end += balance_change(entry);
+            } else if period_start.is_none_or(|start| entry.created_at >= start) {
+                changes_within += balance_change(entry);
+                entries.push(entry.clone());
+            }
+        }
+
+        // Only equity accounts go below zero, any other negative balance means the journal disagrees
+        let balance = |replayed: i128| {
+            i64::try_from(replayed)
+                .ok()
+                .filter(|balance| *balance >= 0 || account.account_type == AccountType::Equity)
+                .ok_or_else(|| {
+                    tracing::error!(%account_id, replayed, "Statement balance doesn't match the account");
+                    ApiError::new(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Balance of account {account_id} can't be replayed from its journal entries"),
+                    )
+                })
+        };
+
+        let closing_balance = balance(i128::from(account.balance) - changes_after_end)?;
+        let opening_balance = balance(i128::from(closing_balance) - changes_within)?;
+
+        Ok(Statement {
+            statement_id: Uuid::new_v4(),
+            issued_at,
+            ledger_id: account.ledger_id,
+            account_id: account.account_id,
+            alias: account.alias.clone(),
+            account_version: account.version,
+            period_start,
+            period_end,
+            opening_balance,
+            closing_balance,
+            entries,
+        })
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+pub async fn account_statement(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(AccountPath { account_id }): Path<AccountPath>,
+    Query(StatementQuery { from, until }): Query<StatementQuery>,
+) -> Result<Json<SignedStatement>, ApiError> {
+    let Some(signer) = state.signer else {
+        tracing::debug!("Statement signing key not configured");
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Statements are not available, no signing key configured",
+        ));
+    };
+
+    // Accounts out of reach are not found, whether or not their statement could be built
+    let visible = state.repos.accounts.ledger_of(&account_id) == Some(ledger_id) && principal.owns(&account_id);
+
+    let statement = build_statement(&state.repos, &account_id, from, until)
+        .filter(|_| visible)
+        .transpose()?;
+
+    let Some(statement) = statement else {
+        tracing::debug!(%account_id, "Account not found");
+        return Err(ApiError::not_found(format!("Account {account_id} not found")));
+    };
+
+    tracing::debug!(%account_id, statement_id = %statement.statement_id, "Statement issued");
+    Ok(Json(signer.sign(statement)))
+}
+
+pub async fn public_keys(State(state): State<AppState>) -> Json<PublicKeys> {
+    let keys = state.signer.iter().map(|signer| signer.public_key()).collect();
+    Json(PublicKeys { keys })
+}
@@ -1,23 +1,32 @@
 // Copyright 2025 Dotanuki Labs
 // SPDX-License-Identifier: MIT
 
-use crate::SharedState;
-use crate::journal::JournalEntry;
-use axum::Json;
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
-use chrono::{DateTime, Utc};
+use crate::accounts::{self, Account, AccountType, LockedAccounts};
+use crate::auth::Principal;
+use crate::errors::ApiError;
+use crate::events::EventPayload;
+use crate::journal::{JournalEntry, PreparedEntry};
+use crate::ledgers::LedgerScope;
+use crate::metrics::{METRICS, PostingOutcome};
+use crate::pagination::{self, KeysetCursor};
+use crate::{Repositories, SharedState};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::{Extension, Json};
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::{Bound, Range};
 use uuid::Uuid;
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub enum MovementType {
     Debit,
     Credit,
 }
 
 impl MovementType {
-    fn opposite(&self) -> MovementType {
+    pub fn opposite(&self) -> MovementType {
         match self {
             MovementType::Debit => MovementType::Credit,
             MovementType::Credit => MovementType::Debit,
@@ -32,10 +41,22 @@ pub struct CreateNewTransaction {
     rhs_account_id: Uuid,
     description: String,
     amount_in_cents: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lhs_expected_version: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rhs_expected_version: Option<u64>,
+    // Only set by imports booking opening balances, never by clients
+    #[serde(skip)]
+    from_equity: bool,
+    // Only set by imports replaying dated journals, never by clients
+    #[serde(skip)]
+    booked_at: Option<DateTime<Utc>>,
+    // Only set by reversals, never by clients
+    #[serde(skip)]
+    reverses: Option<Uuid>,
 }
 
 impl CreateNewTransaction {
-    #[cfg(test)]
     pub fn new_debit(from: Uuid, to: Uuid, description: &str, amount: u64) -> Self {
         CreateNewTransaction {
             movement_type: MovementType::Debit,
@@ -43,10 +64,14 @@ impl CreateNewTransaction {
             rhs_account_id: to,
             description: description.to_string(),
             amount_in_cents: amount,
+            lhs_expected_version: None,
+            rhs_expected_version: None,
+            from_equity: false,
+            booked_at: None,
+            reverses: None,
         }
     }
 
-    #[cfg(test)]
     pub fn new_credit(from: Uuid, to: Uuid, description: &str, amount: u64) -> Self {
         CreateNewTransaction {
             movement_type: MovementType::Credit,
@@ -54,8 +79,101 @@ impl CreateNewTransaction {
             rhs_account_id: to,
             description: description.to_string(),
             amount_in_cents: amount,
+            lhs_expected_version: None,
+            rhs_expected_version: None,
+            from_equity: false,
+            booked_at: None,
+            reverses: None,
         }
     }
+
+    // Opening balances are drawn from an equity account, which goes below zero by as much
+    pub fn opening_balance(equity_account_id: Uuid, account_id: Uuid, description: &str, amount: u64) -> Self {
+        CreateNewTransaction {
+            from_equity: true,
+            ..CreateNewTransaction::new_debit(equity_account_id, account_id, description, amount)
+        }
+    }
+
+    // Moves the amount of `transaction` back between the same accounts
+    pub fn reversal_of(transaction: &Transaction) -> Self {
+        let description = format!("Reversal of {}", transaction.description);
+
+        CreateNewTransaction {
+            movement_type: transaction.movement_type.opposite(),
+            reverses: Some(transaction.transaction_id),
+            ..CreateNewTransaction::new_debit(
+                transaction.lhs_account_id,
+                transaction.rhs_account_id,
+                &description,
+                transaction.amount_in_cents,
+            )
+        }
+    }
+
+    pub fn booked_at(self, booked_at: DateTime<Utc>) -> Self {
+        CreateNewTransaction {
+            booked_at: Some(booked_at),
+            ..self
+        }
+    }
+
+    pub fn expecting_versions(self, lhs_expected_version: Option<u64>, rhs_expected_version: Option<u64>) -> Self {
+        CreateNewTransaction {
+            lhs_expected_version,
+            rhs_expected_version,
+            ..self
+        }
+    }
+
+    // The account money leaves, whatever the movement type
+    fn source_account_id(&self) -> Uuid {
+        match self.movement_type {
+            MovementType::Debit => self.lhs_account_id,
+            MovementType::Credit => self.rhs_account_id,
+        }
+    }
+
+    // Each entity tag in If-Match sets the expected version of the account it was issued for
+    fn apply_if_match(&mut self, headers: &HeaderMap) -> Result<(), ApiError> {
+        for value in headers.get_all(header::IF_MATCH) {
+            let value = value
+                .to_str()
+                .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, "Invalid If-Match header"))?;
+
+            for etag in value.split(',').map(str::trim).filter(|etag| *etag != "*") {
+                let Some((account_id, version)) = accounts::parse_etag(etag) else {
+                    return Err(ApiError::new(
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid entity tag {etag} in If-Match header"),
+                    ));
+                };
+
+                let expected_version = if account_id == self.lhs_account_id {
+                    &mut self.lhs_expected_version
+                } else if account_id == self.rhs_account_id {
+                    &mut self.rhs_expected_version
+                } else {
+                    return Err(ApiError::new(
+                        StatusCode::PRECONDITION_FAILED,
+                        format!("Entity tag {etag} does not refer to an account of this transaction"),
+                    ));
+                };
+
+                match expected_version {
+                    Some(expected) if *expected != version => {
+                        return Err(ApiError::new(
+                            StatusCode::PRECONDITION_FAILED,
+                            format!("Conflicting expected versions for account {account_id}"),
+                        ));
+                    },
+                    _ => *expected_version = Some(version),
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -64,131 +182,982 @@ pub struct CreatedTransaction {
     pub transaction_id: Uuid,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Transaction {
     pub created_at: DateTime<Utc>,
     pub transaction_id: Uuid,
+    pub ledger_id: Uuid,
     pub movement_type: MovementType,
     pub lhs_account_id: Uuid,
     pub rhs_account_id: Uuid,
     pub description: String,
     pub amount_in_cents: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverses_transaction_id: Option<Uuid>,
+}
+
+// Lowercased alphanumeric words, as indexed for description search
+pub fn words(text: &str) -> BTreeSet<String> {
+    text.split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TransactionFilter {
+    pub ledger_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub min_amount: Option<u64>,
+    pub max_amount: Option<u64>,
+    pub from: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    // Every word must prefix a word of the description, case-insensitively
+    pub search: Option<String>,
+    pub status: Option<TransactionStatus>,
+}
+
+// Reversed transactions stay listed, next to the reversals that undid them
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionStatus {
+    Posted,
+    Reversed,
+}
+
+impl TransactionFilter {
+    fn matches(&self, transaction: &Transaction) -> bool {
+        transaction.ledger_id == self.ledger_id
+            && self.account_id.is_none_or(|account_id| {
+                transaction.lhs_account_id == account_id || transaction.rhs_account_id == account_id
+            })
+            && self.min_amount.is_none_or(|min| transaction.amount_in_cents >= min)
+            && self.max_amount.is_none_or(|max| transaction.amount_in_cents <= max)
+            && self.from.is_none_or(|from| transaction.created_at >= from)
+            && self.until.is_none_or(|until| transaction.created_at < until)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+pub enum TransactionSort {
+    #[serde(rename = "created_at")]
+    CreatedAt,
+    #[default]
+    #[serde(rename = "-created_at")]
+    CreatedAtDescending,
+    #[serde(rename = "amount")]
+    Amount,
+    #[serde(rename = "-amount")]
+    AmountDescending,
+}
+
+impl TransactionSort {
+    fn is_descending(&self) -> bool {
+        matches!(
+            self,
+            TransactionSort::CreatedAtDescending | TransactionSort::AmountDescending
+        )
+    }
+
+    // Formats the sort key of a transaction for keyset cursors
+    fn key_of(&self, transaction: &Transaction) -> String {
+        match self {
+            TransactionSort::CreatedAt | TransactionSort::CreatedAtDescending => {
+                transaction.created_at.to_rfc3339_opts(SecondsFormat::Nanos, true)
+            },
+            TransactionSort::Amount | TransactionSort::AmountDescending => transaction.amount_in_cents.to_string(),
+        }
+    }
+
+    fn parse_key(&self, key: &str) -> Option<SortKey> {
+        match self {
+            TransactionSort::CreatedAt | TransactionSort::CreatedAtDescending => {
+                let created_at = DateTime::parse_from_rfc3339(key).ok()?;
+                Some(SortKey::CreatedAt(created_at.with_timezone(&Utc)))
+            },
+            TransactionSort::Amount | TransactionSort::AmountDescending => Some(SortKey::Amount(key.parse().ok()?)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SortKey {
+    CreatedAt(DateTime<Utc>),
+    Amount(u64),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TransactionsPage {
+    pub transactions: Vec<Transaction>,
+    // Only counted for the first page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    pub next_cursor: Option<String>,
+}
+
+// Transaction positions by creation time and by amount, ties broken by position
+#[derive(Default)]
+struct SortedPositions {
+    by_time: BTreeSet<(DateTime<Utc>, usize)>,
+    by_amount: BTreeSet<(u64, usize)>,
+}
+
+impl SortedPositions {
+    fn insert(&mut self, transaction: &Transaction, position: usize) {
+        self.by_time.insert((transaction.created_at, position));
+        self.by_amount.insert((transaction.amount_in_cents, position));
+    }
+}
+
+// Walks an index in key order from `start`, or backwards from `start`, while keys stay `within`
+fn walk<'a, K: Ord + Copy + 'a>(
+    index: &'a BTreeSet<(K, usize)>,
+    descending: bool,
+    start: Bound<(K, usize)>,
+    within: impl Fn(K) -> bool + 'a,
+) -> Box<dyn Iterator<Item = usize> + 'a> {
+    let positions = move |(key, position): &(K, usize)| within(*key).then_some(*position);
+
+    match descending {
+        false => Box::new(index.range((start, Bound::Unbounded)).map_while(positions)),
+        true => Box::new(index.range((Bound::Unbounded, start)).rev().map_while(positions)),
+    }
 }
 
 #[derive(Default)]
 pub struct TransactionsRepository {
     transactions: Vec<Transaction>,
+    by_id: HashMap<Uuid, usize>,
+    sorted: SortedPositions,
+    by_account: HashMap<Uuid, SortedPositions>,
+    by_word: BTreeMap<String, Vec<usize>>,
+    reversed_by: HashMap<Uuid, Uuid>,
 }
 
 impl TransactionsRepository {
-    fn save_transaction(&mut self, transaction: Transaction) {
+    pub fn save_transaction(&mut self, transaction: Transaction) {
+        let position = self.transactions.len();
+        self.by_id.insert(transaction.transaction_id, position);
+        self.sorted.insert(&transaction, position);
+
+        for account_id in BTreeSet::from([transaction.lhs_account_id, transaction.rhs_account_id]) {
+            self.by_account
+                .entry(account_id)
+                .or_default()
+                .insert(&transaction, position);
+        }
+
+        for word in words(&transaction.description) {
+            self.by_word.entry(word).or_default().push(position);
+        }
+
+        if let Some(reversed_id) = transaction.reverses_transaction_id {
+            self.reversed_by.insert(reversed_id, transaction.transaction_id);
+        }
+
         self.transactions.push(transaction);
     }
 
-    fn fetch_transaction(&self, id: &Uuid) -> Option<&Transaction> {
-        self.transactions.iter().find(|tx| tx.transaction_id == *id)
+    // Positions of transactions whose description has words starting with every searched word
+    fn search(&self, text: &str) -> BTreeSet<usize> {
+        let mut found: Option<BTreeSet<usize>> = None;
+
+        for searched in words(text) {
+            let positions: BTreeSet<usize> = self
+                .by_word
+                .range(searched.clone()..)
+                .take_while(|(word, _)| word.starts_with(&searched))
+                .flat_map(|(_, positions)| positions.iter().copied())
+                .collect();
+
+            found = Some(match found {
+                None => positions,
+                Some(found) => found.intersection(&positions).copied().collect(),
+            });
+        }
+
+        found.unwrap_or_default()
+    }
+
+    // Positions in sort order, after the cursor or else from the filter bounds
+    fn sorted_positions<'a>(
+        &'a self,
+        filter: &TransactionFilter,
+        sort: TransactionSort,
+        after: Option<(SortKey, usize)>,
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let index = match filter.account_id {
+            Some(account_id) => match self.by_account.get(&account_id) {
+                Some(index) => index,
+                None => return Box::new(std::iter::empty()),
+            },
+            None => &self.sorted,
+        };
+
+        let descending = sort.is_descending();
+        let (from, until) = (filter.from, filter.until);
+        let (min_amount, max_amount) = (filter.min_amount, filter.max_amount);
+
+        let within_period = move |created_at| {
+            from.is_none_or(|from| created_at >= from) && until.is_none_or(|until| created_at < until)
+        };
+        let within_amounts =
+            move |amount| min_amount.is_none_or(|min| amount >= min) && max_amount.is_none_or(|max| amount <= max);
+
+        match after {
+            Some((SortKey::CreatedAt(created_at), position)) => walk(
+                &index.by_time,
+                descending,
+                Bound::Excluded((created_at, position)),
+                within_period,
+            ),
+            Some((SortKey::Amount(amount), position)) => walk(
+                &index.by_amount,
+                descending,
+                Bound::Excluded((amount, position)),
+                within_amounts,
+            ),
+            None if matches!(sort, TransactionSort::CreatedAt | TransactionSort::CreatedAtDescending) => {
+                let start = match (descending, from, until) {
+                    (false, Some(from), _) => Bound::Included((from, 0)),
+                    (true, _, Some(until)) => Bound::Excluded((until, 0)),
+                    _ => Bound::Unbounded,
+                };
+
+                walk(&index.by_time, descending, start, within_period)
+            },
+            None => {
+                let start = match (descending, min_amount, max_amount) {
+                    (false, Some(min), _) => Bound::Included((min, 0)),
+                    (true, _, Some(max)) => Bound::Included((max, usize::MAX)),
+                    _ => Bound::Unbounded,
+                };
+
+                walk(&index.by_amount, descending, start, within_amounts)
+            },
+        }
+    }
+
+    // Walks the index of the sort order, narrowed to the account when filtering on one, and
+    // stops once the page is full. Only the first page goes on to count every match.
+    pub fn list(
+        &self,
+        filter: &TransactionFilter,
+        sort: TransactionSort,
+        after: Option<&KeysetCursor>,
+        limit: usize,
+        is_visible: impl Fn(&Transaction) -> bool,
+    ) -> Result<TransactionsPage, ApiError> {
+        let after_key = match after {
+            None => None,
+            Some(cursor) => {
+                let key = sort
+                    .parse_key(&cursor.key)
+                    .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "Invalid cursor"))?;
+                Some((key, cursor.position))
+            },
+        };
+
+        let sorted = self.sorted_positions(filter, sort, after_key);
+        let searched = filter.search.as_ref().map(|text| self.search(text));
+
+        let mut matching = sorted
+            .filter(|position| searched.as_ref().is_none_or(|searched| searched.contains(position)))
+            .filter(|position| {
+                let transaction = &self.transactions[*position];
+                filter.matches(transaction)
+                    && filter.status.is_none_or(|status| self.status_of(transaction) == status)
+                    && is_visible(transaction)
+            });
+
+        let positions: Vec<usize> = matching.by_ref().take(limit).collect();
+        let has_more = matching.next().is_some();
+
+        let total = after
+            .is_none()
+            .then(|| positions.len() + usize::from(has_more) + matching.count());
+
+        let next_cursor = match positions.last() {
+            Some(last) if has_more => Some(
+                KeysetCursor {
+                    key: sort.key_of(&self.transactions[*last]),
+                    position: *last,
+                }
+                .encode(),
+            ),
+            _ => None,
+        };
+
+        Ok(TransactionsPage {
+            transactions: positions
+                .into_iter()
+                .map(|position| self.transactions[position].clone())
+                .collect(),
+            total,
+            next_cursor,
+        })
+    }
+
+    // Up to `limit` matching transactions at the given positions, in append order, and where
+    // to continue from when more transactions match. Descriptions are not searched.
+    pub fn transactions_within(
+        &self,
+        filter: &TransactionFilter,
+        positions: Range<usize>,
+        limit: usize,
+        is_visible: impl Fn(&Transaction) -> bool,
+    ) -> (Vec<Transaction>, Option<usize>) {
+        let end = positions.end.min(self.transactions.len());
+        let start = positions.start.min(end);
+
+        let mut matching = (start..end).filter(|position| {
+            let transaction = &self.transactions[*position];
+            filter.matches(transaction) && is_visible(transaction)
+        });
+
+        let positions: Vec<usize> = matching.by_ref().take(limit).collect();
+        let has_more = matching.next().is_some();
+
+        let next_position = match positions.last() {
+            Some(last) if has_more => Some(last + 1),
+            _ => None,
+        };
+
+        let transactions = positions
+            .into_iter()
+            .map(|position| self.transactions[position].clone())
+            .collect();
+
+        (transactions, next_position)
+    }
+
+    pub fn count(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn fetch_transaction(&self, id: &Uuid) -> Option<&Transaction> {
+        self.by_id.get(id).map(|position| &self.transactions[*position])
+    }
+
+    pub fn reversal_of(&self, id: &Uuid) -> Option<Uuid> {
+        self.reversed_by.get(id).copied()
+    }
+
+    fn status_of(&self, transaction: &Transaction) -> TransactionStatus {
+        match self.reversed_by.contains_key(&transaction.transaction_id) {
+            true => TransactionStatus::Reversed,
+            false => TransactionStatus::Posted,
+        }
+    }
+
+    // Transactions of an account created within [from, until), oldest first
+    pub fn for_account(
+        &self,
+        account_id: &Uuid,
+        from: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> impl Iterator<Item = &Transaction> {
+        let start = from.map_or(Bound::Unbounded, |from| Bound::Included((from, 0)));
+
+        self.by_account
+            .get(account_id)
+            .into_iter()
+            .flat_map(move |index| index.by_time.range((start, Bound::Unbounded)))
+            .take_while(move |(created_at, _)| until.is_none_or(|until| *created_at < until))
+            .map(|(_, position)| &self.transactions[*position])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Transaction> {
+        self.transactions.iter()
     }
 }
 
-pub async fn new_transaction(
-    State(state): State<SharedState>,
-    Json(payload): Json<CreateNewTransaction>,
-) -> Result<Json<CreatedTransaction>, StatusCode> {
-    let mut repos = state.write().expect("Cannot acquire shared state");
+#[derive(Debug, Deserialize)]
+pub struct TransactionPath {
+    pub transaction_id: Uuid,
+}
+
+// A posting applied to locked accounts, not yet stored in transactions and journal
+struct StagedPosting {
+    transaction: Transaction,
+    events: Vec<EventPayload>,
+}
 
-    let lhs_account_id = &payload.lhs_account_id;
-    let rhs_account_id = &payload.rhs_account_id;
+// Validates and applies a posting to already locked accounts, failed postings change nothing
+fn stage_posting(
+    accounts: &mut LockedAccounts,
+    ledger_id: Uuid,
+    payload: CreateNewTransaction,
+) -> Result<StagedPosting, ApiError> {
+    let lhs_account_id = payload.lhs_account_id;
+    let rhs_account_id = payload.rhs_account_id;
 
     // Validate existing accounts
-    let Some(lhs_account) = repos.accounts.fetch_by_id(lhs_account_id) else {
-        tracing::debug!("Account not found -> account_id = {:?}", lhs_account_id);
-        return Err(StatusCode::NOT_FOUND);
-    };
+    for account_id in [lhs_account_id, rhs_account_id] {
+        if accounts.find(&account_id).is_none() {
+            tracing::debug!(%account_id, "Account not found");
+            METRICS.record_posting(PostingOutcome::AccountNotFound);
+            return Err(ApiError::not_found(format!("Account {account_id} not found")));
+        }
+    }
 
-    let Some(rhs_account) = repos.accounts.fetch_by_id(rhs_account_id) else {
-        tracing::debug!("Account not found -> account_id = {:?}", rhs_account_id);
-        return Err(StatusCode::NOT_FOUND);
-    };
+    let lhs_account = accounts.get(&lhs_account_id);
+    let rhs_account = accounts.get(&rhs_account_id);
+
+    // Validate both accounts belong to this ledger
+    if lhs_account.ledger_id != ledger_id || rhs_account.ledger_id != ledger_id {
+        tracing::debug!(%ledger_id, %lhs_account_id, %rhs_account_id, "Transaction crosses ledgers");
+        METRICS.record_posting(PostingOutcome::CrossLedger);
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Transaction crosses ledgers, accounts must belong to ledger {ledger_id}"),
+        ));
+    }
+
+    // Validate both accounts hold the same currency
+    if lhs_account.currency != rhs_account.currency {
+        tracing::debug!(%lhs_account_id, %rhs_account_id, "Transaction mixes currencies");
+        METRICS.record_posting(PostingOutcome::CurrencyMismatch);
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "Transaction mixes currencies, {} and {}",
+                lhs_account.currency, rhs_account.currency
+            ),
+        ));
+    }
+
+    // Validate accounts didn't change since the client last saw them
+    let expectations = [
+        (lhs_account, payload.lhs_expected_version),
+        (rhs_account, payload.rhs_expected_version),
+    ];
+
+    for (account, expected_version) in expectations {
+        match expected_version {
+            Some(expected_version) if expected_version != account.version => {
+                tracing::debug!(account_id = %account.account_id, expected_version, "Account version moved");
+                METRICS.record_posting(PostingOutcome::VersionMismatch);
+                return Err(ApiError::new(
+                    StatusCode::PRECONDITION_FAILED,
+                    format!(
+                        "Account {} is at version {}, expected version {expected_version}",
+                        account.account_id, account.version
+                    ),
+                ));
+            },
+            _ => {},
+        }
+    }
 
     let amount_to_move = payload.amount_in_cents;
 
-    // Validate sufficient balance and update
-    match &payload.movement_type {
-        MovementType::Debit => {
-            if lhs_account.balance < amount_to_move {
-                tracing::debug!("Insufficient balance -> account_id = {:?}", lhs_account_id);
-                return Err(StatusCode::CONFLICT);
-            } else {
-                lhs_account.subtract_balance(amount_to_move);
-                rhs_account.add_balance(amount_to_move)
-            }
-        },
-        MovementType::Credit => {
-            if rhs_account.balance < amount_to_move {
-                tracing::debug!("Insufficient balance -> account_id = {:?}", rhs_account);
-                return Err(StatusCode::CONFLICT);
-            } else {
-                rhs_account.subtract_balance(amount_to_move);
-                lhs_account.add_balance(amount_to_move)
-            }
-        },
+    // Validate sufficient balance, only opening balances may take equity below zero
+    let (source_account, target_account) = match &payload.movement_type {
+        MovementType::Debit => (lhs_account, rhs_account),
+        MovementType::Credit => (rhs_account, lhs_account),
+    };
+
+    let draws_from_equity = payload.from_equity && source_account.account_type == AccountType::Equity;
+    let remaining = source_account.balance.checked_sub_unsigned(amount_to_move);
+
+    if !remaining.is_some_and(|remaining| remaining >= 0 || draws_from_equity) {
+        tracing::debug!(account_id = %source_account.account_id, "Insufficient balance");
+        METRICS.record_posting(PostingOutcome::InsufficientBalance);
+        return Err(ApiError::conflict(format!(
+            "Insufficient balance on account {}",
+            source_account.account_id
+        )));
     }
 
-    // Create a transaction record
-    let transaction_id = Uuid::new_v4();
-    let created_at = Utc::now();
+    // Validate the credited balance fits, a transfer to the same account moves nothing
+    if source_account.account_id != target_account.account_id
+        && target_account.balance.checked_add_unsigned(amount_to_move).is_none()
+    {
+        tracing::debug!(account_id = %target_account.account_id, "Balance overflow");
+        METRICS.record_posting(PostingOutcome::BalanceOverflow);
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Balance of account {} would overflow", target_account.account_id),
+        ));
+    }
+
+    let source_account_id = source_account.account_id;
+    let target_account_id = target_account.account_id;
 
+    // Update balances
+    accounts.get_mut(&source_account_id).subtract_balance(amount_to_move);
+    accounts.get_mut(&target_account_id).add_balance(amount_to_move);
+
+    // Create a transaction record
     let tx = Transaction {
-        transaction_id,
-        created_at,
+        transaction_id: Uuid::new_v4(),
+        ledger_id,
+        created_at: payload.booked_at.unwrap_or_else(Utc::now),
         movement_type: payload.movement_type,
         lhs_account_id: payload.lhs_account_id,
         rhs_account_id: payload.rhs_account_id,
         description: payload.description,
         amount_in_cents: payload.amount_in_cents,
+        reverses_transaction_id: payload.reverses,
     };
 
-    // Create double-entries
+    let balance_changed = |account: &Account| EventPayload::BalanceChanged {
+        account_id: account.account_id,
+        transaction_id: tx.transaction_id,
+        balance: account.balance,
+        version: account.version,
+    };
+
+    let posted = match tx.reverses_transaction_id {
+        Some(reversed_transaction_id) => EventPayload::TransactionReversed {
+            reversed_transaction_id,
+            transaction: tx.clone(),
+        },
+        None => EventPayload::TransactionPosted {
+            transaction: tx.clone(),
+        },
+    };
+
+    let events = vec![
+        posted,
+        balance_changed(accounts.get(&tx.lhs_account_id)),
+        balance_changed(accounts.get(&tx.rhs_account_id)),
+    ];
+
+    Ok(StagedPosting {
+        transaction: tx,
+        events,
+    })
+}
+
+fn double_entries(tx: &Transaction) -> [JournalEntry; 2] {
     let left_entry = JournalEntry {
-        created_at: Utc::now(),
+        created_at: tx.created_at,
         entry_id: Uuid::new_v4(),
         transaction_id: tx.transaction_id,
+        ledger_id: tx.ledger_id,
         account_id: tx.lhs_account_id,
         movement_type: tx.movement_type,
         amount_in_cents: tx.amount_in_cents,
+        previous_hash: String::new(),
+        hash: String::new(),
     };
 
     let right_entry = JournalEntry {
+        entry_id: Uuid::new_v4(),
         account_id: tx.rhs_account_id,
         movement_type: tx.movement_type.opposite(),
         ..left_entry.clone()
     };
 
-    // Store results
-    repos.journal.save_entries(vec![left_entry, right_entry]);
-    repos.transactions.save_transaction(tx);
+    [left_entry, right_entry]
+}
+
+// Must be called while still holding the accounts, so balances and journal move together
+fn commit_postings(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    postings: Vec<StagedPosting>,
+    mut events: Vec<EventPayload>,
+) -> Vec<Transaction> {
+    // Entries and copies are prepared first, the shared locks only cover appending them
+    let entries: Vec<PreparedEntry> = postings
+        .iter()
+        .flat_map(|posting| double_entries(&posting.transaction))
+        .map(PreparedEntry::from)
+        .collect();
+    let saved: Vec<Transaction> = postings.iter().map(|posting| posting.transaction.clone()).collect();
+
+    events.reserve(postings.len() * 3);
+    let mut committed = Vec::with_capacity(postings.len());
+
+    for posting in postings {
+        events.extend(posting.events);
+        committed.push(posting.transaction);
+    }
 
-    // Return status
-    let tx = CreatedTransaction {
-        created_at,
-        transaction_id,
+    {
+        let mut transactions = crate::write_lock(&repos.transactions);
+        let mut journal = crate::write_lock(&repos.journal);
+        journal.append_prepared(entries);
+
+        for transaction in saved {
+            transactions.save_transaction(transaction);
+        }
+
+        // The outbox is appended with the journal, so a committed posting always has its events
+        repos.record_events(ledger_id, events);
+    }
+
+    for transaction in &committed {
+        tracing::debug!(transaction_id = %transaction.transaction_id, "Transaction created");
+        METRICS.record_posting(PostingOutcome::Created);
+    }
+
+    committed
+}
+
+pub fn post_transaction(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    payload: CreateNewTransaction,
+) -> Result<Transaction, ApiError> {
+    // Lock only the accounts of this transaction
+    let mut accounts = match repos
+        .accounts
+        .lock_for_posting(&[payload.lhs_account_id, payload.rhs_account_id])
+    {
+        Ok(locked) => locked,
+        Err(account_id) => {
+            tracing::debug!(%account_id, "Account not found");
+            METRICS.record_posting(PostingOutcome::AccountNotFound);
+            return Err(ApiError::not_found(format!("Account {account_id} not found")));
+        },
     };
 
-    tracing::debug!("Transaction created -> {:?}", tx);
-    Ok(Json(tx))
+    // Both accounts of a reversal are locked, so it can't race another reversal of the same transaction
+    if let Some(reversed_id) = payload.reverses {
+        if let Some(reversal_id) = crate::read_lock(&repos.transactions).reversal_of(&reversed_id) {
+            tracing::debug!(%reversed_id, %reversal_id, "Transaction already reversed");
+            METRICS.record_posting(PostingOutcome::AlreadyReversed);
+            return Err(ApiError::conflict(format!(
+                "Transaction {reversed_id} is already reversed by {reversal_id}"
+            )));
+        }
+    }
+
+    let posting = stage_posting(&mut accounts, ledger_id, payload)?;
+    let mut committed = commit_postings(repos, ledger_id, vec![posting], Vec::new());
+    Ok(committed.remove(0))
+}
+
+pub async fn new_transaction(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Extension(principal): Extension<Principal>,
+    headers: HeaderMap,
+    Json(mut payload): Json<CreateNewTransaction>,
+) -> Result<Json<CreatedTransaction>, ApiError> {
+    check_source_ownership(&principal, std::slice::from_ref(&payload))?;
+    payload.apply_if_match(&headers)?;
+    let tx = post_transaction(&state, ledger_id, payload)?;
+
+    Ok(Json(CreatedTransaction {
+        created_at: tx.created_at,
+        transaction_id: tx.transaction_id,
+    }))
+}
+
+// Restricted principals never move money out of accounts they don't own
+fn check_source_ownership(principal: &Principal, payloads: &[CreateNewTransaction]) -> Result<(), ApiError> {
+    match payloads
+        .iter()
+        .find(|payload| !principal.owns(&payload.source_account_id()))
+    {
+        Some(payload) => Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!(
+                "Not allowed to move money out of account {}",
+                payload.source_account_id()
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+pub const MAX_BATCH_SIZE: usize = 5000;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateTransactionsBatch {
+    // Atomic batches are applied entirely or not at all
+    #[serde(default = "atomic_by_default")]
+    pub atomic: bool,
+    pub transactions: Vec<CreateNewTransaction>,
+}
+
+fn atomic_by_default() -> bool {
+    true
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemOutcome {
+    Created,
+    Failed,
+    RolledBack,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub outcome: BatchItemOutcome,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<CreatedTransaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BatchReport {
+    pub atomic: bool,
+    pub created: usize,
+    pub failed: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+// Locks every account of the batch once and stores all postings under a single journal lock.
+// Items are applied in order, each one seeing the balances and versions left by the previous ones.
+pub fn post_batch(repos: &Repositories, ledger_id: Uuid, batch: CreateTransactionsBatch) -> BatchReport {
+    post_batch_announcing(repos, ledger_id, batch, Vec::new())
+}
+
+// Registers new accounts and posts an atomic batch over them, all or nothing. The accounts are
+// announced right before the postings once committed, and removed again when the batch fails.
+pub fn post_batch_with_accounts(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    new_accounts: Vec<Account>,
+    transactions: Vec<CreateNewTransaction>,
+) -> Result<BatchReport, ApiError> {
+    let account_ids: Vec<Uuid> = new_accounts.iter().map(|account| account.account_id).collect();
+
+    repos
+        .accounts
+        .save_accounts(new_accounts.clone())
+        .map_err(|error| ApiError::conflict(error.to_string()))?;
+
+    let announced = new_accounts
+        .into_iter()
+        .map(|account| EventPayload::AccountCreated { account })
+        .collect();
+
+    let batch = CreateTransactionsBatch {
+        atomic: true,
+        transactions,
+    };
+
+    let report = post_batch_announcing(repos, ledger_id, batch, announced);
+
+    if report.failed > 0 {
+        repos.accounts.remove_accounts(&account_ids);
+    }
+
+    Ok(report)
+}
+
+fn post_batch_announcing(
+    repos: &Repositories,
+    ledger_id: Uuid,
+    batch: CreateTransactionsBatch,
+    announced: Vec<EventPayload>,
+) -> BatchReport {
+    // Accounts are only removed before anybody was told about them, so unknown ones fail per item
+    let account_ids: Vec<Uuid> = batch
+        .transactions
+        .iter()
+        .flat_map(|payload| [payload.lhs_account_id, payload.rhs_account_id])
+        .filter(|account_id| repos.accounts.ledger_of(account_id).is_some())
+        .collect();
+
+    let mut accounts = repos
+        .accounts
+        .lock_for_posting(&account_ids)
+        .expect("Known accounts can always be locked");
+
+    let snapshot = batch.atomic.then(|| accounts.snapshot());
+    let mut staged = Vec::with_capacity(batch.transactions.len());
+    let mut failures = Vec::new();
+
+    for (index, payload) in batch.transactions.into_iter().enumerate() {
+        match stage_posting(&mut accounts, ledger_id, payload) {
+            Ok(posting) => staged.push((index, posting)),
+            Err(error) => failures.push((index, error)),
+        }
+    }
+
+    let mut results: Vec<BatchItemResult> = failures
+        .into_iter()
+        .map(|(index, error)| BatchItemResult {
+            index,
+            outcome: BatchItemOutcome::Failed,
+            status: error.status().as_u16(),
+            transaction: None,
+            error: Some(error.message().to_string()),
+        })
+        .collect();
+
+    let failed = results.len();
+    let mut created = 0;
+
+    if let Some(snapshot) = snapshot.filter(|_| failed > 0) {
+        accounts.restore(snapshot);
+
+        results.extend(staged.into_iter().map(|(index, _)| {
+            METRICS.record_posting(PostingOutcome::RolledBack);
+            BatchItemResult {
+                index,
+                outcome: BatchItemOutcome::RolledBack,
+                status: StatusCode::FAILED_DEPENDENCY.as_u16(),
+                transaction: None,
+                error: Some("Rolled back, another transaction of this batch failed".to_string()),
+            }
+        }));
+    } else {
+        let (indexes, postings): (Vec<usize>, Vec<StagedPosting>) = staged.into_iter().unzip();
+        let committed = commit_postings(repos, ledger_id, postings, announced);
+        created = committed.len();
+
+        results.extend(indexes.into_iter().zip(committed).map(|(index, tx)| BatchItemResult {
+            index,
+            outcome: BatchItemOutcome::Created,
+            status: StatusCode::OK.as_u16(),
+            transaction: Some(CreatedTransaction {
+                created_at: tx.created_at,
+                transaction_id: tx.transaction_id,
+            }),
+            error: None,
+        }));
+    }
+
+    results.sort_by_key(|result| result.index);
+
+    BatchReport {
+        atomic: batch.atomic,
+        created,
+        failed,
+        results,
+    }
+}
+
+pub async fn new_transactions_batch(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Extension(principal): Extension<Principal>,
+    Json(batch): Json<CreateTransactionsBatch>,
+) -> Result<(StatusCode, Json<BatchReport>), ApiError> {
+    check_source_ownership(&principal, &batch.transactions)?;
+
+    if batch.transactions.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Batches are limited to {MAX_BATCH_SIZE} transactions"),
+        ));
+    }
+
+    let report = post_batch(&state, ledger_id, batch);
+    tracing::debug!(
+        atomic = report.atomic,
+        created = report.created,
+        failed = report.failed,
+        "Transactions batch posted"
+    );
+
+    // Atomic batches with failures applied nothing
+    let status = if report.atomic && report.failed > 0 {
+        StatusCode::UNPROCESSABLE_ENTITY
+    } else {
+        StatusCode::OK
+    };
+
+    Ok((status, Json(report)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionsQuery {
+    account_id: Option<Uuid>,
+    min_amount: Option<u64>,
+    max_amount: Option<u64>,
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    q: Option<String>,
+    status: Option<TransactionStatus>,
+    #[serde(default)]
+    sort: TransactionSort,
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+pub async fn list_transactions(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Query(query): Query<TransactionsQuery>,
+) -> Result<Json<TransactionsPage>, ApiError> {
+    let cursor = pagination::parse_keyset_cursor(query.cursor.as_deref())?;
+    let limit = pagination::page_size(query.limit)?;
+
+    let filter = TransactionFilter {
+        ledger_id,
+        account_id: query.account_id,
+        min_amount: query.min_amount,
+        max_amount: query.max_amount,
+        from: query.from,
+        until: query.until,
+        search: query.q.filter(|text| !words(text).is_empty()),
+        status: query.status,
+    };
+
+    let page = crate::read_lock(&state.transactions).list(&filter, query.sort, cursor.as_ref(), limit, |tx| {
+        principal.owns(&tx.lhs_account_id) || principal.owns(&tx.rhs_account_id)
+    })?;
+
+    tracing::debug!(?filter, total = page.total, "Transactions listed");
+    Ok(Json(page))
+}
+
+// Reversals can't be reversed themselves, a transaction is posted again instead
+pub async fn reverse_transaction(
+    State(state): State<SharedState>,
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(TransactionPath { transaction_id }): Path<TransactionPath>,
+) -> Result<Json<CreatedTransaction>, ApiError> {
+    let existing = crate::read_lock(&state.transactions)
+        .fetch_transaction(&transaction_id)
+        .filter(|tx| tx.ledger_id == ledger_id)
+        .filter(|tx| principal.owns(&tx.lhs_account_id) || principal.owns(&tx.rhs_account_id))
+        .cloned();
+
+    let Some(transaction) = existing else {
+        tracing::debug!(%transaction_id, "Transaction not found");
+        return Err(ApiError::not_found(format!("Transaction {transaction_id} not found")));
+    };
+
+    if let Some(reversed_id) = transaction.reverses_transaction_id {
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Transaction {transaction_id} reverses {reversed_id}, it can't be reversed"),
+        ));
+    }
+
+    let reversal = CreateNewTransaction::reversal_of(&transaction);
+    check_source_ownership(&principal, std::slice::from_ref(&reversal))?;
+    let tx = post_transaction(&state, ledger_id, reversal)?;
+
+    Ok(Json(CreatedTransaction {
+        created_at: tx.created_at,
+        transaction_id: tx.transaction_id,
+    }))
 }
 
 pub async fn transaction_details(
     State(state): State<SharedState>,
-    Path(transaction_id): Path<Uuid>,
-) -> Result<Json<Transaction>, StatusCode> {
-    let repos = &state.read().expect("Cannot acquire shared state");
+    Extension(principal): Extension<Principal>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(TransactionPath { transaction_id }): Path<TransactionPath>,
+) -> Result<Json<Transaction>, ApiError> {
+    let transactions = crate::read_lock(&state.transactions);
 
-    let existing = repos.transactions.fetch_transaction(&transaction_id);
+    let existing = transactions
+        .fetch_transaction(&transaction_id)
+        .filter(|tx| tx.ledger_id == ledger_id)
+        .filter(|tx| principal.owns(&tx.lhs_account_id) || principal.owns(&tx.rhs_account_id));
 
     let Some(account) = existing.cloned() else {
-        tracing::debug!("Not found -> account_id = {:?}", &transaction_id);
-        return Err(StatusCode::NOT_FOUND);
+        tracing::debug!(%transaction_id, "Transaction not found");
+        return Err(ApiError::not_found(format!("Transaction {transaction_id} not found")));
     };
 
     Ok(Json(account))
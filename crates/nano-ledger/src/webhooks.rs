@@ -0,0 +1,589 @@
+// Copyright 2025 Dotanuki Labs
+// SPDX-License-Identifier: MIT
+
+use crate::errors::ApiError;
+use crate::events::{EVENT_TYPES, LedgerEvent};
+use crate::ledgers::LedgerScope;
+use crate::{AppState, Repositories, SharedState};
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+pub const EVENT_HEADER: &str = "x-webhook-event";
+pub const DELIVERY_HEADER: &str = "x-webhook-delivery";
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_LOGGED_ATTEMPTS: usize = 1000;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateNewWebhook {
+    pub url: String,
+    pub event_types: BTreeSet<String>,
+    pub secret: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Webhook {
+    pub webhook_id: Uuid,
+    pub ledger_id: Uuid,
+    pub url: String,
+    pub event_types: BTreeSet<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip)]
+    secret: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DeliveryAttempt {
+    pub delivery_id: Uuid,
+    pub event_sequence: u64,
+    pub event_type: String,
+    pub attempt: u32,
+    pub attempted_at: DateTime<Utc>,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub delivered: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DeadLetter {
+    pub delivery_id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: LedgerEvent,
+    pub attempts: u32,
+    pub last_error: String,
+    pub dead_lettered_at: DateTime<Utc>,
+}
+
+// A delivery tracked by the outbox until it succeeds or gets dead-lettered
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PendingDelivery {
+    pub webhook_id: Uuid,
+    pub delivery_id: Uuid,
+    pub attempts: u32,
+    pub retry_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl PendingDelivery {
+    pub fn new(webhook_id: Uuid) -> Self {
+        PendingDelivery {
+            webhook_id,
+            delivery_id: Uuid::new_v4(),
+            attempts: 0,
+            retry_at: Utc::now(),
+            last_error: None,
+        }
+    }
+}
+
+// Signs `<timestamp>.<body>`, so a captured payload cannot be replayed with another timestamp
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[derive(Default)]
+pub struct WebhooksRepository {
+    webhooks: Vec<Webhook>,
+    attempts: HashMap<Uuid, VecDeque<DeliveryAttempt>>,
+    dead_letters: HashMap<Uuid, Vec<DeadLetter>>,
+}
+
+impl WebhooksRepository {
+    pub fn save_webhook(&mut self, webhook: Webhook) {
+        self.webhooks.push(webhook);
+    }
+
+    fn find(&self, webhook_id: &Uuid) -> Option<&Webhook> {
+        self.webhooks.iter().find(|webhook| webhook.webhook_id == *webhook_id)
+    }
+
+    pub fn fetch_webhook(&self, ledger_id: &Uuid, webhook_id: &Uuid) -> Option<Webhook> {
+        self.find(webhook_id)
+            .filter(|webhook| webhook.ledger_id == *ledger_id)
+            .cloned()
+    }
+
+    pub fn list(&self, ledger_id: &Uuid) -> Vec<Webhook> {
+        self.webhooks
+            .iter()
+            .filter(|webhook| webhook.ledger_id == *ledger_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn delete(&mut self, ledger_id: &Uuid, webhook_id: &Uuid) -> bool {
+        let before = self.webhooks.len();
+        self.webhooks
+            .retain(|webhook| !(webhook.webhook_id == *webhook_id && webhook.ledger_id == *ledger_id));
+
+        let deleted = self.webhooks.len() < before;
+
+        if deleted {
+            self.attempts.remove(webhook_id);
+            self.dead_letters.remove(webhook_id);
+        }
+
+        deleted
+    }
+
+    pub fn subscribed_to(&self, event: &LedgerEvent) -> Vec<Uuid> {
+        self.webhooks
+            .iter()
+            .filter(|webhook| webhook.ledger_id == event.ledger_id)
+            .filter(|webhook| webhook.event_types.contains(event.payload.name()))
+            .map(|webhook| webhook.webhook_id)
+            .collect()
+    }
+
+    fn record_attempt(&mut self, webhook_id: Uuid, attempt: DeliveryAttempt) {
+        if self.find(&webhook_id).is_none() {
+            return;
+        }
+
+        let attempts = self.attempts.entry(webhook_id).or_default();
+
+        if attempts.len() == MAX_LOGGED_ATTEMPTS {
+            attempts.pop_front();
+        }
+
+        attempts.push_back(attempt);
+    }
+
+    pub fn attempts(&self, webhook_id: &Uuid) -> Vec<DeliveryAttempt> {
+        self.attempts
+            .get(webhook_id)
+            .map(|attempts| attempts.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn dead_letter(&mut self, dead_letter: DeadLetter) {
+        if self.find(&dead_letter.webhook_id).is_some() {
+            self.dead_letters
+                .entry(dead_letter.webhook_id)
+                .or_default()
+                .push(dead_letter);
+        }
+    }
+
+    pub fn dead_letters(&self, webhook_id: &Uuid) -> Vec<DeadLetter> {
+        self.dead_letters.get(webhook_id).cloned().unwrap_or_default()
+    }
+
+    fn take_dead_letter(&mut self, webhook_id: &Uuid, delivery_id: &Uuid) -> Option<DeadLetter> {
+        let dead_letters = self.dead_letters.get_mut(webhook_id)?;
+        let position = dead_letters
+            .iter()
+            .position(|dead_letter| dead_letter.delivery_id == *delivery_id)?;
+        Some(dead_letters.remove(position))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 8,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let defaults = RetryPolicy::default();
+        let read = |variable| std::env::var(variable).ok().and_then(|value| value.parse::<u64>().ok());
+
+        RetryPolicy {
+            max_attempts: read("WEBHOOK_MAX_ATTEMPTS")
+                .and_then(|attempts| u32::try_from(attempts).ok())
+                .filter(|attempts| *attempts > 0)
+                .unwrap_or(defaults.max_attempts),
+            initial_backoff: read("WEBHOOK_INITIAL_BACKOFF_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.initial_backoff),
+            ..defaults
+        }
+    }
+
+    // Doubles after every failed attempt, up to the maximum backoff
+    pub fn backoff(&self, failed_attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failed_attempts.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    policy: RetryPolicy,
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        WebhookDispatcher::with_policy(RetryPolicy::default())
+    }
+}
+
+impl WebhookDispatcher {
+    pub fn with_policy(policy: RetryPolicy) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .expect("cannot build webhooks HTTP client");
+
+        WebhookDispatcher { client, policy }
+    }
+
+    pub fn from_env() -> Self {
+        WebhookDispatcher::with_policy(RetryPolicy::from_env())
+    }
+
+    // Redelivers in the background, outside of the outbox and its ordering
+    pub fn dispatch(self: &Arc<Self>, repos: SharedState, webhook_id: Uuid, delivery_id: Uuid, event: LedgerEvent) {
+        let dispatcher = self.clone();
+        tokio::spawn(async move { dispatcher.deliver(&repos, webhook_id, delivery_id, event).await });
+    }
+
+    async fn attempt(
+        &self,
+        webhook: &Webhook,
+        delivery_id: Uuid,
+        event: &LedgerEvent,
+    ) -> Result<u16, (Option<u16>, String)> {
+        let body = serde_json::to_vec(event).expect("Cannot serialize ledger event");
+        let timestamp = Utc::now().timestamp();
+
+        let response = self
+            .client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.payload.name())
+            .header(DELIVERY_HEADER, delivery_id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, sign_payload(&webhook.secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await
+            .map_err(|error| (None, error.to_string()))?;
+
+        let status = response.status().as_u16();
+
+        match response.status().is_success() {
+            true => Ok(status),
+            false => Err((Some(status), format!("Endpoint answered {status}"))),
+        }
+    }
+
+    // Attempts a delivery once and logs the outcome. Deleted webhooks stop receiving
+    // deliveries, even pending retries, so there is nothing left to deliver to them
+    async fn attempt_logged(
+        &self,
+        repos: &Repositories,
+        webhook_id: Uuid,
+        delivery_id: Uuid,
+        attempt: u32,
+        event: &LedgerEvent,
+    ) -> Result<(), String> {
+        let Some(webhook) = crate::read_lock(&repos.webhooks).find(&webhook_id).cloned() else {
+            return Ok(());
+        };
+
+        let outcome = self.attempt(&webhook, delivery_id, event).await;
+
+        let (status_code, error) = match outcome {
+            Ok(status) => (Some(status), None),
+            Err((status, error)) => (status, Some(error)),
+        };
+
+        crate::write_lock(&repos.webhooks).record_attempt(
+            webhook_id,
+            DeliveryAttempt {
+                delivery_id,
+                event_sequence: event.sequence,
+                event_type: event.payload.name().to_string(),
+                attempt,
+                attempted_at: Utc::now(),
+                status_code,
+                error: error.clone(),
+                delivered: error.is_none(),
+            },
+        );
+
+        match error {
+            None => {
+                tracing::debug!(%webhook_id, %delivery_id, attempt, "Webhook delivered");
+                Ok(())
+            },
+            Some(error) => {
+                tracing::debug!(%webhook_id, %delivery_id, attempt, %error, "Webhook delivery failed");
+                Err(error)
+            },
+        }
+    }
+
+    fn dead_letter(
+        &self,
+        repos: &Repositories,
+        webhook_id: Uuid,
+        delivery_id: Uuid,
+        event: &LedgerEvent,
+        last_error: String,
+    ) {
+        tracing::warn!(%webhook_id, %delivery_id, sequence = event.sequence, "Webhook delivery dead-lettered");
+
+        crate::write_lock(&repos.webhooks).dead_letter(DeadLetter {
+            delivery_id,
+            webhook_id,
+            event: event.clone(),
+            attempts: self.policy.max_attempts,
+            last_error,
+            dead_lettered_at: Utc::now(),
+        });
+    }
+
+    // Attempts a pending delivery once its backoff is over. Gives the delivery back while it
+    // still has to be retried, and nothing once delivered or dead-lettered.
+    pub async fn deliver_due(
+        &self,
+        repos: &Repositories,
+        mut delivery: PendingDelivery,
+        event: &LedgerEvent,
+    ) -> Option<PendingDelivery> {
+        if delivery.retry_at > Utc::now() {
+            return Some(delivery);
+        }
+
+        delivery.attempts += 1;
+
+        let error = self
+            .attempt_logged(
+                repos,
+                delivery.webhook_id,
+                delivery.delivery_id,
+                delivery.attempts,
+                event,
+            )
+            .await
+            .err()?;
+
+        if delivery.attempts >= self.policy.max_attempts {
+            self.dead_letter(repos, delivery.webhook_id, delivery.delivery_id, event, error);
+            return None;
+        }
+
+        let backoff =
+            chrono::Duration::from_std(self.policy.backoff(delivery.attempts)).unwrap_or(chrono::Duration::MAX);
+        delivery.retry_at = Utc::now()
+            .checked_add_signed(backoff)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        delivery.last_error = Some(error);
+        Some(delivery)
+    }
+
+    async fn deliver(&self, repos: &Repositories, webhook_id: Uuid, delivery_id: Uuid, event: LedgerEvent) {
+        let mut last_error = String::new();
+
+        for attempt in 1..=self.policy.max_attempts {
+            match self
+                .attempt_logged(repos, webhook_id, delivery_id, attempt, &event)
+                .await
+            {
+                Ok(()) => return,
+                Err(error) => last_error = error,
+            }
+
+            if attempt < self.policy.max_attempts {
+                tokio::time::sleep(self.policy.backoff(attempt)).await;
+            }
+        }
+
+        self.dead_letter(repos, webhook_id, delivery_id, &event, last_error);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookPath {
+    webhook_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeadLetterPath {
+    webhook_id: Uuid,
+    delivery_id: Uuid,
+}
+
+fn validate(payload: &CreateNewWebhook) -> Result<(), ApiError> {
+    let valid_url = reqwest::Url::parse(&payload.url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+
+    if !valid_url {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Invalid webhook URL {}", payload.url),
+        ));
+    }
+
+    if payload.event_types.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "At least one event type is required",
+        ));
+    }
+
+    if let Some(unknown) = payload
+        .event_types
+        .iter()
+        .find(|event_type| !EVENT_TYPES.contains(&event_type.as_str()))
+    {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Unknown event type {unknown}"),
+        ));
+    }
+
+    if payload.secret.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "Webhook secret cannot be empty"));
+    }
+
+    Ok(())
+}
+
+pub async fn new_webhook(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Json(payload): Json<CreateNewWebhook>,
+) -> Result<Json<Webhook>, ApiError> {
+    validate(&payload)?;
+
+    let webhook = Webhook {
+        webhook_id: Uuid::new_v4(),
+        ledger_id,
+        url: payload.url,
+        event_types: payload.event_types,
+        created_at: Utc::now(),
+        secret: payload.secret,
+    };
+
+    crate::write_lock(&state.webhooks).save_webhook(webhook.clone());
+
+    tracing::info!(webhook_id = %webhook.webhook_id, url = %webhook.url, "Webhook created");
+    Ok(Json(webhook))
+}
+
+pub async fn list_webhooks(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+) -> Json<Vec<Webhook>> {
+    Json(crate::read_lock(&state.webhooks).list(&ledger_id))
+}
+
+fn not_found(webhook_id: &Uuid) -> ApiError {
+    tracing::debug!(%webhook_id, "Webhook not found");
+    ApiError::not_found(format!("Webhook {webhook_id} not found"))
+}
+
+pub async fn webhook_details(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(WebhookPath { webhook_id }): Path<WebhookPath>,
+) -> Result<Json<Webhook>, ApiError> {
+    crate::read_lock(&state.webhooks)
+        .fetch_webhook(&ledger_id, &webhook_id)
+        .map(Json)
+        .ok_or_else(|| not_found(&webhook_id))
+}
+
+pub async fn delete_webhook(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(WebhookPath { webhook_id }): Path<WebhookPath>,
+) -> Result<StatusCode, ApiError> {
+    if !crate::write_lock(&state.webhooks).delete(&ledger_id, &webhook_id) {
+        return Err(not_found(&webhook_id));
+    }
+
+    tracing::info!(%webhook_id, "Webhook deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn webhook_deliveries(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(WebhookPath { webhook_id }): Path<WebhookPath>,
+) -> Result<Json<Vec<DeliveryAttempt>>, ApiError> {
+    let webhooks = crate::read_lock(&state.webhooks);
+
+    if webhooks.fetch_webhook(&ledger_id, &webhook_id).is_none() {
+        return Err(not_found(&webhook_id));
+    }
+
+    Ok(Json(webhooks.attempts(&webhook_id)))
+}
+
+pub async fn webhook_dead_letters(
+    State(state): State<SharedState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(WebhookPath { webhook_id }): Path<WebhookPath>,
+) -> Result<Json<Vec<DeadLetter>>, ApiError> {
+    let webhooks = crate::read_lock(&state.webhooks);
+
+    if webhooks.fetch_webhook(&ledger_id, &webhook_id).is_none() {
+        return Err(not_found(&webhook_id));
+    }
+
+    Ok(Json(webhooks.dead_letters(&webhook_id)))
+}
+
+pub async fn redeliver_dead_letter(
+    State(state): State<AppState>,
+    LedgerScope(ledger_id): LedgerScope,
+    Path(DeadLetterPath {
+        webhook_id,
+        delivery_id,
+    }): Path<DeadLetterPath>,
+) -> Result<StatusCode, ApiError> {
+    let dead_letter = {
+        let mut webhooks = crate::write_lock(&state.repos.webhooks);
+
+        if webhooks.fetch_webhook(&ledger_id, &webhook_id).is_none() {
+            return Err(not_found(&webhook_id));
+        }
+
+        webhooks.take_dead_letter(&webhook_id, &delivery_id)
+    };
+
+    let Some(dead_letter) = dead_letter else {
+        tracing::debug!(%webhook_id, %delivery_id, "Dead letter not found");
+        return Err(ApiError::not_found(format!(
+            "No dead letter for delivery {delivery_id}"
+        )));
+    };
+
+    tracing::info!(%webhook_id, %delivery_id, "Redelivering dead letter");
+    state
+        .webhooks
+        .dispatch(state.repos.clone(), webhook_id, delivery_id, dead_letter.event);
+
+    Ok(StatusCode::ACCEPTED)
+}